@@ -0,0 +1,561 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::error::HitoError;
+use crate::requests::{run_blocking, CancelToken, Request};
+use crate::{list_annotators, load_hito_config};
+
+/// Labels given by one rater (a data file or an annotator's view): image path -> category IDs.
+pub(crate) struct Rater {
+    pub(crate) name: String,
+    pub(crate) labels: HashMap<String, BTreeSet<String>>,
+    pub(crate) batch_images: Option<HashSet<String>>, // Images of the rater's task batch, if any
+}
+
+impl Rater {
+    /// Categories this rater assigned to `image_path` (empty if the image is unlabelled).
+    pub(crate) fn categories(&self, image_path: &str) -> BTreeSet<String> {
+        self.labels.get(image_path).cloned().unwrap_or_default()
+    }
+
+    /// Whether the rater saw `image_path`: a rater with a task batch saw the images of the batch
+    /// and those they labelled; a rater without one saw every image of the folder.
+    fn has_seen(&self, image_path: &str) -> bool {
+        match &self.batch_images {
+            Some(images) => {
+                images.contains(image_path)
+                    || self.labels.get(image_path).is_some_and(|categories| !categories.is_empty())
+            }
+            None => true,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CategoryAgreement {
+    category_id: String,
+    percent_agreement: f64,
+    cohens_kappa: Option<f64>, // Only computed for exactly two raters
+    fleiss_kappa: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct ConfusionMatrix {
+    labels: Vec<String>, // Label sets, e.g. "cat+dog" or "uncategorized"
+    counts: Vec<Vec<usize>>, // counts[row][col], summed over every rater pair (earlier rater on rows)
+}
+
+#[derive(Serialize)]
+pub struct DisputedImage {
+    image_path: String,
+    labels: Vec<(String, Vec<String>)>, // rater -> categories
+}
+
+#[derive(Serialize)]
+pub struct AgreementReport {
+    raters: Vec<String>,
+    image_count: usize,
+    excluded_image_count: usize, // Labelled images some rater never saw, left out of the statistics
+    percent_agreement: f64,
+    cohens_kappa: Option<f64>, // Only computed for exactly two raters
+    fleiss_kappa: Option<f64>,
+    categories: Vec<CategoryAgreement>,
+    confusion_matrix: ConfusionMatrix,
+    disputed_images: Vec<DisputedImage>,
+}
+
+/// Load raters either from several data files or from annotator views of a single data file.
+///
+/// With `filenames`, each file is one rater (using its merged view). Otherwise each entry of
+/// `annotators` is one rater from `filename`, defaulting to every annotator found in the file.
+/// The `batch_images` of a task batch file are the images its raters were given. `cancel` is
+/// checked before each rater is loaded.
+pub(crate) fn load_raters(
+    directory: &str,
+    filenames: Option<Vec<String>>,
+    filename: Option<String>,
+    annotators: Option<Vec<String>>,
+    cancel: &CancelToken,
+) -> Result<Vec<Rater>, HitoError> {
+    let to_labels = |image_categories: Vec<(String, Vec<crate::CategoryAssignment>)>| {
+        image_categories
+            .into_iter()
+            .map(|(path, assignments)| {
+                let categories = assignments.into_iter().map(|a| a.category_id).collect();
+                (path, categories)
+            })
+            .collect::<HashMap<String, BTreeSet<String>>>()
+    };
+
+    let raters = match filenames {
+        Some(filenames) if !filenames.is_empty() => filenames
+            .into_iter()
+            .map(|name| {
+                cancel.check()?;
+                let data = load_hito_config(directory.to_string(), Some(name.clone()), None)?;
+                Ok(Rater {
+                    name,
+                    labels: to_labels(data.image_categories),
+                    batch_images: data.batch_images.map(HashSet::from_iter),
+                })
+            })
+            .collect::<Result<Vec<_>, HitoError>>()?,
+        _ => {
+            let annotators = match annotators {
+                Some(annotators) if !annotators.is_empty() => annotators,
+                _ => list_annotators(directory.to_string(), filename.clone())?,
+            };
+            annotators
+                .into_iter()
+                .map(|annotator| {
+                    cancel.check()?;
+                    let data = load_hito_config(directory.to_string(), filename.clone(), Some(annotator.clone()))?;
+                    Ok(Rater {
+                        name: annotator,
+                        labels: to_labels(data.image_categories),
+                        batch_images: data.batch_images.map(HashSet::from_iter),
+                    })
+                })
+                .collect::<Result<Vec<_>, HitoError>>()?
+        }
+    };
+
+    if raters.len() < 2 {
+        return Err(HitoError::invalid_input(format!("At least two raters are required, found {}", raters.len())));
+    }
+
+    Ok(raters)
+}
+
+/// Images to compare, sorted by path, and the number of labelled images left out.
+///
+/// These are the images labelled by at least one rater; unless `include_unseen` is set, only those
+/// every rater saw (see `Rater::has_seen`), so images outside a rater's batch do not count as
+/// disagreements. Raters without a batch leaving a labelled image empty do count.
+pub(crate) fn rated_images(raters: &[Rater], include_unseen: bool) -> (Vec<String>, usize) {
+    let labelled: BTreeSet<&String> = raters
+        .iter()
+        .flat_map(|rater| rater.labels.iter())
+        .filter(|(_, categories)| !categories.is_empty())
+        .map(|(path, _)| path)
+        .collect();
+    let total = labelled.len();
+    let images: Vec<String> = labelled
+        .into_iter()
+        .filter(|path| include_unseen || raters.iter().all(|rater| rater.has_seen(path)))
+        .cloned()
+        .collect();
+    let excluded = total - images.len();
+    (images, excluded)
+}
+
+/// Name used for a set of categories in the confusion matrix.
+fn label_key(categories: &BTreeSet<String>) -> String {
+    if categories.is_empty() {
+        "uncategorized".to_string()
+    } else {
+        categories.iter().cloned().collect::<Vec<_>>().join("+")
+    }
+}
+
+/// Cohen's kappa for two raters. `ratings` holds one (rater A, rater B) class pair per item.
+///
+/// Returns `None` when kappa is undefined (chance agreement of 1 with imperfect observed agreement,
+/// or no items at all).
+fn cohens_kappa(ratings: &[(String, String)]) -> Option<f64> {
+    if ratings.is_empty() {
+        return None;
+    }
+
+    let n = ratings.len() as f64;
+    let observed = ratings.iter().filter(|(a, b)| a == b).count() as f64 / n;
+
+    let mut marginals: HashMap<&str, (f64, f64)> = HashMap::new();
+    for (a, b) in ratings {
+        marginals.entry(a).or_default().0 += 1.0;
+        marginals.entry(b).or_default().1 += 1.0;
+    }
+    let expected: f64 = marginals.values().map(|(a, b)| (a / n) * (b / n)).sum();
+
+    kappa(observed, expected)
+}
+
+/// Fleiss' kappa. `counts[i][j]` is how many raters put item `i` in class `j`; every item must
+/// have been rated by the same number of raters.
+fn fleiss_kappa(counts: &[Vec<usize>], rater_count: usize) -> Option<f64> {
+    if counts.is_empty() || rater_count < 2 {
+        return None;
+    }
+
+    let items = counts.len() as f64;
+    let raters = rater_count as f64;
+    let class_count = counts[0].len();
+
+    let observed = counts
+        .iter()
+        .map(|row| {
+            let pairs: f64 = row.iter().map(|&c| (c * c.saturating_sub(1)) as f64).sum();
+            pairs / (raters * (raters - 1.0))
+        })
+        .sum::<f64>()
+        / items;
+
+    let expected = (0..class_count)
+        .map(|j| {
+            let proportion = counts.iter().map(|row| row[j] as f64).sum::<f64>() / (items * raters);
+            proportion * proportion
+        })
+        .sum::<f64>();
+
+    kappa(observed, expected)
+}
+
+fn kappa(observed: f64, expected: f64) -> Option<f64> {
+    if (1.0 - expected).abs() < f64::EPSILON {
+        // Every rating fell in a single class: agreement is perfect but kappa is undefined
+        return if (1.0 - observed).abs() < f64::EPSILON { Some(1.0) } else { None };
+    }
+    Some((observed - expected) / (1.0 - expected))
+}
+
+/// Compute agreement statistics for a set of raters (see `rated_images` for `include_unseen`).
+pub(crate) fn agreement_report(raters: &[Rater], include_unseen: bool) -> AgreementReport {
+    let (images, excluded_image_count) = rated_images(raters, include_unseen);
+    let rater_count = raters.len();
+
+    // Per-image label sets, one entry per rater
+    let image_labels: Vec<Vec<BTreeSet<String>>> = images
+        .iter()
+        .map(|path| raters.iter().map(|rater| rater.categories(path)).collect())
+        .collect();
+
+    let categories: BTreeSet<String> = image_labels
+        .iter()
+        .flat_map(|labels| labels.iter().flatten().cloned())
+        .collect();
+
+    let all_agree = |labels: &[BTreeSet<String>]| labels.windows(2).all(|w| w[0] == w[1]);
+    let percent_of = |count: usize| {
+        if images.is_empty() { 1.0 } else { count as f64 / images.len() as f64 }
+    };
+
+    // Per-category agreement treats each category as an independent yes/no decision
+    let category_agreement = categories
+        .iter()
+        .map(|category_id| {
+            let decisions: Vec<Vec<bool>> = image_labels
+                .iter()
+                .map(|labels| labels.iter().map(|set| set.contains(category_id)).collect())
+                .collect();
+
+            let agreeing = decisions.iter().filter(|d| d.windows(2).all(|w| w[0] == w[1])).count();
+            let counts: Vec<Vec<usize>> = decisions
+                .iter()
+                .map(|d| {
+                    let yes = d.iter().filter(|&&v| v).count();
+                    vec![yes, rater_count - yes]
+                })
+                .collect();
+            let cohens = (rater_count == 2).then(|| {
+                let pairs: Vec<(String, String)> = decisions
+                    .iter()
+                    .map(|d| (d[0].to_string(), d[1].to_string()))
+                    .collect();
+                cohens_kappa(&pairs)
+            });
+
+            CategoryAgreement {
+                category_id: category_id.clone(),
+                percent_agreement: percent_of(agreeing),
+                cohens_kappa: cohens.flatten(),
+                fleiss_kappa: fleiss_kappa(&counts, rater_count),
+            }
+        })
+        .collect();
+
+    // Overall agreement treats each distinct label set as one nominal class
+    let keys: Vec<Vec<String>> = image_labels
+        .iter()
+        .map(|labels| labels.iter().map(label_key).collect())
+        .collect();
+    let classes: Vec<String> = keys.iter().flatten().cloned().collect::<BTreeSet<_>>().into_iter().collect();
+    let class_index: BTreeMap<&str, usize> = classes.iter().enumerate().map(|(i, c)| (c.as_str(), i)).collect();
+
+    let class_counts: Vec<Vec<usize>> = keys
+        .iter()
+        .map(|row| {
+            let mut counts = vec![0; classes.len()];
+            for key in row {
+                counts[class_index[key.as_str()]] += 1;
+            }
+            counts
+        })
+        .collect();
+
+    let mut matrix = vec![vec![0; classes.len()]; classes.len()];
+    for row in &keys {
+        for i in 0..row.len() {
+            for j in (i + 1)..row.len() {
+                matrix[class_index[row[i].as_str()]][class_index[row[j].as_str()]] += 1;
+            }
+        }
+    }
+
+    let cohens = if rater_count == 2 {
+        let pairs: Vec<(String, String)> = keys.iter().map(|row| (row[0].clone(), row[1].clone())).collect();
+        cohens_kappa(&pairs)
+    } else {
+        None
+    };
+
+    let disputed_images: Vec<DisputedImage> = images
+        .iter()
+        .zip(&image_labels)
+        .filter(|(_, labels)| !all_agree(labels))
+        .map(|(path, labels)| DisputedImage {
+            image_path: path.clone(),
+            labels: raters
+                .iter()
+                .zip(labels)
+                .map(|(rater, set)| (rater.name.clone(), set.iter().cloned().collect()))
+                .collect(),
+        })
+        .collect();
+
+    AgreementReport {
+        raters: raters.iter().map(|r| r.name.clone()).collect(),
+        image_count: images.len(),
+        excluded_image_count,
+        percent_agreement: percent_of(images.len() - disputed_images.len()),
+        cohens_kappa: cohens,
+        fleiss_kappa: fleiss_kappa(&class_counts, rater_count),
+        categories: category_agreement,
+        confusion_matrix: ConfusionMatrix { labels: classes, counts: matrix },
+        disputed_images,
+    }
+}
+
+/// Compute inter-annotator agreement for a directory.
+///
+/// Raters are either several data files (`filenames`, e.g. one `.hito.json` per annotator) or
+/// annotator views of one data file (`filename` + `annotators`, defaulting to every annotator in
+/// the file). Every image labelled by some rater is compared, and leaving it unlabelled counts as
+/// assigning no categories to it. Raters of a task batch (see `create_task_batches`) only saw their
+/// batch, so images outside it are left out unless `include_unseen` is set, in which case such a
+/// rater counts as leaving them unlabelled.
+///
+/// # Returns
+///
+/// Overall and per-category Cohen's kappa (two raters) and Fleiss' kappa, a confusion matrix
+/// over label sets, the images the raters disagree on (for adjudication), and the number of
+/// images left out.
+///
+/// Runs on the worker pool, as it reads every rater's data file; a `request_id` makes it
+/// cancellable.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref()), err(Debug))]
+pub async fn compute_agreement(
+    directory: String,
+    filenames: Option<Vec<String>>,
+    filename: Option<String>,
+    annotators: Option<Vec<String>>,
+    include_unseen: Option<bool>,
+    request_id: Option<String>,
+) -> Result<AgreementReport, HitoError> {
+    let cancel = Request::start(request_id);
+    run_blocking(move || {
+        let raters = load_raters(&directory, filenames, filename, annotators, cancel.token())?;
+        Ok(agreement_report(&raters, include_unseen.unwrap_or(false)))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{save_hito_config, CategoryAssignment};
+    use crate::scope::granted_temp_dir;
+    use tauri::async_runtime::block_on;
+
+    fn rater(name: &str, labels: &[(&str, &[&str])]) -> Rater {
+        Rater {
+            name: name.to_string(),
+            labels: labels
+                .iter()
+                .map(|(path, categories)| {
+                    (path.to_string(), categories.iter().map(|c| c.to_string()).collect())
+                })
+                .collect(),
+            batch_images: None,
+        }
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("kappa should be defined");
+        assert!((actual - expected).abs() < 1e-9, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn test_cohens_kappa_textbook_example() {
+        // 50 items: both yes 20, A yes/B no 5, A no/B yes 10, both no 15 -> kappa = 0.4
+        let mut ratings = Vec::new();
+        ratings.extend(std::iter::repeat_n(("yes".to_string(), "yes".to_string()), 20));
+        ratings.extend(std::iter::repeat_n(("yes".to_string(), "no".to_string()), 5));
+        ratings.extend(std::iter::repeat_n(("no".to_string(), "yes".to_string()), 10));
+        ratings.extend(std::iter::repeat_n(("no".to_string(), "no".to_string()), 15));
+
+        assert_close(cohens_kappa(&ratings), 0.4);
+    }
+
+    #[test]
+    fn test_fleiss_kappa_perfect_and_undefined() {
+        // Perfect agreement across two classes
+        let counts = vec![vec![3, 0], vec![0, 3]];
+        assert_close(fleiss_kappa(&counts, 3), 1.0);
+
+        // No variation at all: everybody agrees, kappa reported as 1
+        let counts = vec![vec![3, 0], vec![3, 0]];
+        assert_close(fleiss_kappa(&counts, 3), 1.0);
+
+        assert!(fleiss_kappa(&[], 3).is_none());
+    }
+
+    #[test]
+    fn test_agreement_report_two_raters() {
+        let raters = vec![
+            rater("alice", &[("/a.jpg", &["cat"]), ("/b.jpg", &["dog"]), ("/c.jpg", &["cat"])]),
+            rater("bob", &[("/a.jpg", &["cat"]), ("/b.jpg", &["cat"]), ("/c.jpg", &["cat"])]),
+        ];
+
+        let report = agreement_report(&raters, false);
+
+        assert_eq!(report.image_count, 3);
+        assert!((report.percent_agreement - 2.0 / 3.0).abs() < 1e-9);
+        assert!(report.cohens_kappa.is_some());
+        assert_eq!(report.disputed_images.len(), 1);
+        assert_eq!(report.disputed_images[0].image_path, "/b.jpg");
+
+        let cat = report.categories.iter().find(|c| c.category_id == "cat").unwrap();
+        assert!((cat.percent_agreement - 2.0 / 3.0).abs() < 1e-9);
+
+        // Rows are alice's labels, columns bob's
+        let labels = &report.confusion_matrix.labels;
+        let cat_index = labels.iter().position(|l| l == "cat").unwrap();
+        let dog_index = labels.iter().position(|l| l == "dog").unwrap();
+        assert_eq!(report.confusion_matrix.counts[cat_index][cat_index], 2);
+        assert_eq!(report.confusion_matrix.counts[dog_index][cat_index], 1);
+    }
+
+    #[test]
+    fn test_agreement_report_counts_missing_labels_as_uncategorized() {
+        let raters = vec![
+            rater("alice", &[("/a.jpg", &["cat"])]),
+            rater("bob", &[]),
+            rater("carol", &[("/a.jpg", &["cat"])]),
+        ];
+
+        let report = agreement_report(&raters, true);
+
+        assert_eq!(report.image_count, 1);
+        assert_eq!(report.excluded_image_count, 0);
+        assert!(report.cohens_kappa.is_none());
+        assert_eq!(report.disputed_images.len(), 1);
+        assert!(report.confusion_matrix.labels.contains(&"uncategorized".to_string()));
+    }
+
+    #[test]
+    fn test_agreement_report_only_scores_images_every_rater_saw() {
+        let mut alice = rater("alice", &[("/a.jpg", &["cat"]), ("/b.jpg", &["dog"])]);
+        let mut bob = rater("bob", &[("/c.jpg", &["dog"])]);
+        // Batches overlapping on /a.jpg, which bob left unlabelled
+        alice.batch_images = Some(["/a.jpg", "/b.jpg"].into_iter().map(String::from).collect());
+        bob.batch_images = Some(["/a.jpg", "/c.jpg"].into_iter().map(String::from).collect());
+        let raters = vec![alice, bob];
+
+        let report = agreement_report(&raters, false);
+        assert_eq!(report.image_count, 1);
+        assert_eq!(report.excluded_image_count, 2);
+        assert_eq!(report.disputed_images.len(), 1);
+        assert_eq!(report.disputed_images[0].image_path, "/a.jpg");
+
+        // Without batches every rater saw the whole folder, so leaving an image empty disagrees
+        let raters = vec![
+            rater("alice", &[("/a.jpg", &["cat"]), ("/b.jpg", &["dog"])]),
+            rater("bob", &[("/a.jpg", &["cat"])]),
+        ];
+        for include_unseen in [false, true] {
+            let report = agreement_report(&raters, include_unseen);
+            assert_eq!(report.image_count, 2);
+            assert_eq!(report.excluded_image_count, 0);
+            assert_eq!(report.disputed_images.len(), 1);
+            assert_eq!(report.disputed_images[0].image_path, "/b.jpg");
+        }
+
+        // A batch only restricts its own rater
+        let mut bob = rater("bob", &[("/a.jpg", &["cat"])]);
+        bob.batch_images = Some(["/a.jpg"].into_iter().map(String::from).collect());
+        let raters = vec![rater("alice", &[("/a.jpg", &["cat"]), ("/b.jpg", &["dog"])]), bob];
+        let report = agreement_report(&raters, false);
+        assert_eq!(report.image_count, 1);
+        assert_eq!(report.excluded_image_count, 1);
+        assert_close(report.cohens_kappa, 1.0);
+    }
+
+    #[test]
+    fn test_compute_agreement_from_files_and_annotators() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        let assignment = |category_id: &str| CategoryAssignment {
+            category_id: category_id.to_string(),
+            assigned_at: "2024-01-01T00:00:00Z".to_string(),
+            ..Default::default()
+        };
+
+        for (file, category) in [("alice.json", "cat"), ("bob.json", "dog")] {
+            save_hito_config(
+                directory.clone(),
+                vec![("/a.jpg".to_string(), vec![assignment(category)])],
+                Some(file.to_string()),
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        }
+
+        let report = block_on(compute_agreement(
+            directory.clone(),
+            Some(vec!["alice.json".to_string(), "bob.json".to_string()]),
+            None,
+            None,
+            None,
+            None,
+        ))
+        .unwrap();
+        assert_eq!(report.raters, vec!["alice.json", "bob.json"]);
+        assert_eq!(report.disputed_images.len(), 1);
+
+        // Annotator views of a single file
+        for annotator in ["alice", "bob"] {
+            save_hito_config(
+                directory.clone(),
+                vec![("/a.jpg".to_string(), vec![assignment("cat")])],
+                None,
+                None,
+                None,
+                Some(annotator.to_string()),
+            )
+            .unwrap();
+        }
+
+        let report = block_on(compute_agreement(directory.clone(), None, None, None, None, None)).unwrap();
+        assert_eq!(report.raters, vec!["alice", "bob"]);
+        assert!(report.disputed_images.is_empty());
+        assert_close(report.fleiss_kappa, 1.0);
+
+        // A single rater is not enough
+        let result = block_on(compute_agreement(directory, Some(vec!["alice.json".to_string()]), None, None, None, None));
+        assert!(result.err().unwrap().message.contains("At least two raters"));
+    }
+}
@@ -0,0 +1,371 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{ErrorCode, HitoError};
+use crate::random::SplitMix64;
+use crate::requests::{run_blocking, Request};
+use crate::{create_hito_file, get_hito_file_path, load_hito_config, read_directory, HitoFile};
+
+#[derive(Serialize)]
+pub struct TaskBatch {
+    filename: String,
+    path: String,
+    image_count: usize,
+}
+
+#[derive(Serialize)]
+pub struct TaskBatchReport {
+    batches: Vec<TaskBatch>,
+    overlap_images: Vec<String>, // Images included in every batch
+}
+
+/// How `create_task_batches` splits the images.
+#[derive(Deserialize)]
+pub struct BatchOptions {
+    mode: String, // "count", "random" or "stratified"
+    seed: Option<u64>, // Shuffle seed, default 0
+    overlap: Option<usize>, // Images shared by every batch, default 0
+}
+
+/// Key used to group images for stratified batching ("uncategorized" or sorted category IDs).
+fn stratum_key(categories: &[String]) -> String {
+    if categories.is_empty() {
+        return "uncategorized".to_string();
+    }
+    let mut categories = categories.to_vec();
+    categories.sort();
+    categories.dedup();
+    categories.join("+")
+}
+
+/// Order images so that every prefix and every round-robin share is spread evenly over the strata.
+///
+/// Each stratum is shuffled, then the strata are interleaved by relative position.
+fn stratified_order(
+    images: Vec<String>,
+    labels: &HashMap<String, Vec<String>>,
+    rng: &mut SplitMix64,
+) -> Vec<String> {
+    let mut strata: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for image in images {
+        let key = stratum_key(labels.get(&image).map(Vec::as_slice).unwrap_or_default());
+        strata.entry(key).or_default().push(image);
+    }
+
+    let mut positioned = Vec::new();
+    for mut members in strata.into_values() {
+        rng.shuffle(&mut members);
+        let len = members.len() as f64;
+        positioned.extend(
+            members
+                .into_iter()
+                .enumerate()
+                .map(|(i, image)| ((i as f64 + 0.5) / len, image)),
+        );
+    }
+    positioned.sort_by(|a, b| a.0.total_cmp(&b.0));
+    positioned.into_iter().map(|(_, image)| image).collect()
+}
+
+/// Split images into `batch_count` batches, with the first `overlap` images shared by all of them.
+fn partition(
+    images: Vec<String>,
+    mode: &str,
+    batch_count: usize,
+    overlap: usize,
+    seed: u64,
+    labels: &HashMap<String, Vec<String>>,
+) -> Result<(Vec<String>, Vec<Vec<String>>), HitoError> {
+    if batch_count == 0 {
+        return Err(HitoError::invalid_input("Batch count must be at least 1"));
+    }
+    if batch_count > images.len() {
+        return Err(HitoError::invalid_input(format!(
+            "Batch count {} exceeds the number of images ({})",
+            batch_count,
+            images.len()
+        )));
+    }
+    if overlap > images.len() {
+        return Err(HitoError::invalid_input(format!("Overlap {} exceeds the number of images ({})", overlap, images.len())));
+    }
+
+    let mut rng = SplitMix64::new(seed);
+    let mut ordered = match mode {
+        "count" => images,
+        "random" => {
+            let mut images = images;
+            rng.shuffle(&mut images);
+            images
+        }
+        "stratified" => stratified_order(images, labels, &mut rng),
+        other => return Err(HitoError::invalid_input(format!("Unknown batching mode: {}", other))),
+    };
+
+    let rest = ordered.split_off(overlap);
+    let shared = ordered;
+    let mut batches = vec![Vec::new(); batch_count];
+
+    if mode == "stratified" {
+        // Dealing round-robin keeps each batch's label mix close to the whole directory's
+        for (i, image) in rest.into_iter().enumerate() {
+            batches[i % batch_count].push(image);
+        }
+    } else {
+        // Contiguous chunks whose sizes differ by at most one
+        let base = rest.len() / batch_count;
+        let extra = rest.len() % batch_count;
+        let mut rest = rest.into_iter();
+        for (i, batch) in batches.iter_mut().enumerate() {
+            let size = base + usize::from(i < extra);
+            batch.extend(rest.by_ref().take(size));
+        }
+    }
+
+    for batch in &mut batches {
+        batch.splice(0..0, shared.iter().cloned());
+    }
+
+    Ok((shared, batches))
+}
+
+/// Partition the images of a directory into work packages for annotators.
+///
+/// `options.mode` is one of:
+/// - `"count"`: consecutive runs of images in path order
+/// - `"random"`: shuffled with `seed` (default 0) before splitting
+/// - `"stratified"`: balanced by the existing labels in `filename` (default `.hito.json`),
+///   shuffled within each label group with `seed`
+///
+/// The first `overlap` images (default 0) of the chosen order are put into every batch so agreement
+/// can be measured on them. Each batch is written to `<output_prefix>-<n>.json` (default prefix
+/// `.hito-batch`) in `directory`, with the categories, hotkeys and attributes of the source data
+/// file and its image subset in `batch_images`. Opening that file with `list_images` restricts the
+/// view to the batch. There can be at most one batch per image. Batch files are never overwritten:
+/// if any of them exists already, nothing is written and the command fails with a `conflict` error.
+/// If writing a batch fails, the batch files written before it are removed again.
+///
+/// Runs on the worker pool, as it lists the directory; a `request_id` makes it cancellable until
+/// the batch files are written.
+///
+/// # Returns
+///
+/// A `TaskBatchReport` with the written files and the shared overlap images.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref(), output_prefix = output_prefix.as_deref()), err(Debug))]
+pub async fn create_task_batches(
+    directory: String,
+    batch_count: usize,
+    options: BatchOptions,
+    filename: Option<String>,
+    output_prefix: Option<String>,
+    request_id: Option<String>,
+) -> Result<TaskBatchReport, HitoError> {
+    let cancel = Request::start(request_id);
+    run_blocking(move || {
+        let output_prefix = output_prefix.unwrap_or_else(|| ".hito-batch".to_string());
+        if output_prefix.is_empty() || output_prefix.contains(['/', '\\']) {
+            return Err(HitoError::invalid_input(format!("Invalid batch file prefix: {}", output_prefix)));
+        }
+
+        let images: Vec<String> = read_directory(directory.clone(), None, cancel.token())?
+            .images
+            .into_iter()
+            .map(|image| image.path)
+            .collect();
+
+        let source = load_hito_config(directory.clone(), filename, None)?;
+        let labels: HashMap<String, Vec<String>> = source
+            .image_categories
+            .iter()
+            .map(|(path, assignments)| (path.clone(), assignments.iter().map(|a| a.category_id.clone()).collect()))
+            .collect();
+
+        // Validates `batch_count` against the images before any path is built for it
+        let (overlap_images, batches) = partition(
+            images,
+            &options.mode,
+            batch_count,
+            options.overlap.unwrap_or(0),
+            options.seed.unwrap_or(0),
+            &labels,
+        )?;
+
+        // Writing is not interrupted, so a cancelled request leaves no batch files behind
+        cancel.token().check()?;
+
+        let targets = (1..=batch_count)
+            .map(|n| {
+                let filename = format!("{}-{}.json", output_prefix, n);
+                let path = get_hito_file_path(&directory, Some(&filename))?;
+                Ok((filename, path))
+            })
+            .collect::<Result<Vec<_>, HitoError>>()?;
+        if let Some((filename, path)) = targets.iter().find(|(_, path)| path.exists()) {
+            return Err(HitoError::new(ErrorCode::Conflict, format!("Batch file already exists: {}", filename))
+                .with_path(path));
+        }
+
+        let mut written: Vec<TaskBatch> = Vec::new();
+        for ((filename, path), batch_images) in targets.into_iter().zip(batches) {
+            let image_count = batch_images.len();
+
+            let data = HitoFile {
+                categories: source.categories.clone(),
+                hotkeys: source.hotkeys.clone(),
+                attributes: source.attributes.clone(),
+                batch_images: Some(batch_images),
+                ..Default::default()
+            };
+            if let Err(e) = create_hito_file(&path, &data) {
+                // A partial set of batches would leave some images unassigned
+                for batch in &written {
+                    if let Err(e) = fs::remove_file(&batch.path) {
+                        tracing::warn!(path = %batch.path, error = ?e, "Failed to remove batch file");
+                    }
+                }
+                return Err(e);
+            }
+
+            written.push(TaskBatch {
+                filename,
+                path: path.to_string_lossy().to_string(),
+                image_count,
+            });
+        }
+
+        Ok(TaskBatchReport {
+            batches: written,
+            overlap_images,
+        })
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scope::granted_temp_dir;
+    use std::fs;
+    use crate::{list_directory, read_hito_file};
+    use tauri::async_runtime::block_on;
+
+    fn count(overlap: Option<usize>) -> BatchOptions {
+        BatchOptions { mode: "count".to_string(), seed: None, overlap }
+    }
+
+    fn images(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("/img{:02}.jpg", i)).collect()
+    }
+
+    #[test]
+    fn test_partition_by_count_with_overlap() {
+        let (shared, batches) = partition(images(10), "count", 3, 1, 0, &HashMap::new()).unwrap();
+
+        assert_eq!(shared, vec!["/img00.jpg"]);
+        let sizes: Vec<usize> = batches.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![4, 4, 4]);
+        assert_eq!(batches[0][1], "/img01.jpg");
+        assert!(batches.iter().all(|b| b[0] == "/img00.jpg"));
+    }
+
+    #[test]
+    fn test_partition_random_is_seeded() {
+        let run = |seed| partition(images(20), "random", 2, 0, seed, &HashMap::new()).unwrap().1;
+
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+
+        let mut all: Vec<String> = run(7).concat();
+        all.sort();
+        assert_eq!(all, images(20));
+    }
+
+    #[test]
+    fn test_partition_stratified_balances_labels() {
+        let mut labels = HashMap::new();
+        for image in images(12).iter().take(4) {
+            labels.insert(image.clone(), vec!["cat".to_string()]);
+        }
+
+        let (_, batches) = partition(images(12), "stratified", 2, 0, 3, &labels).unwrap();
+
+        for batch in &batches {
+            assert_eq!(batch.len(), 6);
+            assert_eq!(batch.iter().filter(|image| labels.contains_key(*image)).count(), 2);
+        }
+    }
+
+    #[test]
+    fn test_partition_rejects_invalid_input() {
+        assert!(partition(images(3), "count", 0, 0, 0, &HashMap::new()).is_err());
+        assert!(partition(images(3), "count", 2, 4, 0, &HashMap::new()).is_err());
+        assert!(partition(images(3), "alphabet", 2, 0, 0, &HashMap::new()).is_err());
+        // Huge counts fail before anything is allocated for them
+        assert!(partition(images(3), "count", 4, 0, 0, &HashMap::new()).is_err());
+        assert!(partition(images(3), "count", usize::MAX, 0, 0, &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_create_task_batches_writes_restricted_views() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        for i in 0..5 {
+            fs::write(temp_dir.path().join(format!("img{}.png", i)), b"fake").unwrap();
+        }
+
+        let report = block_on(create_task_batches(directory.clone(), 2, count(Some(1)), None, None, None)).unwrap();
+
+        assert_eq!(report.batches.len(), 2);
+        assert_eq!(report.batches[0].filename, ".hito-batch-1.json");
+        assert_eq!(report.batches[0].image_count, 3);
+        assert_eq!(report.overlap_images.len(), 1);
+
+        let batch = read_hito_file(&temp_dir.path().join(".hito-batch-2.json")).unwrap();
+        assert_eq!(batch.batch_images.as_ref().unwrap().len(), 3);
+
+        let view = block_on(list_directory(directory.clone(), Some(report.batches[1].path.clone()), None, None)).unwrap();
+        let paths: Vec<String> = view.images.into_iter().map(|image| image.path).collect();
+        let mut expected = batch.batch_images.unwrap();
+        expected.sort();
+        assert_eq!(paths, expected);
+
+        // A corrupt batch file does not fail the listing
+        fs::write(&report.batches[1].path, "{ not json").unwrap();
+        let view = block_on(list_directory(directory.clone(), Some(report.batches[1].path.clone()), None, None)).unwrap();
+        assert_eq!(view.images.len(), 5);
+
+        assert!(block_on(create_task_batches(directory, 2, count(None), None, Some("../x".to_string()), None)).is_err());
+    }
+
+    #[test]
+    fn test_create_task_batches_never_overwrites_batch_files() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        for i in 0..4 {
+            fs::write(temp_dir.path().join(format!("img{}.png", i)), b"fake").unwrap();
+        }
+
+        block_on(create_task_batches(directory.clone(), 2, count(None), None, None, None)).unwrap();
+        let first = temp_dir.path().join(".hito-batch-1.json");
+        fs::write(&first, r#"{"image_categories":[["/img0.png",[{"category_id":"cat","assigned_at":"2024-01-01T00:00:00Z"}]]]}"#).unwrap();
+        let labelled = fs::read_to_string(&first).unwrap();
+
+        // Running again, even with fewer batches, leaves the existing files alone
+        for batch_count in [2, 1] {
+            let error = block_on(create_task_batches(directory.clone(), batch_count, count(None), None, None, None)).err().unwrap();
+            assert_eq!(error.code, ErrorCode::Conflict);
+        }
+        assert_eq!(fs::read_to_string(&first).unwrap(), labelled);
+
+        // A larger run conflicts on the existing files and writes none of the new ones
+        let error = block_on(create_task_batches(directory.clone(), 3, count(None), None, None, None)).err().unwrap();
+        assert_eq!(error.code, ErrorCode::Conflict);
+        assert!(!temp_dir.path().join(".hito-batch-3.json").exists());
+
+        block_on(create_task_batches(directory, 2, count(None), None, Some("round2".to_string()), None)).unwrap();
+        assert!(temp_dir.path().join("round2-2.json").exists());
+    }
+}
@@ -0,0 +1,445 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::error::{ErrorCode, HitoError};
+use crate::requests::{run_blocking, Request};
+use crate::{
+    create_hito_file, get_hito_file_path, load_hito_config, AttributeValue, CategoryAssignment, HitoFile, ImageNote, ReviewItem,
+};
+
+#[derive(Serialize)]
+pub struct MergeReport {
+    output_path: String,
+    image_count: usize,
+    review: Vec<ReviewItem>,
+}
+
+/// How per-rater votes for one category on one image are turned into a decision.
+enum Strategy {
+    Majority,
+    Unanimous,
+    Weighted(HashMap<String, f64>),
+    Union,
+}
+
+/// Outcome of a vote on a single category.
+#[derive(PartialEq, Debug)]
+enum Decision {
+    Keep,
+    Drop,
+    Tie,
+}
+
+impl Strategy {
+    fn parse(strategy: &str, weights: Option<HashMap<String, f64>>) -> Result<Self, HitoError> {
+        match strategy {
+            "majority" => Ok(Strategy::Majority),
+            "unanimous" => Ok(Strategy::Unanimous),
+            "weighted" => {
+                let weights = weights.unwrap_or_default();
+                if let Some((name, weight)) = weights.iter().find(|(_, w)| !w.is_finite() || **w < 0.0) {
+                    return Err(HitoError::invalid_input(format!("Invalid weight for {}: {}", name, weight)));
+                }
+                Ok(Strategy::Weighted(weights))
+            }
+            "union" => Ok(Strategy::Union),
+            other => Err(HitoError::invalid_input(format!("Unknown merge strategy: {}", other))),
+        }
+    }
+
+    /// Decide on a category given the raters that voted for it out of all `raters`.
+    fn decide(&self, voters: &[&str], raters: &[String]) -> Decision {
+        let compare = |yes: f64, total: f64| {
+            let half = total / 2.0;
+            if (yes - half).abs() < 1e-9 {
+                Decision::Tie
+            } else if yes > half {
+                Decision::Keep
+            } else {
+                Decision::Drop
+            }
+        };
+
+        match self {
+            Strategy::Majority => compare(voters.len() as f64, raters.len() as f64),
+            Strategy::Unanimous => {
+                if voters.len() == raters.len() { Decision::Keep } else { Decision::Drop }
+            }
+            Strategy::Weighted(weights) => {
+                let weight = |name: &str| weights.get(name).copied().unwrap_or(1.0);
+                let yes: f64 = voters.iter().map(|name| weight(name)).sum();
+                let total: f64 = raters.iter().map(|name| weight(name)).sum();
+                if total == 0.0 { Decision::Drop } else { compare(yes, total) }
+            }
+            Strategy::Union => Decision::Keep,
+        }
+    }
+}
+
+/// Merge the labels of several data files into one set of assignments.
+///
+/// Returns the merged `image_categories` and the images that need manual review.
+fn merge_files(
+    files: &[(String, HitoFile)],
+    strategy: &Strategy,
+) -> (Vec<(String, Vec<CategoryAssignment>)>, Vec<ReviewItem>) {
+    let raters: Vec<String> = files.iter().map(|(name, _)| name.clone()).collect();
+
+    // image -> category -> (voters, latest assignment)
+    let mut votes: BTreeMap<String, BTreeMap<String, (Vec<&str>, &CategoryAssignment)>> = BTreeMap::new();
+    for (name, file) in files {
+        for (path, assignments) in &file.image_categories {
+            for assignment in assignments {
+                let entry = votes
+                    .entry(path.clone())
+                    .or_default()
+                    .entry(assignment.category_id.clone())
+                    .or_insert_with(|| (Vec::new(), assignment));
+                if !entry.0.contains(&name.as_str()) {
+                    entry.0.push(name.as_str());
+                }
+                if assignment.assigned_at > entry.1.assigned_at {
+                    entry.1 = assignment;
+                }
+            }
+        }
+    }
+
+    let mut image_categories = Vec::new();
+    let mut review = Vec::new();
+    for (path, categories) in votes {
+        let mut kept = Vec::new();
+        let mut tied = Vec::new();
+        let mut dropped = Vec::new();
+
+        for (category_id, (voters, latest)) in categories {
+            match strategy.decide(&voters, &raters) {
+                Decision::Keep => kept.push(CategoryAssignment {
+                    category_id,
+                    assigned_at: latest.assigned_at.clone(),
+                    regions: None, // Regions are one rater's, there is no vote on them
+                    annotator: None,
+                }),
+                Decision::Tie => tied.push(category_id),
+                Decision::Drop => dropped.push(category_id),
+            }
+        }
+
+        if !tied.is_empty() {
+            review.push(ReviewItem {
+                image_path: path.clone(),
+                category_ids: tied,
+                reason: "tie".to_string(),
+            });
+        } else if matches!(strategy, Strategy::Unanimous) && !dropped.is_empty() {
+            review.push(ReviewItem {
+                image_path: path.clone(),
+                category_ids: dropped,
+                reason: "disagreement".to_string(),
+            });
+        }
+
+        if !kept.is_empty() {
+            image_categories.push((path, kept));
+        }
+    }
+
+    (image_categories, review)
+}
+
+/// Collect the notes of every file, per image in order of first appearance.
+fn merge_notes(files: &mut [(String, HitoFile)]) -> Option<Vec<(String, Vec<ImageNote>)>> {
+    let mut merged: Vec<(String, Vec<ImageNote>)> = Vec::new();
+    for (path, notes) in files.iter_mut().flat_map(|(_, f)| f.image_notes.take().unwrap_or_default()) {
+        match merged.iter_mut().find(|(p, _)| *p == path) {
+            Some((_, entries)) => entries.extend(notes),
+            None => merged.push((path, notes)),
+        }
+    }
+    if merged.is_empty() { None } else { Some(merged) }
+}
+
+/// Collect the image attributes of every file; the first file with a value for an attribute wins.
+fn merge_image_attributes(files: &mut [(String, HitoFile)]) -> Option<Vec<(String, Vec<AttributeValue>)>> {
+    let mut merged: Vec<(String, Vec<AttributeValue>)> = Vec::new();
+    for (path, values) in files.iter_mut().flat_map(|(_, f)| f.image_attributes.take().unwrap_or_default()) {
+        let index = match merged.iter().position(|(p, _)| *p == path) {
+            Some(index) => index,
+            None => {
+                merged.push((path, Vec::new()));
+                merged.len() - 1
+            }
+        };
+        let entries = &mut merged[index].1;
+        for value in values {
+            if !entries.iter().any(|v| v.attribute_id == value.attribute_id) {
+                entries.push(value);
+            }
+        }
+    }
+    if merged.is_empty() { None } else { Some(merged) }
+}
+
+/// Merge several label files for the same directory into a new data file.
+///
+/// Each file in `filenames` is one rater (e.g. one `.hito.json` per annotator). `strategy` is one of:
+/// - `"majority"`: keep a category when more than half of the raters assigned it
+/// - `"unanimous"`: keep a category only when every rater assigned it
+/// - `"weighted"`: like majority, but each rater's vote counts with its weight in `weights`
+///   (keyed by filename, default 1.0)
+/// - `"union"`: keep every category any rater assigned
+///
+/// Categories with exactly half of the (weighted) votes are left out and the image is listed for
+/// manual review in the new file's `review` section, as are images with dropped categories when
+/// merging unanimously. Categories and hotkeys are taken from the first file that has them.
+///
+/// Notes of all files are kept, and each image attribute takes its value from the first file that
+/// has one. Regions are dropped, as there is no vote on them. The output file must not exist yet:
+/// an existing file fails with a `conflict` error and is never overwritten. Each input file may be
+/// listed only once.
+///
+/// Runs on the worker pool, as it reads every input file; a `request_id` makes it cancellable
+/// until the output file is written.
+///
+/// # Returns
+///
+/// A `MergeReport` with the path of the written file and the review list.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filenames = filenames.len(), output_filename = %output_filename), err(Debug))]
+pub async fn merge_label_files(
+    directory: String,
+    filenames: Vec<String>,
+    strategy: String,
+    weights: Option<HashMap<String, f64>>,
+    output_filename: String,
+    request_id: Option<String>,
+) -> Result<MergeReport, HitoError> {
+    let cancel = Request::start(request_id);
+    run_blocking(move || {
+        let strategy = Strategy::parse(&strategy, weights)?;
+
+        if filenames.len() < 2 {
+            return Err(HitoError::invalid_input(format!("At least two label files are required, found {}", filenames.len())));
+        }
+        if filenames.contains(&output_filename) {
+            return Err(HitoError::invalid_input(format!("Output file must not be one of the input files: {}", output_filename)));
+        }
+        // The same file listed twice would vote twice
+        let mut input_paths = HashSet::new();
+        for name in &filenames {
+            if !input_paths.insert(get_hito_file_path(&directory, Some(name))?) {
+                return Err(HitoError::invalid_field("filenames", format!("Label file is listed more than once: {}", name)));
+            }
+        }
+
+        // Fail before loading the inputs; the file is created atomically below in case it appears meanwhile
+        let output_path = get_hito_file_path(&directory, Some(&output_filename))?;
+        if output_path.exists() {
+            return Err(HitoError::new(ErrorCode::Conflict, format!("Output file already exists: {}", output_filename))
+                .with_path(&output_path));
+        }
+
+        let mut files = filenames
+            .into_iter()
+            .map(|name| {
+                cancel.token().check()?;
+                let data = load_hito_config(directory.clone(), Some(name.clone()), None)?;
+                Ok((name, data))
+            })
+            .collect::<Result<Vec<_>, HitoError>>()?;
+
+        let (image_categories, review) = merge_files(&files, &strategy);
+
+        let merged = HitoFile {
+            image_categories,
+            categories: files.iter_mut().find_map(|(_, f)| f.categories.take()),
+            hotkeys: files.iter_mut().find_map(|(_, f)| f.hotkeys.take()),
+            attributes: files.iter_mut().find_map(|(_, f)| f.attributes.take()),
+            image_notes: merge_notes(&mut files),
+            image_attributes: merge_image_attributes(&mut files),
+            review: if review.is_empty() { None } else { Some(review.clone()) },
+            ..Default::default()
+        };
+
+        cancel.token().check()?;
+        create_hito_file(&output_path, &merged)?;
+
+        Ok(MergeReport {
+            output_path: output_path.to_string_lossy().to_string(),
+            image_count: merged.image_categories.len(),
+            review,
+        })
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{read_hito_file, save_hito_config, Geometry, Region};
+    use crate::scope::granted_temp_dir;
+    use tauri::async_runtime::block_on;
+
+    fn file(name: &str, labels: &[(&str, &[&str])]) -> (String, HitoFile) {
+        let image_categories = labels
+            .iter()
+            .map(|(path, categories)| {
+                let assignments = categories
+                    .iter()
+                    .map(|c| CategoryAssignment {
+                        category_id: c.to_string(),
+                        assigned_at: "2024-01-01T00:00:00Z".to_string(),
+                        ..Default::default()
+                    })
+                    .collect();
+                (path.to_string(), assignments)
+            })
+            .collect();
+        (name.to_string(), HitoFile { image_categories, ..Default::default() })
+    }
+
+    fn categories_of<'a>(merged: &'a [(String, Vec<CategoryAssignment>)], path: &str) -> Vec<&'a str> {
+        merged
+            .iter()
+            .find(|(p, _)| p == path)
+            .map(|(_, a)| a.iter().map(|a| a.category_id.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_majority_marks_ties_for_review() {
+        let files = vec![
+            file("alice", &[("/a.jpg", &["cat"]), ("/b.jpg", &["dog"])]),
+            file("bob", &[("/a.jpg", &["cat"]), ("/b.jpg", &["cat"])]),
+            file("carol", &[("/a.jpg", &["dog"])]),
+        ];
+
+        let (merged, review) = merge_files(&files, &Strategy::Majority);
+        assert_eq!(categories_of(&merged, "/a.jpg"), vec!["cat"]);
+        assert!(categories_of(&merged, "/b.jpg").is_empty());
+        assert!(review.is_empty());
+
+        // Two raters split evenly on /b.jpg
+        let (merged, review) = merge_files(&files[..2], &Strategy::Majority);
+        assert!(categories_of(&merged, "/b.jpg").is_empty());
+        assert_eq!(review.len(), 1);
+        assert_eq!(review[0].image_path, "/b.jpg");
+        assert_eq!(review[0].category_ids, vec!["cat", "dog"]);
+        assert_eq!(review[0].reason, "tie");
+    }
+
+    #[test]
+    fn test_unanimous_union_and_weighted() {
+        let files = vec![
+            file("alice", &[("/a.jpg", &["cat", "dog"])]),
+            file("bob", &[("/a.jpg", &["cat"])]),
+        ];
+
+        let (merged, review) = merge_files(&files, &Strategy::Unanimous);
+        assert_eq!(categories_of(&merged, "/a.jpg"), vec!["cat"]);
+        assert_eq!(review[0].category_ids, vec!["dog"]);
+        assert_eq!(review[0].reason, "disagreement");
+
+        let (merged, review) = merge_files(&files, &Strategy::Union);
+        assert_eq!(categories_of(&merged, "/a.jpg"), vec!["cat", "dog"]);
+        assert!(review.is_empty());
+
+        let weights = HashMap::from([("alice".to_string(), 2.0)]);
+        let strategy = Strategy::parse("weighted", Some(weights)).unwrap();
+        let (merged, review) = merge_files(&files, &strategy);
+        assert_eq!(categories_of(&merged, "/a.jpg"), vec!["cat", "dog"]);
+        assert!(review.is_empty());
+    }
+
+    #[test]
+    fn test_merged_assignments_have_no_regions() {
+        let mut files = vec![
+            file("alice", &[("/a.jpg", &["cat"])]),
+            file("bob", &[("/a.jpg", &["cat"])]),
+        ];
+        files[0].1.image_categories[0].1[0].regions = Some(vec![Region {
+            id: "region_1".to_string(),
+            geometry: Geometry::Point { x: 0.5, y: 0.5 },
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+        }]);
+        files[0].1.image_categories[0].1[0].assigned_at = "2024-02-01T00:00:00Z".to_string();
+
+        let (merged, _) = merge_files(&files, &Strategy::Majority);
+        assert_eq!(categories_of(&merged, "/a.jpg"), vec!["cat"]);
+        assert!(merged[0].1[0].regions.is_none());
+        assert_eq!(merged[0].1[0].assigned_at, "2024-02-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_strategy_and_bad_weights() {
+        assert!(Strategy::parse("coinflip", None).is_err());
+        let weights = HashMap::from([("alice".to_string(), -1.0)]);
+        assert!(Strategy::parse("weighted", Some(weights)).is_err());
+        assert_eq!(Strategy::Union.decide(&[], &["a".to_string()]), Decision::Keep);
+    }
+
+    #[test]
+    fn test_merge_label_files_writes_new_file() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+
+        for (name, categories) in [("alice.json", vec!["cat"]), ("bob.json", vec!["dog"])] {
+            let (_, data) = file(name, &[("/a.jpg", &categories)]);
+            save_hito_config(directory.clone(), data.image_categories, Some(name.to_string()), None, None, None)
+                .unwrap();
+        }
+        let filenames = vec!["alice.json".to_string(), "bob.json".to_string()];
+        let merge = |filenames: &[String], strategy: &str, output_filename: &str| {
+            block_on(merge_label_files(
+                directory.clone(),
+                filenames.to_vec(),
+                strategy.to_string(),
+                None,
+                output_filename.to_string(),
+                None,
+            ))
+        };
+
+        assert!(merge(&filenames, "majority", "alice.json").is_err());
+
+        let report = merge(&filenames, "majority", "merged.json").unwrap();
+        assert_eq!(report.image_count, 0);
+        assert_eq!(report.review.len(), 1);
+
+        let merged = read_hito_file(&temp_dir.path().join("merged.json")).unwrap();
+        assert!(merged.image_categories.is_empty());
+        assert_eq!(merged.review.unwrap()[0].image_path, "/a.jpg");
+
+        // An existing file is never overwritten
+        let error = merge(&filenames, "union", "merged.json").err().unwrap();
+        assert_eq!(error.code, ErrorCode::Conflict);
+
+        // A file listed twice would outvote the others
+        let duplicated = vec!["alice.json".to_string(), "bob.json".to_string(), "alice.json".to_string()];
+        let error = merge(&duplicated, "majority", "twice.json").err().unwrap();
+        assert_eq!(error.field.as_deref(), Some("filenames"));
+        assert!(!temp_dir.path().join("twice.json").exists());
+    }
+
+    #[test]
+    fn test_merge_keeps_notes_and_first_attribute_values() {
+        let note = |text: &str| ImageNote { text: text.to_string(), author: None, created_at: "2024-01-01T00:00:00Z".to_string() };
+        let value = |value: f64| AttributeValue {
+            attribute_id: "rating".to_string(),
+            value,
+            assigned_at: "2024-01-01T00:00:00Z".to_string(),
+        };
+        let mut files = vec![file("alice", &[]), file("bob", &[])];
+        files[0].1.image_notes = Some(vec![("/a.jpg".to_string(), vec![note("blurry")])]);
+        files[1].1.image_notes = Some(vec![("/a.jpg".to_string(), vec![note("dark")])]);
+        files[0].1.image_attributes = Some(vec![("/a.jpg".to_string(), vec![value(4.0)])]);
+        files[1].1.image_attributes = Some(vec![("/a.jpg".to_string(), vec![value(2.0)])]);
+
+        let notes = merge_notes(&mut files).unwrap();
+        assert_eq!(notes[0].1.iter().map(|n| n.text.as_str()).collect::<Vec<_>>(), vec!["blurry", "dark"]);
+        let attributes = merge_image_attributes(&mut files).unwrap();
+        assert_eq!(attributes[0].1.len(), 1);
+        assert_eq!(attributes[0].1[0].value, 4.0);
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+/// Read a time without a zone as local time, as the user sees it.
+///
+/// A time repeated by a DST change takes its first occurrence; one skipped by it, the first time after.
+fn from_local(naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| Local.from_local_datetime(&(naive + Duration::hours(1))).earliest())
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Start of a day in local time.
+fn local_midnight(date: NaiveDate) -> Option<DateTime<Utc>> {
+    from_local(date.and_hms_opt(0, 0, 0)?)
+}
+
+/// Parse a filter date: RFC 3339, `YYYY-MM-DDTHH:MM:SS` or `YYYY-MM-DD` (the last two in local time).
+pub(crate) fn parse_date(value: &str) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    let invalid = || format!("Invalid date: {}", value);
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S") {
+        return from_local(date).ok_or_else(invalid);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return local_midnight(date).ok_or_else(invalid);
+    }
+    Err(invalid())
+}
+
+/// Parse the upper bound of a filter range, with whether it is inclusive.
+///
+/// A date-only value covers that whole local day, so the range ends (exclusively) at the next
+/// local midnight. Any other value ends the range at that time, inclusively when `inclusive` is set.
+pub(crate) fn parse_end_date(value: &str, inclusive: bool) -> Result<(DateTime<Utc>, bool), String> {
+    let value = value.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let next_midnight = date.succ_opt().and_then(local_midnight).ok_or_else(|| format!("Invalid date: {}", value))?;
+        return Ok((next_midnight, false));
+    }
+    Ok((parse_date(value)?, inclusive))
+}
+
+/// Parse a relative duration such as `90s`, `30m`, `2h`, `7d` or `2w`.
+pub(crate) fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let invalid = || format!("Invalid duration: {} (expected e.g. 30m, 2h or 7d)", value);
+    let split = value.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (number, unit) = value.split_at(split);
+    let number: i64 = number.parse().map_err(|_| invalid())?;
+
+    match unit {
+        "s" => Duration::try_seconds(number),
+        "m" => Duration::try_minutes(number),
+        "h" => Duration::try_hours(number),
+        "d" => Duration::try_days(number),
+        "w" => Duration::try_weeks(number),
+        _ => None,
+    }
+    .ok_or_else(invalid)
+}
+
+/// Parse an RFC 3339 timestamp stored on an image or assignment.
+pub(crate) fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).ok().map(|date| date.with_timezone(&Utc))
+}
+
+/// Capture time read from a file, with the file's modification time when it was read
+type CachedCaptureTime = (SystemTime, Option<String>);
+
+/// Most capture times kept; the cache is emptied when it is full, e.g. after browsing many folders
+const MAX_CAPTURE_TIMES: usize = 50_000;
+
+/// Capture times read so far, keyed by path
+static CAPTURE_TIMES: OnceLock<Mutex<HashMap<PathBuf, CachedCaptureTime>>> = OnceLock::new();
+
+/// Get the capture time of an image (see `read_capture_time`), reading the EXIF data only when
+/// the file changed since it was last read.
+pub(crate) fn capture_time(image_path: &str) -> Option<String> {
+    let modified = fs::metadata(image_path).and_then(|metadata| metadata.modified()).ok()?;
+    let cache = CAPTURE_TIMES.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some((read_at, captured)) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(Path::new(image_path)) {
+        if *read_at == modified {
+            return captured.clone();
+        }
+    }
+
+    let captured = read_capture_time(image_path);
+    let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+    if cache.len() >= MAX_CAPTURE_TIMES {
+        cache.clear();
+    }
+    cache.insert(PathBuf::from(image_path), (modified, captured.clone()));
+    captured
+}
+
+/// Read the EXIF capture time (`DateTimeOriginal`, falling back to `DateTime`) of an image.
+///
+/// EXIF times have no zone unless `OffsetTimeOriginal` is present; without it they are read as
+/// local time, like the camera clock usually is.
+fn read_capture_time(image_path: &str) -> Option<String> {
+    let file = File::open(Path::new(image_path)).ok()?;
+    let exif = exif::Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()?;
+
+    let ascii = |tag: exif::Tag| match exif.get_field(tag, exif::In::PRIMARY).map(|f| &f.value) {
+        Some(exif::Value::Ascii(values)) => values.first().cloned(),
+        _ => None,
+    };
+
+    let mut captured = ascii(exif::Tag::DateTimeOriginal)
+        .or_else(|| ascii(exif::Tag::DateTime))
+        .and_then(|data| exif::DateTime::from_ascii(&data).ok())?;
+    if let Some(offset) = ascii(exif::Tag::OffsetTimeOriginal) {
+        let _ = captured.parse_offset(&offset);
+    }
+
+    let naive = NaiveDate::from_ymd_opt(captured.year.into(), captured.month.into(), captured.day.into())?
+        .and_hms_opt(captured.hour.into(), captured.minute.into(), captured.second.into())?;
+    let date = match captured.offset {
+        Some(minutes) => FixedOffset::east_opt(i32::from(minutes) * 60)?
+            .from_local_datetime(&naive)
+            .single()?
+            .with_timezone(&Utc),
+        None => from_local(naive)?,
+    };
+    Some(date.to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date_formats() {
+        let expected = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        assert_eq!(parse_date("2024-03-01T12:00:00Z").unwrap(), expected);
+        assert_eq!(parse_date("2024-03-01T13:00:00+01:00").unwrap(), expected);
+        // Values without a zone are local time
+        let local = |hour| Local.with_ymd_and_hms(2024, 3, 1, hour, 0, 0).unwrap().with_timezone(&Utc);
+        assert_eq!(parse_date("2024-03-01T12:00:00").unwrap(), local(12));
+        assert_eq!(parse_date("2024-03-01").unwrap(), local(0));
+        assert!(parse_date("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_date_only_end_covers_whole_day() {
+        let next_midnight = Local.with_ymd_and_hms(2024, 3, 2, 0, 0, 0).unwrap().with_timezone(&Utc);
+        assert_eq!(parse_end_date("2024-03-01", true).unwrap(), (next_midnight, false));
+        assert_eq!(parse_end_date("2024-03-01", false).unwrap(), (next_midnight, false));
+
+        let noon = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        assert_eq!(parse_end_date("2024-03-01T12:00:00Z", true).unwrap(), (noon, true));
+        assert_eq!(parse_end_date("2024-03-01T12:00:00Z", false).unwrap(), (noon, false));
+        assert!(parse_end_date("9999-99-99", true).is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("2h").unwrap(), Duration::hours(2));
+        assert_eq!(parse_duration("7d").unwrap(), Duration::days(7));
+        assert_eq!(parse_duration("90s").unwrap(), Duration::seconds(90));
+        assert!(parse_duration("2").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("2y").is_err());
+    }
+
+    #[test]
+    fn test_read_capture_time_without_exif() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("plain.jpg");
+        std::fs::write(&path, b"not really a jpeg").unwrap();
+
+        assert_eq!(read_capture_time(path.to_str().unwrap()), None);
+        assert_eq!(read_capture_time("/nonexistent/image.jpg"), None);
+
+        // The result is cached until the file changes
+        assert_eq!(capture_time(path.to_str().unwrap()), None);
+        let cached = CAPTURE_TIMES.get().unwrap().lock().unwrap();
+        assert!(cached.contains_key(&path));
+        assert_eq!(capture_time("/nonexistent/image.jpg"), None);
+    }
+}
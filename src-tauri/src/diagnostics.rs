@@ -0,0 +1,325 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::error::HitoError;
+use crate::get_app_data_path;
+
+const LOG_FILE_PREFIX: &str = "hito";
+const LOG_FILE_SUFFIX: &str = "log";
+const MAX_LOG_FILES: usize = 7; // One file per day
+const LOG_FILTER_ENV: &str = "HITO_LOG"; // e.g. HITO_LOG=hito_lib=debug
+const DEFAULT_LOG_FILTER: &str = "warn,hito_lib=info";
+const REDACTED: &str = "<redacted>";
+
+#[derive(Serialize)]
+struct VersionInfo {
+    app_version: &'static str,
+    tauri_version: &'static str,
+    os: &'static str,
+    arch: &'static str,
+    exported_at: String,
+}
+
+impl VersionInfo {
+    fn current() -> Self {
+        VersionInfo {
+            app_version: env!("CARGO_PKG_VERSION"),
+            tauri_version: tauri::VERSION,
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            exported_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+fn app_data_dir(app: &AppHandle) -> Result<PathBuf, HitoError> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| HitoError::internal(format!("Failed to get app data directory: {}", e)))
+}
+
+/// Send command spans and events to a daily rotating log file in `<app data>/logs`.
+///
+/// Each command span logs its duration (`time.busy`/`time.idle`) when it closes. The filter can
+/// be overridden with the `HITO_LOG` environment variable.
+///
+/// # Returns
+///
+/// The guard that flushes buffered lines to the file; it has to live as long as the app.
+pub(crate) fn init_logging(app: &AppHandle) -> Result<WorkerGuard, HitoError> {
+    let log_dir = app_data_dir(app)?.join("logs");
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(&log_dir)
+        .map_err(|e| HitoError::internal(format!("Failed to open log file: {}", e)).with_path(&log_dir))?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+
+    let filter = EnvFilter::try_from_env(LOG_FILTER_ENV).unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+    tracing_subscriber::fmt()
+        .with_writer(writer)
+        .with_ansi(false)
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .try_init()
+        .map_err(|e| HitoError::internal(format!("Failed to start logging: {}", e)))?;
+    Ok(guard)
+}
+
+/// Replace a leading `home` in `s` with `~`.
+fn redact_home(s: &str, home: &str) -> String {
+    match s.strip_prefix(home) {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => format!("~{}", rest),
+        _ => s.to_string(),
+    }
+}
+
+/// Replace `home` with `~` wherever it starts a path in the log line `line`.
+///
+/// A match followed by more of a name (`/home/alice2` for home `/home/alice`) is left alone.
+fn redact_home_in_line(line: &str, home: &str) -> String {
+    let mut redacted = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find(home) {
+        let after = &rest[start + home.len()..];
+        let ends_path = after
+            .chars()
+            .next()
+            .is_none_or(|c| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.')));
+        redacted.push_str(&rest[..start]);
+        redacted.push_str(if ends_path { "~" } else { home });
+        rest = after;
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+fn redact_home_in(value: &mut Value, home: &str) {
+    match value {
+        Value::String(s) => *s = redact_home(s, home),
+        Value::Array(items) => items.iter_mut().for_each(|item| redact_home_in(item, home)),
+        Value::Object(map) => {
+            // Directory paths are also used as keys (data_file_paths)
+            *map = std::mem::take(map)
+                .into_iter()
+                .map(|(key, mut item)| {
+                    redact_home_in(&mut item, home);
+                    (redact_home(&key, home), item)
+                })
+                .collect();
+        }
+        _ => {}
+    }
+}
+
+/// Strip personal details from app-config.json: the annotator name and the home directory in paths.
+fn sanitize_app_config(config: &mut Value, home: Option<&str>) {
+    if let Some(annotator) = config.get_mut("annotator").filter(|annotator| !annotator.is_null()) {
+        *annotator = Value::from(REDACTED);
+    }
+    if let Some(home) = home.filter(|home| !home.is_empty()) {
+        redact_home_in(config, home);
+    }
+}
+
+/// Write the diagnostics zip: `logs/*` with the home directory redacted, the sanitized
+/// `app-config.json` (when there is one) and `version.json`.
+///
+/// An app-config.json that cannot be parsed cannot be sanitized, so only a note with the parse
+/// error is included (`app-config.error.txt`).
+fn write_bundle(
+    output_path: &Path,
+    log_dir: &Path,
+    app_config_path: &Path,
+    home: Option<&str>,
+) -> Result<(), HitoError> {
+    let write_error = |e: io::Error| HitoError::io("Failed to write diagnostics bundle", output_path, e);
+    let zip_error = |e: zip::result::ZipError| {
+        HitoError::internal(format!("Failed to write diagnostics bundle: {}", e)).with_path(output_path)
+    };
+    let json_error = |e: serde_json::Error| HitoError::internal(format!("Failed to write diagnostics bundle: {}", e));
+
+    let file = File::create(output_path).map_err(write_error)?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default();
+
+    let mut log_files: Vec<PathBuf> = match fs::read_dir(log_dir) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).filter(|path| path.is_file()).collect(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(HitoError::io("Failed to read log directory", log_dir, e)),
+    };
+    log_files.sort();
+    let home = home.filter(|home| !home.is_empty());
+    for log_file in &log_files {
+        let name = log_file.file_name().unwrap_or_default().to_string_lossy();
+        let content = fs::read(log_file).map_err(|e| HitoError::io("Failed to read log file", log_file, e))?;
+        let content = String::from_utf8_lossy(&content);
+        zip.start_file(format!("logs/{}", name), options).map_err(zip_error)?;
+        for line in content.split_inclusive('\n') {
+            let line = match home {
+                Some(home) => redact_home_in_line(line, home),
+                None => line.to_string(),
+            };
+            zip.write_all(line.as_bytes()).map_err(write_error)?;
+        }
+    }
+
+    match fs::read_to_string(app_config_path) {
+        Ok(content) => match serde_json::from_str::<Value>(&content) {
+            Ok(mut config) => {
+                sanitize_app_config(&mut config, home);
+                zip.start_file("app-config.json", options).map_err(zip_error)?;
+                serde_json::to_writer_pretty(&mut zip, &config).map_err(json_error)?;
+            }
+            Err(e) => {
+                // A broken config is often what the bug report is about, so it must not stop the export
+                zip.start_file("app-config.error.txt", options).map_err(zip_error)?;
+                writeln!(zip, "app-config.json could not be parsed and was left out: {}", e).map_err(write_error)?;
+            }
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(HitoError::io("Failed to read app data", app_config_path, e)),
+    }
+
+    zip.start_file("version.json", options).map_err(zip_error)?;
+    serde_json::to_writer_pretty(&mut zip, &VersionInfo::current()).map_err(json_error)?;
+
+    zip.finish().map_err(zip_error)?;
+    Ok(())
+}
+
+/// Export a zip for bug reports with the logs, the sanitized app-config.json and version info.
+///
+/// The bundle is written to `<app data>/diagnostics`, outside the opened directories.
+///
+/// # Returns
+///
+/// The path of the written zip file.
+#[tauri::command]
+#[tracing::instrument(skip_all, err(Debug))]
+pub fn export_diagnostics(app: AppHandle) -> Result<String, HitoError> {
+    let app_data_dir = app_data_dir(&app)?;
+    let output_dir = app_data_dir.join("diagnostics");
+    fs::create_dir_all(&output_dir).map_err(|e| HitoError::io("Failed to create diagnostics directory", &output_dir, e))?;
+    let output_path = output_dir.join(format!("hito-diagnostics-{}.zip", Utc::now().format("%Y%m%d-%H%M%S")));
+
+    let home = app.path().home_dir().ok().map(|home| home.to_string_lossy().into_owned());
+    write_bundle(&output_path, &app_data_dir.join("logs"), &get_app_data_path(&app)?, home.as_deref())?;
+    tracing::info!(path = %output_path.display(), "Exported diagnostics bundle");
+    Ok(output_path.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Read;
+    use tempfile::TempDir;
+    use zip::ZipArchive;
+
+    #[test]
+    fn test_sanitize_app_config() {
+        let mut config = json!({
+            "categories": [{ "id": "c1", "name": "Cats" }],
+            "annotator": "alice",
+            "data_file_paths": { "/home/alice/photos": "/home/alice/photos/.hito.json" },
+            "search_roots": ["/home/alice/photos", "/home/alice2", "/mnt/shared"],
+        });
+
+        sanitize_app_config(&mut config, Some("/home/alice"));
+
+        assert_eq!(config["annotator"], REDACTED);
+        assert_eq!(config["data_file_paths"]["~/photos"], "~/photos/.hito.json");
+        assert_eq!(config["search_roots"], json!(["~/photos", "/home/alice2", "/mnt/shared"]));
+        assert_eq!(config["categories"][0]["name"], "Cats");
+
+        let mut without_annotator = json!({ "annotator": null });
+        sanitize_app_config(&mut without_annotator, None);
+        assert!(without_annotator["annotator"].is_null());
+    }
+
+    #[test]
+    fn test_write_bundle() {
+        let temp_dir = TempDir::new().unwrap();
+        let log_dir = temp_dir.path().join("logs");
+        fs::create_dir(&log_dir).unwrap();
+        fs::write(log_dir.join("hito.2026-10-17.log"), "list_images: close time.busy=3ms\n").unwrap();
+        let app_config_path = temp_dir.path().join("app-config.json");
+        fs::write(&app_config_path, r#"{"categories":[],"hotkeys":[],"annotator":"alice"}"#).unwrap();
+        let output_path = temp_dir.path().join("bundle.zip");
+
+        write_bundle(&output_path, &log_dir, &app_config_path, None).unwrap();
+
+        let mut archive = ZipArchive::new(File::open(&output_path).unwrap()).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(names, vec!["app-config.json", "logs/hito.2026-10-17.log", "version.json"]);
+
+        let mut config = String::new();
+        archive.by_name("app-config.json").unwrap().read_to_string(&mut config).unwrap();
+        assert!(!config.contains("alice"));
+
+        let mut version = String::new();
+        archive.by_name("version.json").unwrap().read_to_string(&mut version).unwrap();
+        let version: Value = serde_json::from_str(&version).unwrap();
+        assert_eq!(version["app_version"], env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn test_redact_home_in_line() {
+        let line = "INFO list_images{path=/home/alice/photos} close; also /home/alice2/x and \"/home/alice\"";
+        assert_eq!(
+            redact_home_in_line(line, "/home/alice"),
+            "INFO list_images{path=~/photos} close; also /home/alice2/x and \"~\""
+        );
+    }
+
+    #[test]
+    fn test_write_bundle_redacts_logs_and_survives_broken_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let log_dir = temp_dir.path().join("logs");
+        fs::create_dir(&log_dir).unwrap();
+        fs::write(log_dir.join("hito.log"), "load_image{image_path=/home/alice/a.jpg}\nsecond line\n").unwrap();
+        let app_config_path = temp_dir.path().join("app-config.json");
+        fs::write(&app_config_path, r#"{"annotator":"alice","#).unwrap();
+        let output_path = temp_dir.path().join("bundle.zip");
+
+        write_bundle(&output_path, &log_dir, &app_config_path, Some("/home/alice")).unwrap();
+
+        let mut archive = ZipArchive::new(File::open(&output_path).unwrap()).unwrap();
+        let mut log = String::new();
+        archive.by_name("logs/hito.log").unwrap().read_to_string(&mut log).unwrap();
+        assert_eq!(log, "load_image{image_path=~/a.jpg}\nsecond line\n");
+
+        assert!(archive.by_name("app-config.json").is_err());
+        let mut note = String::new();
+        archive.by_name("app-config.error.txt").unwrap().read_to_string(&mut note).unwrap();
+        assert!(note.contains("could not be parsed"));
+        assert!(!note.contains("alice"));
+    }
+
+    #[test]
+    fn test_write_bundle_without_logs_or_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_path = temp_dir.path().join("bundle.zip");
+
+        write_bundle(&output_path, &temp_dir.path().join("logs"), &temp_dir.path().join("app-config.json"), None).unwrap();
+
+        let archive = ZipArchive::new(File::open(&output_path).unwrap()).unwrap();
+        assert_eq!(archive.file_names().collect::<Vec<_>>(), vec!["version.json"]);
+    }
+}
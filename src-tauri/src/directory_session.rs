@@ -0,0 +1,460 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::error::HitoError;
+use crate::requests::{run_blocking, CancelToken, Request};
+use crate::scope;
+use crate::{
+    filter_and_sort_images, get_hito_file_path, load_hito_config, read_directory, ImageLabels, ImagePath, SortOptions,
+};
+
+/// Change counters of data files and image directories, keyed by canonical path (see `mark_changed`)
+static GENERATIONS: OnceLock<Mutex<HashMap<PathBuf, u64>>> = OnceLock::new();
+
+fn generations() -> std::sync::MutexGuard<'static, HashMap<PathBuf, u64>> {
+    GENERATIONS.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Record that a data file was written or that images were added to or removed from a directory,
+/// so sessions over it refresh on their next query.
+pub(crate) fn mark_changed(path: impl AsRef<Path>) {
+    *generations().entry(canonical(path.as_ref())).or_default() += 1;
+}
+
+fn generation(path: &Path) -> u64 {
+    generations().get(path).copied().unwrap_or(0)
+}
+
+/// Listing and label state of an open directory.
+///
+/// Cheap to clone, so queries can work on a copy without holding the lock over every session.
+#[derive(Clone)]
+struct DirectorySession {
+    directory: String,
+    filename: Option<String>,
+    annotator: Option<String>,
+    directory_path: PathBuf, // Canonical, for the change counters
+    data_file_path: PathBuf,
+    images: Arc<Vec<ImagePath>>,
+    labels: Arc<ImageLabels>,
+    listing_generation: u64,
+    labels_generation: u64,
+}
+
+impl DirectorySession {
+    fn load(
+        directory: &str,
+        filename: Option<String>,
+        annotator: Option<String>,
+        cancel: &CancelToken,
+    ) -> Result<Self, HitoError> {
+        let data_file_path = get_hito_file_path(directory, filename.as_deref())?;
+        let mut session = DirectorySession {
+            directory: directory.to_string(),
+            filename,
+            annotator,
+            directory_path: canonical(Path::new(directory)),
+            data_file_path,
+            images: Arc::default(),
+            labels: Arc::default(),
+            listing_generation: 0,
+            labels_generation: 0,
+        };
+        session.relist(cancel)?;
+        session.reload_labels()?;
+        Ok(session)
+    }
+
+    fn relist(&mut self, cancel: &CancelToken) -> Result<(), HitoError> {
+        // Read before listing, so a change made while listing is picked up by the next query
+        let listing_generation = generation(&self.directory_path);
+        let data_file_path = self
+            .data_file_path
+            .to_str()
+            .ok_or_else(|| HitoError::invalid_input("Failed to convert path to string").with_path(&self.data_file_path))?
+            .to_string();
+        self.images = Arc::new(read_directory(self.directory.clone(), Some(data_file_path), cancel)?.images);
+        self.listing_generation = listing_generation;
+        Ok(())
+    }
+
+    fn reload_labels(&mut self) -> Result<(), HitoError> {
+        let labels_generation = generation(&canonical(&self.data_file_path));
+        let data = load_hito_config(self.directory.clone(), self.filename.clone(), self.annotator.clone())?;
+        self.labels = Arc::new(ImageLabels::new(data.image_categories, data.image_notes, data.image_attributes));
+        self.labels_generation = labels_generation;
+        Ok(())
+    }
+
+    /// Relist the images or reload the labels when they changed since they were read.
+    ///
+    /// # Returns
+    ///
+    /// Whether anything was re-read.
+    fn refresh(&mut self, cancel: &CancelToken) -> Result<bool, HitoError> {
+        let mut refreshed = false;
+        if generation(&self.directory_path) != self.listing_generation {
+            self.relist(cancel)?;
+            refreshed = true;
+        }
+        if generation(&canonical(&self.data_file_path)) != self.labels_generation {
+            self.reload_labels()?;
+            refreshed = true;
+        }
+        Ok(refreshed)
+    }
+
+    /// Whether `other` is the same session with a listing and labels at least as recent.
+    fn is_superseded_by(&self, other: &DirectorySession) -> bool {
+        self.data_file_path == other.data_file_path
+            && self.annotator == other.annotator
+            && self.listing_generation <= other.listing_generation
+            && self.labels_generation <= other.labels_generation
+    }
+
+    fn info(&self) -> SessionInfo {
+        SessionInfo {
+            directory: self.directory.clone(),
+            image_count: self.images.len(),
+            labelled_count: self.labels.categories.values().filter(|a| !a.is_empty()).count(),
+        }
+    }
+}
+
+/// Open directory sessions keyed by directory path, managed as Tauri state.
+///
+/// Sessions let the frontend filter and sort large folders by sending only the options, instead
+/// of the full image and label lists on every change. Label saves, moves and deletes mark the data
+/// file or directory as changed, and the session refreshes on its next query.
+///
+/// The map is only locked to look up or store a session; listing, loading labels and filtering
+/// run on the worker pool without it, so a slow folder does not hold up queries on the others.
+#[derive(Default, Clone)]
+pub struct DirectorySessions {
+    sessions: Arc<Mutex<HashMap<String, DirectorySession>>>,
+}
+
+#[derive(Serialize)]
+pub struct SessionInfo {
+    directory: String,
+    image_count: usize,
+    labelled_count: usize,
+}
+
+/// Filter and sort options of a session query (see `sort_images`), plus the page to return.
+#[derive(Deserialize, Default)]
+pub struct SessionQuery {
+    #[serde(flatten)]
+    options: SortOptions,
+    offset: Option<usize>, // Default 0
+    limit: Option<usize>, // Default: everything after `offset`
+}
+
+#[derive(Serialize)]
+pub struct SessionPage {
+    total: usize, // Number of images matching the filters
+    offset: usize,
+    images: Vec<ImagePath>,
+}
+
+impl DirectorySessions {
+    fn open(
+        &self,
+        directory: &str,
+        filename: Option<String>,
+        annotator: Option<String>,
+        cancel: &CancelToken,
+    ) -> Result<SessionInfo, HitoError> {
+        let session = DirectorySession::load(directory, filename, annotator, cancel)?;
+        let info = session.info();
+        self.lock()?.insert(directory.to_string(), session);
+        Ok(info)
+    }
+
+    fn reload(&self, directory: &str, cancel: &CancelToken) -> Result<SessionInfo, HitoError> {
+        let (filename, annotator) = {
+            let sessions = self.lock()?;
+            let session = sessions.get(directory).ok_or_else(|| no_session(directory))?;
+            (session.filename.clone(), session.annotator.clone())
+        };
+        self.open(directory, filename, annotator, cancel)
+    }
+
+    fn close(&self, directory: &str) -> Result<bool, HitoError> {
+        Ok(self.lock()?.remove(directory).is_some())
+    }
+
+    /// Get a copy of the session of `directory`, refreshed if its images or labels changed.
+    ///
+    /// A refreshed copy replaces the stored session, unless that was reopened or refreshed meanwhile.
+    fn current(&self, directory: &str, cancel: &CancelToken) -> Result<DirectorySession, HitoError> {
+        let mut session = self.lock()?.get(directory).cloned().ok_or_else(|| no_session(directory))?;
+        if session.refresh(cancel)? {
+            if let Some(stored) = self.lock()?.get_mut(directory).filter(|stored| stored.is_superseded_by(&session)) {
+                *stored = session.clone();
+            }
+        }
+        Ok(session)
+    }
+
+    /// Run `sort_images` over the session's images and labels, refreshing them first if needed.
+    ///
+    /// # Returns
+    ///
+    /// The session the query ran on and the matching images.
+    fn matching(
+        &self,
+        directory: &str,
+        query: SessionQuery,
+        cancel: &CancelToken,
+    ) -> Result<(DirectorySession, Vec<ImagePath>), HitoError> {
+        let session = self.current(directory, cancel)?;
+        let images = filter_and_sort_images(&session.images, &session.labels, query.options, cancel)?;
+        Ok((session, images))
+    }
+
+    fn page(&self, directory: &str, query: SessionQuery, cancel: &CancelToken) -> Result<SessionPage, HitoError> {
+        let (offset, limit) = (query.offset.unwrap_or(0), query.limit.unwrap_or(usize::MAX));
+        let (_, images) = self.matching(directory, query, cancel)?;
+        let total = images.len();
+        let images = images.into_iter().skip(offset).take(limit).collect();
+        Ok(SessionPage { total, offset, images })
+    }
+
+    fn indices(&self, directory: &str, query: SessionQuery, cancel: &CancelToken) -> Result<Vec<usize>, HitoError> {
+        let (offset, limit) = (query.offset.unwrap_or(0), query.limit.unwrap_or(usize::MAX));
+        let (session, matching) = self.matching(directory, query, cancel)?;
+        let positions: HashMap<&str, usize> =
+            session.images.iter().enumerate().map(|(i, img)| (img.path.as_str(), i)).collect();
+        Ok(matching
+            .iter()
+            .filter_map(|img| positions.get(img.path.as_str()).copied())
+            .skip(offset)
+            .take(limit)
+            .collect())
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, HashMap<String, DirectorySession>>, HitoError> {
+        Ok(self.sessions.lock()?)
+    }
+}
+
+fn no_session(directory: &str) -> HitoError {
+    HitoError::not_found(format!("No session is open for directory: {}", directory)).with_path(directory)
+}
+
+/// Open (or reopen) a session for `directory`, listing its images and loading its labels once.
+///
+/// `filename` and `annotator` are used as in `load_hito_config`; a task batch data file limits the
+/// session to the batch's images, as in `list_images`.
+///
+/// Runs on the worker pool; a `request_id` makes it cancellable, as in `list_images`.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref()), err(Debug))]
+pub async fn open_directory_session(
+    sessions: State<'_, DirectorySessions>,
+    directory: String,
+    filename: Option<String>,
+    annotator: Option<String>,
+    request_id: Option<String>,
+) -> Result<SessionInfo, HitoError> {
+    let cancel = Request::start(request_id);
+    let sessions = sessions.inner().clone();
+    run_blocking(move || {
+        if !scope::check(&directory)?.is_dir() {
+            return Err(HitoError::invalid_input(format!("Path is not a directory: {}", directory)));
+        }
+        sessions.open(&directory, filename, annotator, cancel.token())
+    })
+    .await
+}
+
+/// Re-read the listing and labels of an open session, e.g. after files were changed outside the app.
+///
+/// Runs on the worker pool; a `request_id` makes it cancellable.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory), err(Debug))]
+pub async fn reload_directory_session(
+    sessions: State<'_, DirectorySessions>,
+    directory: String,
+    request_id: Option<String>,
+) -> Result<SessionInfo, HitoError> {
+    let cancel = Request::start(request_id);
+    let sessions = sessions.inner().clone();
+    run_blocking(move || sessions.reload(&directory, cancel.token())).await
+}
+
+/// Drop the session of `directory`. Returns whether one was open.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory), err(Debug))]
+pub fn close_directory_session(sessions: State<'_, DirectorySessions>, directory: String) -> Result<bool, HitoError> {
+    sessions.close(&directory)
+}
+
+/// Filter and sort the images of an open session and return one page of the result.
+///
+/// Runs on the worker pool (capture-date filters read every file); a `request_id` makes it
+/// cancellable.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory), err(Debug))]
+pub async fn query_directory_session(
+    sessions: State<'_, DirectorySessions>,
+    directory: String,
+    query: Option<SessionQuery>,
+    request_id: Option<String>,
+) -> Result<SessionPage, HitoError> {
+    let cancel = Request::start(request_id);
+    let sessions = sessions.inner().clone();
+    run_blocking(move || sessions.page(&directory, query.unwrap_or_default(), cancel.token())).await
+}
+
+/// Like `query_directory_session`, but return positions in the session's listing (the order of
+/// `list_images`) instead of the images themselves.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory), err(Debug))]
+pub async fn query_directory_session_indices(
+    sessions: State<'_, DirectorySessions>,
+    directory: String,
+    query: Option<SessionQuery>,
+    request_id: Option<String>,
+) -> Result<Vec<usize>, HitoError> {
+    let cancel = Request::start(request_id);
+    let sessions = sessions.inner().clone();
+    run_blocking(move || sessions.indices(&directory, query.unwrap_or_default(), cancel.token())).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::{copy_image_file, move_image_file, save_hito_config, CategoryAssignment, FilterOptions};
+    use crate::scope::granted_temp_dir;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, String) {
+        let temp_dir = granted_temp_dir();
+        for name in ["img10.jpg", "img2.jpg", "img1.jpg", "other.png"] {
+            fs::write(temp_dir.path().join(name), b"image").unwrap();
+        }
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        (temp_dir, directory)
+    }
+
+    fn paths(page: &SessionPage) -> Vec<String> {
+        page.images
+            .iter()
+            .map(|img| Path::new(&img.path).file_name().unwrap().to_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_query_pages_through_sorted_images() {
+        let (_temp_dir, directory) = setup();
+        let sessions = DirectorySessions::default();
+
+        let info = sessions.open(&directory, None, None, &CancelToken::default()).unwrap();
+        assert_eq!(info.image_count, 4);
+
+        let query = |offset, limit| SessionQuery { offset: Some(offset), limit: Some(limit), ..Default::default() };
+        let first = sessions.page(&directory, query(0, 2), &CancelToken::default()).unwrap();
+        assert_eq!(first.total, 4);
+        assert_eq!(paths(&first), vec!["img1.jpg", "img2.jpg"]);
+        let second = sessions.page(&directory, query(2, 2), &CancelToken::default()).unwrap();
+        assert_eq!(paths(&second), vec!["img10.jpg", "other.png"]);
+        assert!(sessions.page(&directory, query(4, 2), &CancelToken::default()).unwrap().images.is_empty());
+
+        // Listing is sorted by path: img1, img10, img2, other
+        assert_eq!(sessions.indices(&directory, SessionQuery::default(), &CancelToken::default()).unwrap(), vec![0, 2, 1, 3]);
+    }
+
+    #[test]
+    fn test_query_picks_up_saved_labels() {
+        let (_temp_dir, directory) = setup();
+        let sessions = DirectorySessions::default();
+        sessions.open(&directory, None, None, &CancelToken::default()).unwrap();
+
+        let image = Path::new(&directory).join("img2.jpg").to_str().unwrap().to_string();
+        let assignment = CategoryAssignment {
+            category_id: "cat".to_string(),
+            assigned_at: "2024-01-01T00:00:00Z".to_string(),
+            ..Default::default()
+        };
+        save_hito_config(directory.clone(), vec![(image, vec![assignment])], None, None, None, None).unwrap();
+
+        let labelled = || SessionQuery {
+            options: SortOptions {
+                filter_options: Some(FilterOptions { category_id: Some("cat".to_string()), ..Default::default() }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(paths(&sessions.page(&directory, labelled(), &CancelToken::default()).unwrap()), vec!["img2.jpg"]);
+        assert_eq!(sessions.reload(&directory, &CancelToken::default()).unwrap().labelled_count, 1);
+    }
+
+    #[test]
+    fn test_query_picks_up_moved_images() {
+        let (_temp_dir, directory) = setup();
+        let destination = granted_temp_dir();
+        let sessions = DirectorySessions::default();
+        sessions.open(&directory, None, None, &CancelToken::default()).unwrap();
+
+        let image = Path::new(&directory).join("img2.jpg").to_str().unwrap().to_string();
+        move_image_file(image, destination.path().to_str().unwrap().to_string(), None, None).unwrap();
+
+        let page = sessions.page(&directory, SessionQuery::default(), &CancelToken::default()).unwrap();
+        assert_eq!(paths(&page), vec!["img1.jpg", "img10.jpg", "other.png"]);
+    }
+
+    #[test]
+    fn test_query_picks_up_copied_images() {
+        let (_temp_dir, directory) = setup();
+        let source = granted_temp_dir();
+        let sessions = DirectorySessions::default();
+        sessions.open(&directory, None, None, &CancelToken::default()).unwrap();
+
+        let image = source.path().join("new.jpg");
+        fs::write(&image, b"image").unwrap();
+        copy_image_file(image.to_str().unwrap().to_string(), directory.clone()).unwrap();
+
+        let page = sessions.page(&directory, SessionQuery::default(), &CancelToken::default()).unwrap();
+        assert_eq!(paths(&page), vec!["img1.jpg", "img10.jpg", "img2.jpg", "new.jpg", "other.png"]);
+    }
+
+    #[test]
+    fn test_cancelled_query_leaves_the_session_usable() {
+        let (_temp_dir, directory) = setup();
+        let sessions = DirectorySessions::default();
+        sessions.open(&directory, None, None, &CancelToken::default()).unwrap();
+
+        let request = Request::start(Some("test-session-query".to_string()));
+        assert!(crate::requests::cancel_request("test-session-query".to_string()));
+        let error = sessions.page(&directory, SessionQuery::default(), request.token()).err().unwrap();
+        assert_eq!(error.code, ErrorCode::Cancelled);
+
+        assert_eq!(sessions.page(&directory, SessionQuery::default(), &CancelToken::default()).unwrap().total, 4);
+    }
+
+    #[test]
+    fn test_closed_session_is_an_error() {
+        let (_temp_dir, directory) = setup();
+        let sessions = DirectorySessions::default();
+        sessions.open(&directory, None, None, &CancelToken::default()).unwrap();
+
+        assert!(sessions.close(&directory).unwrap());
+        assert!(!sessions.close(&directory).unwrap());
+        let error = sessions.page(&directory, SessionQuery::default(), &CancelToken::default()).err().unwrap();
+        assert_eq!(error.code, ErrorCode::NotFound);
+        assert!(sessions.reload(&directory, &CancelToken::default()).is_err());
+    }
+}
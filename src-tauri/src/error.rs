@@ -0,0 +1,164 @@
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::query::QueryError;
+
+/// Stable error codes the frontend can branch on (retry, prompt, translate).
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    NotFound, // Missing file or directory, or an unknown ID
+    PermissionDenied,
+    Conflict, // The target already exists
+    InvalidInput, // Arguments the command cannot act on
+    InvalidData, // Stored data that cannot be parsed
+    Io, // Any other file system error
+    Database, // Metadata index errors
+    Cancelled, // Stopped by cancel_request
+    Internal,
+}
+
+/// Error returned by every command.
+///
+/// Serialized as `{ code, message, path, io_kind, field, position }`; `message` is meant for logs
+/// and as a fallback, the UI should go by `code`.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct HitoError {
+    pub(crate) code: ErrorCode,
+    pub(crate) message: String,
+    pub(crate) path: Option<String>, // File or directory the error is about
+    pub(crate) io_kind: Option<String>, // `std::io::ErrorKind` of the underlying error, e.g. "PermissionDenied"
+    pub(crate) field: Option<String>, // Argument or filter field an invalid_input error is about, e.g. "name_pattern"
+    pub(crate) position: Option<usize>, // Character offset in the field's value, when known
+}
+
+impl HitoError {
+    pub(crate) fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        HitoError {
+            code,
+            message: message.into(),
+            path: None,
+            io_kind: None,
+            field: None,
+            position: None,
+        }
+    }
+
+    pub(crate) fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub(crate) fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub(crate) fn invalid_data(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidData, message)
+    }
+
+    pub(crate) fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+
+    pub(crate) fn cancelled() -> Self {
+        Self::new(ErrorCode::Cancelled, "Request was cancelled")
+    }
+
+    /// Invalid value for the argument or filter field `field`.
+    pub(crate) fn invalid_field(field: &str, message: impl Into<String>) -> Self {
+        HitoError {
+            field: Some(field.to_string()),
+            ..Self::invalid_input(message)
+        }
+    }
+
+    /// File system error while working on `path`; the code follows the error's kind.
+    pub(crate) fn io(context: &str, path: impl AsRef<Path>, error: io::Error) -> Self {
+        let code = match error.kind() {
+            io::ErrorKind::NotFound => ErrorCode::NotFound,
+            io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+            io::ErrorKind::AlreadyExists => ErrorCode::Conflict,
+            io::ErrorKind::InvalidInput => ErrorCode::InvalidInput,
+            io::ErrorKind::InvalidData => ErrorCode::InvalidData,
+            _ => ErrorCode::Io,
+        };
+        HitoError {
+            io_kind: Some(format!("{:?}", error.kind())),
+            ..Self::new(code, format!("{}: {}", context, error)).with_path(path)
+        }
+    }
+
+    pub(crate) fn with_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.path = Some(path.as_ref().to_string_lossy().into_owned());
+        self
+    }
+}
+
+impl fmt::Display for HitoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.field, self.position) {
+            (Some(field), Some(position)) => write!(f, "Invalid {}: {} at position {}", field, self.message, position),
+            (Some(field), None) => write!(f, "Invalid {}: {}", field, self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for HitoError {}
+
+impl From<QueryError> for HitoError {
+    fn from(error: QueryError) -> Self {
+        HitoError {
+            position: Some(error.position),
+            ..Self::invalid_field("query", error.message)
+        }
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for HitoError {
+    fn from(error: std::sync::PoisonError<T>) -> Self {
+        Self::internal(format!("Failed to acquire lock: {}", error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_errors_map_to_codes() {
+        let error = HitoError::io(
+            "Failed to read image",
+            "/photos/a.jpg",
+            io::Error::from(io::ErrorKind::PermissionDenied),
+        );
+        assert_eq!(error.code, ErrorCode::PermissionDenied);
+        assert_eq!(error.path.as_deref(), Some("/photos/a.jpg"));
+        assert_eq!(error.io_kind.as_deref(), Some("PermissionDenied"));
+
+        let error = HitoError::io("Failed to copy image", "/b.jpg", io::Error::from(io::ErrorKind::AlreadyExists));
+        assert_eq!(error.code, ErrorCode::Conflict);
+        let error = HitoError::io("Failed to read directory", "/c", io::Error::from(io::ErrorKind::Interrupted));
+        assert_eq!(error.code, ErrorCode::Io);
+    }
+
+    #[test]
+    fn test_serialized_shape() {
+        let error = HitoError::not_found("Image does not exist: /a.jpg").with_path("/a.jpg");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "code": "not_found",
+                "message": "Image does not exist: /a.jpg",
+                "path": "/a.jpg",
+                "io_kind": null,
+                "field": null,
+                "position": null,
+            })
+        );
+        assert_eq!(HitoError::invalid_field("sort_keys", "Unknown sort key: x").to_string(), "Invalid sort_keys: Unknown sort key: x");
+    }
+}
@@ -0,0 +1,588 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::HitoError;
+use crate::{create_hito_file, get_hito_file_path, CategoryAssignment, CategoryData, HitoFile, Region};
+
+/// One entry of the append-only label event log kept next to a data file.
+///
+/// `kind` is one of `"assign"`, `"unassign"`, `"region_add"`, `"region_update"`, `"region_remove"`,
+/// `"category_create"`, `"category_delete"`, `"move"`, `"delete"` or `"snapshot"`. The snapshot is
+/// written when a log is started for a data file that already has labels, so the log can always be
+/// replayed from its first line.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub(crate) struct LabelEvent {
+    pub(crate) timestamp: String, // ISO 8601 datetime string
+    pub(crate) kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) image_path: Option<String>, // Destination path for moves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) from_path: Option<String>, // Source path for moves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) category_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) annotator: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) category: Option<CategoryData>, // Created category
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) region: Option<Region>, // Added, updated (new geometry) or removed region
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) snapshot: Option<LabelSnapshot>,
+}
+
+/// Label state reconstructed from (or recorded into) the event log.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub(crate) struct LabelSnapshot {
+    pub(crate) image_categories: Vec<(String, Vec<CategoryAssignment>)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) categories: Option<Vec<CategoryData>>,
+}
+
+/// Get the path of the event log that belongs to a data file.
+///
+/// `.hito.json` logs to `.hito.events.jsonl`, `labels.json` to `labels.events.jsonl`.
+pub(crate) fn get_event_log_path(directory: &str, filename: Option<&str>) -> Result<PathBuf, HitoError> {
+    Ok(event_log_path_for(&get_hito_file_path(directory, filename)?))
+}
+
+fn event_log_path_for(data_file: &Path) -> PathBuf {
+    let filename = data_file.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let stem = filename.strip_suffix(".json").unwrap_or(&filename);
+    data_file.with_file_name(format!("{}.events.jsonl", stem))
+}
+
+/// Append events to a log, one JSON object per line.
+pub(crate) fn append_events(log_path: &Path, events: &[LabelEvent]) -> Result<(), HitoError> {
+    if events.is_empty() {
+        return Ok(());
+    }
+
+    let mut lines = String::new();
+    for event in events {
+        let line = serde_json::to_string(event)
+            .map_err(|e| HitoError::internal(format!("Failed to serialize event: {}", e)))?;
+        lines.push_str(&line);
+        lines.push('\n');
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .map_err(|e| HitoError::io("Failed to open event log", log_path, e))?;
+    file.write_all(lines.as_bytes())
+        .map_err(|e| HitoError::io("Failed to write event log", log_path, e))
+}
+
+/// Read all events of a log in the order they were written, empty if the log does not exist.
+/// Malformed lines are skipped.
+pub(crate) fn read_events(log_path: &Path) -> Result<Vec<LabelEvent>, HitoError> {
+    if !log_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(log_path)
+        .map_err(|e| HitoError::io("Failed to read event log", log_path, e))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Snapshot event for a data file whose log is about to be started, if it has any labels yet.
+pub(crate) fn initial_snapshot(log_path: &Path, existing: &HitoFile, now: &str) -> Option<LabelEvent> {
+    if log_path.exists() || (existing.image_categories.is_empty() && existing.categories.is_none()) {
+        return None;
+    }
+
+    Some(LabelEvent {
+        timestamp: now.to_string(),
+        kind: "snapshot".to_string(),
+        snapshot: Some(LabelSnapshot {
+            image_categories: existing.image_categories.clone(),
+            categories: existing.categories.clone(),
+        }),
+        ..Default::default()
+    })
+}
+
+/// Compare two sets of assignments and describe the change as assign/unassign events.
+///
+/// An assignment is identified by image, category and annotator, so each annotator's assignments
+/// are compared separately. With `annotator` set, only that annotator's assignments are compared.
+/// New assignments are logged at their `assigned_at`, removed ones at `now`.
+pub(crate) fn diff_assignments(
+    old: &[(String, Vec<CategoryAssignment>)],
+    new: &[(String, Vec<CategoryAssignment>)],
+    annotator: Option<&str>,
+    now: &str,
+) -> Vec<LabelEvent> {
+    let index = |image_categories: &[(String, Vec<CategoryAssignment>)]| {
+        let mut keys: HashMap<(String, String, Option<String>), String> = HashMap::new();
+        for (path, assignments) in image_categories {
+            for assignment in assignments.iter().filter(|a| annotator.is_none() || a.annotator.as_deref() == annotator) {
+                keys.insert(
+                    (path.clone(), assignment.category_id.clone(), assignment.annotator.clone()),
+                    assignment.assigned_at.clone(),
+                );
+            }
+        }
+        keys
+    };
+    let old_keys = index(old);
+    let new_keys = index(new);
+
+    let event = |timestamp: &str, kind: &str, (path, category_id, annotator): &(String, String, Option<String>)| LabelEvent {
+        timestamp: timestamp.to_string(),
+        kind: kind.to_string(),
+        image_path: Some(path.clone()),
+        category_id: Some(category_id.clone()),
+        annotator: annotator.clone(),
+        ..Default::default()
+    };
+
+    let mut events: Vec<LabelEvent> = new_keys
+        .iter()
+        .filter(|(key, _)| !old_keys.contains_key(*key))
+        .map(|(key, assigned_at)| event(assigned_at, "assign", key))
+        .chain(
+            old_keys
+                .keys()
+                .filter(|key| !new_keys.contains_key(*key))
+                .map(|key| event(now, "unassign", key)),
+        )
+        .collect();
+    events.sort_by(|a, b| {
+        (&a.timestamp, &a.image_path, &a.category_id).cmp(&(&b.timestamp, &b.image_path, &b.category_id))
+    });
+    events
+}
+
+/// Describe categories added to or removed from a data file as category events.
+pub(crate) fn diff_categories(
+    old: Option<&[CategoryData]>,
+    new: Option<&[CategoryData]>,
+    annotator: Option<&str>,
+    now: &str,
+) -> Vec<LabelEvent> {
+    let old = old.unwrap_or_default();
+    let new = new.unwrap_or_default();
+    let event = |kind: &str, category: &CategoryData, with_data: bool| LabelEvent {
+        timestamp: now.to_string(),
+        kind: kind.to_string(),
+        category_id: Some(category.id.clone()),
+        annotator: annotator.map(str::to_string),
+        category: with_data.then(|| category.clone()),
+        ..Default::default()
+    };
+
+    new.iter()
+        .filter(|c| !old.iter().any(|o| o.id == c.id))
+        .map(|c| event("category_create", c, true))
+        .chain(
+            old.iter()
+                .filter(|o| !new.iter().any(|c| c.id == o.id))
+                .map(|o| event("category_delete", o, false)),
+        )
+        .collect()
+}
+
+/// Describe a change to a region of the assignment of `category_id` on `image_path`.
+///
+/// `kind` is `"region_add"`, `"region_update"` or `"region_remove"`.
+pub(crate) fn region_event(
+    kind: &str,
+    image_path: &str,
+    assignment: &CategoryAssignment,
+    region: &Region,
+    now: &str,
+) -> LabelEvent {
+    LabelEvent {
+        timestamp: now.to_string(),
+        kind: kind.to_string(),
+        image_path: Some(image_path.to_string()),
+        category_id: Some(assignment.category_id.clone()),
+        annotator: assignment.annotator.clone(),
+        region: Some(region.clone()),
+        ..Default::default()
+    }
+}
+
+/// Record a move or delete of an image file in the log of its data file.
+///
+/// The data file is `data_file_path` if given, otherwise `.hito.json` in the image's directory.
+/// Nothing is recorded when that data file does not exist, i.e. the directory is not being labelled.
+pub(crate) fn record_file_event(
+    image_path: &str,
+    data_file_path: Option<&str>,
+    mut event: LabelEvent,
+) -> Result<(), HitoError> {
+    let data_file = match data_file_path {
+        Some(path) => PathBuf::from(path),
+        None => match Path::new(image_path).parent() {
+            Some(parent) => parent.join(".hito.json"),
+            None => return Ok(()),
+        },
+    };
+    if !data_file.exists() {
+        return Ok(());
+    }
+
+    event.timestamp = Utc::now().to_rfc3339();
+    append_events(&event_log_path_for(&data_file), &[event])
+}
+
+/// Rebuild the label state by applying events in log order, ignoring those after `until`.
+fn replay(events: &[LabelEvent], until: Option<DateTime<Utc>>) -> LabelSnapshot {
+    let mut state = LabelSnapshot::default();
+
+    for event in events {
+        if let Some(until) = until {
+            match DateTime::parse_from_rfc3339(&event.timestamp) {
+                Ok(at) if at.with_timezone(&Utc) <= until => {}
+                _ => continue,
+            }
+        }
+
+        match (event.kind.as_str(), &event.image_path, &event.category_id) {
+            ("snapshot", _, _) => {
+                if let Some(snapshot) = &event.snapshot {
+                    state = snapshot.clone();
+                }
+            }
+            ("assign", Some(path), Some(category_id)) => {
+                let assignments = match state.image_categories.iter_mut().find(|(p, _)| p == path) {
+                    Some((_, assignments)) => assignments,
+                    None => {
+                        state.image_categories.push((path.clone(), Vec::new()));
+                        &mut state.image_categories.last_mut().unwrap().1
+                    }
+                };
+                if !assignments.iter().any(|a| a.category_id == *category_id && a.annotator == event.annotator) {
+                    assignments.push(CategoryAssignment {
+                        category_id: category_id.clone(),
+                        assigned_at: event.timestamp.clone(),
+                        annotator: event.annotator.clone(),
+                        ..Default::default()
+                    });
+                }
+            }
+            ("unassign", Some(path), Some(category_id)) => {
+                if let Some((_, assignments)) = state.image_categories.iter_mut().find(|(p, _)| p == path) {
+                    assignments.retain(|a| !(a.category_id == *category_id && a.annotator == event.annotator));
+                }
+                state.image_categories.retain(|(_, assignments)| !assignments.is_empty());
+            }
+            ("region_add", Some(path), Some(category_id)) => {
+                let assignment = state
+                    .image_categories
+                    .iter_mut()
+                    .filter(|(p, _)| p == path)
+                    .flat_map(|(_, assignments)| assignments.iter_mut())
+                    .find(|a| a.category_id == *category_id && a.annotator == event.annotator);
+                if let (Some(assignment), Some(region)) = (assignment, &event.region) {
+                    assignment.regions.get_or_insert_with(Vec::new).push(region.clone());
+                }
+            }
+            ("region_update" | "region_remove", Some(path), _) => {
+                let Some(region) = &event.region else {
+                    continue;
+                };
+                for (_, assignments) in state.image_categories.iter_mut().filter(|(p, _)| p == path) {
+                    for assignment in assignments.iter_mut() {
+                        if let Some(regions) = assignment.regions.as_mut() {
+                            if event.kind == "region_update" {
+                                regions.iter_mut().filter(|r| r.id == region.id).for_each(|r| *r = region.clone());
+                            } else {
+                                regions.retain(|r| r.id != region.id);
+                            }
+                        }
+                        if assignment.regions.as_ref().is_some_and(|regions| regions.is_empty()) {
+                            assignment.regions = None;
+                        }
+                    }
+                }
+            }
+            ("category_create", _, _) => {
+                if let Some(category) = &event.category {
+                    let categories = state.categories.get_or_insert_with(Vec::new);
+                    categories.retain(|c| c.id != category.id);
+                    categories.push(category.clone());
+                }
+            }
+            ("category_delete", _, Some(category_id)) => {
+                if let Some(categories) = &mut state.categories {
+                    categories.retain(|c| c.id != *category_id);
+                }
+            }
+            ("move", Some(path), _) => {
+                if let Some(entry) = state.image_categories.iter_mut().find(|(p, _)| Some(p) == event.from_path.as_ref()) {
+                    entry.0 = path.clone();
+                }
+            }
+            ("delete", Some(path), _) => {
+                state.image_categories.retain(|(p, _)| p != path);
+            }
+            _ => {}
+        }
+    }
+
+    state
+}
+
+/// Get every logged event that concerns `image_path`, oldest first.
+///
+/// Moves are included both under their source and destination path.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref(), image_path = %image_path), err(Debug))]
+pub fn get_label_history(
+    directory: String,
+    filename: Option<String>,
+    image_path: String,
+) -> Result<Vec<LabelEvent>, HitoError> {
+    let events = read_events(&get_event_log_path(&directory, filename.as_deref())?)?;
+    Ok(events
+        .into_iter()
+        .filter(|e| {
+            e.image_path.as_deref() == Some(image_path.as_str())
+                || e.from_path.as_deref() == Some(image_path.as_str())
+        })
+        .collect())
+}
+
+/// Replay the event log of a data file to rebuild its labels as of `until` (default: now).
+///
+/// The result has the image assignments and categories of that moment. When `output_filename` is
+/// given the rebuilt state is also written there as a new data file; an existing file is never
+/// overwritten and fails with a `conflict` error.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref(), output_filename = output_filename.as_deref()), err(Debug))]
+pub fn replay_label_log(
+    directory: String,
+    filename: Option<String>,
+    until: Option<String>,
+    output_filename: Option<String>,
+) -> Result<HitoFile, HitoError> {
+    let until = until
+        .map(|until| {
+            DateTime::parse_from_rfc3339(&until)
+                .map(|at| at.with_timezone(&Utc))
+                .map_err(|e| HitoError::invalid_field("until", format!("Invalid timestamp {}: {}", until, e)))
+        })
+        .transpose()?;
+
+    let events = read_events(&get_event_log_path(&directory, filename.as_deref())?)?;
+    let state = replay(&events, until);
+    let data = HitoFile {
+        image_categories: state.image_categories,
+        categories: state.categories,
+        ..Default::default()
+    };
+
+    if let Some(output_filename) = output_filename {
+        if output_filename == filename.as_deref().unwrap_or(".hito.json") {
+            return Err(HitoError::invalid_input(format!("Output file must not be the replayed data file: {}", output_filename)));
+        }
+        create_hito_file(&get_hito_file_path(&directory, Some(&output_filename))?, &data)?;
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::scope::granted_temp_dir;
+    use crate::{save_hito_config, Geometry};
+
+    fn assignment(category_id: &str, assigned_at: &str) -> CategoryAssignment {
+        CategoryAssignment {
+            category_id: category_id.to_string(),
+            assigned_at: assigned_at.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn category(id: &str) -> CategoryData {
+        CategoryData {
+            id: id.to_string(),
+            name: id.to_string(),
+            color: "#ffffff".to_string(),
+            mutually_exclusive_with: None,
+        }
+    }
+
+    fn event(timestamp: &str, kind: &str, image_path: &str, category_id: &str) -> LabelEvent {
+        LabelEvent {
+            timestamp: timestamp.to_string(),
+            kind: kind.to_string(),
+            image_path: Some(image_path.to_string()),
+            category_id: Some(category_id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_event_log_path_follows_data_file() {
+        assert!(event_log_path_for(Path::new("/d/.hito.json")).ends_with(".hito.events.jsonl"));
+        assert!(event_log_path_for(Path::new("/d/labels.json")).ends_with("labels.events.jsonl"));
+    }
+
+    #[test]
+    fn test_diff_assignments_and_round_trip() {
+        let old = vec![("/a.jpg".to_string(), vec![assignment("cat", "2024-01-01T00:00:00Z")])];
+        let new = vec![
+            ("/a.jpg".to_string(), vec![assignment("cat", "2024-01-01T00:00:00Z")]),
+            ("/b.jpg".to_string(), vec![assignment("dog", "2024-01-01T00:01:00Z")]),
+        ];
+
+        let events = diff_assignments(&old, &new, None, "2024-01-01T00:02:00Z");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, "assign");
+        assert_eq!(events[0].timestamp, "2024-01-01T00:01:00Z");
+
+        let events = diff_assignments(&new, &old, None, "2024-01-01T00:02:00Z");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, "unassign");
+        assert_eq!(events[0].image_path.as_deref(), Some("/b.jpg"));
+
+        let temp_dir = granted_temp_dir();
+        let log_path = temp_dir.path().join("log.jsonl");
+        append_events(&log_path, &events).unwrap();
+        append_events(&log_path, &events).unwrap();
+        assert_eq!(read_events(&log_path).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_diff_categories() {
+        let events = diff_categories(Some(&[category("cat")]), Some(&[category("dog")]), Some("alice"), "2024-01-01T00:00:00Z");
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, "category_create");
+        assert_eq!(events[0].category.as_ref().unwrap().id, "dog");
+        assert_eq!(events[1].kind, "category_delete");
+        assert_eq!(events[1].category_id.as_deref(), Some("cat"));
+        assert_eq!(events[1].annotator.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn test_replay_until_point_in_time() {
+        let events = vec![
+            LabelEvent {
+                timestamp: "2024-01-01T00:00:00Z".to_string(),
+                kind: "snapshot".to_string(),
+                snapshot: Some(LabelSnapshot {
+                    image_categories: vec![("/a.jpg".to_string(), vec![assignment("cat", "2023-12-31T00:00:00Z")])],
+                    categories: Some(vec![category("cat")]),
+                }),
+                ..Default::default()
+            },
+            LabelEvent {
+                timestamp: "2024-01-01T00:01:00Z".to_string(),
+                kind: "category_create".to_string(),
+                category_id: Some("dog".to_string()),
+                category: Some(category("dog")),
+                ..Default::default()
+            },
+            event("2024-01-01T00:02:00Z", "assign", "/b.jpg", "dog"),
+            event("2024-01-01T00:03:00Z", "unassign", "/a.jpg", "cat"),
+            LabelEvent {
+                timestamp: "2024-01-01T00:04:00Z".to_string(),
+                kind: "move".to_string(),
+                image_path: Some("/moved/b.jpg".to_string()),
+                from_path: Some("/b.jpg".to_string()),
+                ..Default::default()
+            },
+        ];
+        let at = |t: &str| Some(DateTime::parse_from_rfc3339(t).unwrap().with_timezone(&Utc));
+
+        let state = replay(&events, at("2024-01-01T00:02:30Z"));
+        assert_eq!(state.image_categories.len(), 2);
+        assert_eq!(state.categories.as_ref().unwrap().len(), 2);
+
+        let state = replay(&events, None);
+        assert_eq!(state.image_categories.len(), 1);
+        assert_eq!(state.image_categories[0].0, "/moved/b.jpg");
+
+        let state = replay(&events, at("2023-06-01T00:00:00Z"));
+        assert!(state.image_categories.is_empty());
+        assert!(state.categories.is_none());
+    }
+
+    #[test]
+    fn test_replay_region_events() {
+        let region = |x: f64| Region {
+            id: "region_1".to_string(),
+            geometry: Geometry::Point { x, y: 0.5 },
+            created_at: "2024-01-01T00:01:00Z".to_string(),
+        };
+        let labelled = assignment("defect", "2024-01-01T00:01:00Z");
+        let mut events = diff_assignments(&[], &[("/a.jpg".to_string(), vec![labelled.clone()])], None, "2024-01-01T00:01:00Z");
+        events.push(region_event("region_add", "/a.jpg", &labelled, &region(0.1), "2024-01-01T00:01:00Z"));
+        events.push(region_event("region_update", "/a.jpg", &labelled, &region(0.2), "2024-01-01T00:02:00Z"));
+        events.push(region_event("region_remove", "/a.jpg", &labelled, &region(0.2), "2024-01-01T00:03:00Z"));
+        let at = |t: &str| Some(DateTime::parse_from_rfc3339(t).unwrap().with_timezone(&Utc));
+        let regions = |state: &LabelSnapshot| state.image_categories[0].1[0].regions.clone();
+
+        let state = replay(&events, at("2024-01-01T00:01:30Z"));
+        assert!(regions(&state).unwrap()[0].geometry == Geometry::Point { x: 0.1, y: 0.5 });
+        let state = replay(&events, at("2024-01-01T00:02:30Z"));
+        assert!(regions(&state).unwrap()[0].geometry == Geometry::Point { x: 0.2, y: 0.5 });
+
+        // The assignment stays as a whole-image label
+        let state = replay(&events, None);
+        assert_eq!(state.image_categories[0].1.len(), 1);
+        assert!(regions(&state).is_none());
+    }
+
+    #[test]
+    fn test_record_file_event_requires_data_file() {
+        let temp_dir = granted_temp_dir();
+        let image_path = temp_dir.path().join("a.jpg");
+        let image_path = image_path.to_str().unwrap();
+        let delete = || LabelEvent {
+            kind: "delete".to_string(),
+            image_path: Some(image_path.to_string()),
+            ..Default::default()
+        };
+
+        record_file_event(image_path, None, delete()).unwrap();
+        assert!(!temp_dir.path().join(".hito.events.jsonl").exists());
+
+        fs::write(temp_dir.path().join(".hito.json"), "{\"image_categories\":[]}").unwrap();
+        record_file_event(image_path, None, delete()).unwrap();
+
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        let history = get_label_history(directory, None, image_path.to_string()).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].kind, "delete");
+    }
+
+    #[test]
+    fn test_replay_never_overwrites_a_data_file() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        let labels = |category_id: &str| vec![("/a.jpg".to_string(), vec![assignment(category_id, "2024-01-01T00:00:00Z")])];
+        save_hito_config(directory.clone(), labels("cat"), None, None, None, None).unwrap();
+        save_hito_config(directory.clone(), labels("dog"), Some("alice.json".to_string()), None, None, None).unwrap();
+        let live = fs::read_to_string(temp_dir.path().join(".hito.json")).unwrap();
+
+        let replay_into = |output: &str| {
+            replay_label_log(directory.clone(), Some("alice.json".to_string()), None, Some(output.to_string()))
+        };
+        assert_eq!(replay_into(".hito.json").err().unwrap().code, ErrorCode::Conflict);
+        assert_eq!(fs::read_to_string(temp_dir.path().join(".hito.json")).unwrap(), live);
+        assert_eq!(replay_into("alice.json").err().unwrap().code, ErrorCode::InvalidInput);
+
+        replay_into("alice-replayed.json").unwrap();
+        let replayed = crate::read_hito_file(&temp_dir.path().join("alice-replayed.json")).unwrap();
+        assert_eq!(replayed.image_categories[0].1[0].category_id, "dog");
+        assert_eq!(replay_into("alice-replayed.json").err().unwrap().code, ErrorCode::Conflict);
+    }
+}
@@ -0,0 +1,577 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::error::{ErrorCode, HitoError};
+use crate::requests::{run_blocking, CancelToken, Request};
+use crate::sessions::csv_field;
+use crate::{
+    get_hito_file_path, load_hito_config, read_hito_file, scope, CategoryAssignment, Geometry, HitoFile, ImageNote,
+};
+
+#[derive(Serialize)]
+pub struct DetectionExport {
+    image_count: usize,
+    annotation_count: usize,
+    skipped_points: usize, // Point regions, which have no box in either format
+    skipped_images: Vec<String>, // Images whose size could not be read (COCO only)
+}
+
+#[derive(Serialize)]
+struct CocoFile {
+    images: Vec<CocoImage>,
+    annotations: Vec<CocoAnnotation>,
+    categories: Vec<CocoCategory>,
+}
+
+#[derive(Serialize)]
+struct CocoImage {
+    id: usize,
+    file_name: String,
+    width: usize,
+    height: usize,
+}
+
+#[derive(Serialize)]
+struct CocoAnnotation {
+    id: usize,
+    image_id: usize,
+    category_id: usize,
+    bbox: [f64; 4], // x, y, width, height in pixels
+    area: f64,
+    segmentation: Vec<Vec<f64>>, // Polygon outline in pixels, empty for boxes
+    iscrowd: u8,
+}
+
+#[derive(Serialize)]
+struct CocoCategory {
+    id: usize,
+    name: String,
+    supercategory: String,
+}
+
+/// Labels and notes of one image, as exported.
+#[derive(Default)]
+struct ExportRow<'a> {
+    assignments: Vec<&'a CategoryAssignment>,
+    notes: Vec<&'a ImageNote>,
+}
+
+/// Render the labels and notes of a data file as CSV, one row per image sorted by path.
+///
+/// Categories are listed by ID and by name (the ID when the file has no name for it), separated
+/// by `;`. Notes are written as `author: text` (just the text without an author), separated by ` | `.
+fn labels_to_csv(data: &HitoFile) -> (String, usize) {
+    let names: HashMap<&str, &str> = data.categories
+        .iter()
+        .flatten()
+        .map(|c| (c.id.as_str(), c.name.as_str()))
+        .collect();
+
+    let mut rows: BTreeMap<&str, ExportRow> = BTreeMap::new();
+    for (path, assignments) in &data.image_categories {
+        rows.entry(path.as_str()).or_default().assignments.extend(assignments);
+    }
+    for (path, notes) in data.image_notes.iter().flatten() {
+        rows.entry(path.as_str()).or_default().notes.extend(notes);
+    }
+    rows.retain(|_, row| !row.assignments.is_empty() || !row.notes.is_empty());
+
+    let mut csv = String::from("image_path,category_ids,category_names,notes\n");
+    for (path, row) in &rows {
+        let ids: Vec<&str> = row.assignments.iter().map(|a| a.category_id.as_str()).collect();
+        let category_names: Vec<&str> = ids.iter().map(|id| names.get(id).copied().unwrap_or(id)).collect();
+        let notes: Vec<String> = row.notes
+            .iter()
+            .map(|note| match &note.author {
+                Some(author) => format!("{}: {}", author, note.text),
+                None => note.text.clone(),
+            })
+            .collect();
+        let fields = [path.to_string(), ids.join(";"), category_names.join(";"), notes.join(" | ")];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    (csv, rows.len())
+}
+
+/// Export the labels and notes of a data file as CSV to `output_path`.
+///
+/// With `annotator` set, only that annotator's assignments and notes are exported; otherwise the
+/// merged view (see `load_hito_config`) and every note.
+///
+/// Runs on the worker pool, as large data files take a while to read and convert; a `request_id`
+/// makes it cancellable until the file is written.
+///
+/// # Returns
+///
+/// The number of exported images.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref(), output_path = %output_path), err(Debug))]
+pub async fn export_labels_csv(
+    directory: String,
+    filename: Option<String>,
+    annotator: Option<String>,
+    output_path: String,
+    request_id: Option<String>,
+) -> Result<usize, HitoError> {
+    let cancel = Request::start(request_id);
+    run_blocking(move || {
+        scope::check(&output_path)?;
+        let mut data = load_hito_config(directory, filename, annotator.clone())?;
+        if let (Some(annotator), Some(image_notes)) = (&annotator, &mut data.image_notes) {
+            for (_, notes) in image_notes.iter_mut() {
+                notes.retain(|note| note.author.as_deref() == Some(annotator.as_str()));
+            }
+        }
+
+        let (csv, count) = labels_to_csv(&data);
+        cancel.token().check()?;
+        fs::write(&output_path, csv)
+            .map_err(|e| HitoError::io("Failed to write CSV file", &output_path, e))?;
+        Ok(count)
+    })
+    .await
+}
+
+/// Images with regions in a data file, in file order.
+struct DetectionImage<'a> {
+    path: &'a str,
+    regions: Vec<(usize, &'a Geometry)>, // Class index (see `detection_classes`) and geometry
+}
+
+/// Class names of a data file for detection exports: its categories in order, then the IDs of
+/// assigned categories it has no entry for, sorted.
+fn detection_classes(data: &HitoFile) -> Vec<(String, String)> {
+    let mut classes: Vec<(String, String)> = data.categories
+        .iter()
+        .flatten()
+        .map(|c| (c.id.clone(), c.name.clone()))
+        .collect();
+    let mut unknown: Vec<&str> = data.image_categories
+        .iter()
+        .flat_map(|(_, assignments)| assignments.iter())
+        .map(|a| a.category_id.as_str())
+        .filter(|id| !classes.iter().any(|(known, _)| known == id))
+        .collect();
+    unknown.sort();
+    unknown.dedup();
+    classes.extend(unknown.into_iter().map(|id| (id.to_string(), id.to_string())));
+    classes
+}
+
+/// Collect the regions of every image, attributed to `annotator` when set.
+fn detection_images<'a>(
+    data: &'a HitoFile,
+    classes: &[(String, String)],
+    annotator: Option<&str>,
+) -> Vec<DetectionImage<'a>> {
+    data.image_categories
+        .iter()
+        .map(|(path, assignments)| DetectionImage {
+            path,
+            regions: assignments
+                .iter()
+                .filter(|a| annotator.is_none() || a.annotator.as_deref() == annotator)
+                .flat_map(|a| {
+                    let class = classes.iter().position(|(id, _)| *id == a.category_id).unwrap_or_default();
+                    a.regions.iter().flatten().map(move |r| (class, &r.geometry))
+                })
+                .collect(),
+        })
+        .filter(|image| !image.regions.is_empty())
+        .collect()
+}
+
+/// Path of an image relative to the exported directory, or its file name when it lies elsewhere.
+fn relative_image_path(image_path: &str, directory: &Path) -> PathBuf {
+    let path = Path::new(image_path);
+    match path.strip_prefix(directory) {
+        Ok(relative) if relative.components().all(|c| matches!(c, Component::Normal(_))) => relative.to_path_buf(),
+        _ => PathBuf::from(path.file_name().unwrap_or_default()),
+    }
+}
+
+/// Axis-aligned box around a geometry in normalized coordinates, `None` for points.
+fn bounding_box(geometry: &Geometry) -> Option<[f64; 4]> {
+    match geometry {
+        Geometry::Bbox { x, y, width, height } => Some([*x, *y, *width, *height]),
+        Geometry::Polygon { points } => {
+            let (mut min_x, mut min_y, mut max_x, mut max_y) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+            for [x, y] in points {
+                min_x = min_x.min(*x);
+                min_y = min_y.min(*y);
+                max_x = max_x.max(*x);
+                max_y = max_y.max(*y);
+            }
+            Some([min_x, min_y, max_x - min_x, max_y - min_y])
+        }
+        Geometry::Point { .. } => None,
+    }
+}
+
+/// Build the COCO file of a data file's regions; image sizes are read from the image files.
+fn regions_to_coco(
+    data: &HitoFile,
+    directory: &Path,
+    annotator: Option<&str>,
+    cancel: &CancelToken,
+) -> Result<(CocoFile, DetectionExport), HitoError> {
+    let classes = detection_classes(data);
+    let mut coco = CocoFile {
+        images: Vec::new(),
+        annotations: Vec::new(),
+        categories: classes
+            .iter()
+            .enumerate()
+            .map(|(index, (_, name))| CocoCategory { id: index + 1, name: name.clone(), supercategory: String::new() })
+            .collect(),
+    };
+    let mut report = DetectionExport { image_count: 0, annotation_count: 0, skipped_points: 0, skipped_images: Vec::new() };
+
+    for image in detection_images(data, &classes, annotator) {
+        cancel.check()?;
+        let Ok(size) = imagesize::size(image.path) else {
+            report.skipped_images.push(image.path.to_string());
+            continue;
+        };
+        let (width, height) = (size.width as f64, size.height as f64);
+        let image_id = coco.images.len() + 1;
+        coco.images.push(CocoImage {
+            id: image_id,
+            file_name: relative_image_path(image.path, directory).to_string_lossy().into_owned(),
+            width: size.width,
+            height: size.height,
+        });
+
+        for (class, geometry) in image.regions {
+            let Some([x, y, w, h]) = bounding_box(geometry) else {
+                report.skipped_points += 1;
+                continue;
+            };
+            let bbox = [x * width, y * height, w * width, h * height];
+            let (segmentation, area) = match geometry {
+                Geometry::Polygon { points } => {
+                    let outline: Vec<f64> = points.iter().flat_map(|[x, y]| [x * width, y * height]).collect();
+                    // Shoelace formula over the closed outline
+                    let twice_area: f64 = (0..points.len())
+                        .map(|i| {
+                            let ([x1, y1], [x2, y2]) = (points[i], points[(i + 1) % points.len()]);
+                            (x1 * y2 - x2 * y1) * width * height
+                        })
+                        .sum();
+                    (vec![outline], twice_area.abs() / 2.0)
+                }
+                _ => (Vec::new(), bbox[2] * bbox[3]),
+            };
+            coco.annotations.push(CocoAnnotation {
+                id: coco.annotations.len() + 1,
+                image_id,
+                category_id: class + 1,
+                bbox,
+                area,
+                segmentation,
+                iscrowd: 0,
+            });
+        }
+    }
+
+    report.image_count = coco.images.len();
+    report.annotation_count = coco.annotations.len();
+    Ok((coco, report))
+}
+
+/// Export the regions of a data file as a COCO detection file at `output_path`.
+///
+/// Bounding boxes and polygons become annotations in pixels (polygons with their outline as
+/// segmentation); images are listed relative to `directory`. Point regions are skipped, as are
+/// images whose size cannot be read. With `annotator` set, only that annotator's regions are
+/// exported; otherwise the regions of every annotator.
+///
+/// Runs on the worker pool, as it reads the size of every image; a `request_id` makes it cancellable.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref(), output_path = %output_path), err(Debug))]
+pub async fn export_coco(
+    directory: String,
+    filename: Option<String>,
+    annotator: Option<String>,
+    output_path: String,
+    request_id: Option<String>,
+) -> Result<DetectionExport, HitoError> {
+    let cancel = Request::start(request_id);
+    run_blocking(move || {
+        scope::check(&output_path)?;
+        let hito_path = get_hito_file_path(&directory, filename.as_deref())?;
+        let data = read_hito_file(&hito_path)?;
+        let (coco, report) = regions_to_coco(&data, Path::new(&directory), annotator.as_deref(), cancel.token())?;
+
+        let json = serde_json::to_string_pretty(&coco)
+            .map_err(|e| HitoError::internal(format!("Failed to serialize COCO file: {}", e)))?;
+        fs::write(&output_path, json).map_err(|e| HitoError::io("Failed to write COCO file", &output_path, e))?;
+        Ok(report)
+    })
+    .await
+}
+
+/// YOLO label file contents for one image: `class center_x center_y width height` per box.
+fn yolo_lines(regions: &[(usize, &Geometry)]) -> (String, usize) {
+    let mut lines = String::new();
+    let mut skipped_points = 0;
+    for (class, geometry) in regions {
+        match bounding_box(geometry) {
+            Some([x, y, w, h]) => {
+                lines.push_str(&format!("{} {:.6} {:.6} {:.6} {:.6}\n", class, x + w / 2.0, y + h / 2.0, w, h));
+            }
+            None => skipped_points += 1,
+        }
+    }
+    (lines, skipped_points)
+}
+
+/// Label file of `image_path` under `output_dir`: its path relative to `directory` with `.txt`
+/// as extension, or its bare file name for images outside of `directory`.
+fn yolo_label_path(output_dir: &Path, image_path: &str, directory: &Path) -> PathBuf {
+    output_dir.join(relative_image_path(image_path, directory)).with_extension("txt")
+}
+
+/// Images that would share a label file, or overwrite `classes.txt`, grouped by label file.
+///
+/// Paths are compared case-insensitively, as on the default file systems of macOS and Windows.
+fn yolo_collisions<'a>(labels: &[(PathBuf, &'a str)], classes_path: &Path) -> Vec<Vec<&'a str>> {
+    let key = |path: &Path| path.to_string_lossy().to_lowercase();
+    let mut by_label: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for (label_path, image_path) in labels {
+        by_label.entry(key(label_path)).or_default().push(image_path);
+    }
+    let classes_key = key(classes_path);
+    by_label
+        .into_iter()
+        .filter(|(label, images)| images.len() > 1 || *label == classes_key)
+        .map(|(_, images)| images)
+        .collect()
+}
+
+/// Export the regions of a data file as YOLO label files under `output_dir`.
+///
+/// Each image with regions gets a `.txt` file at its path relative to `directory` (sub-folders
+/// are recreated) with one line per box; polygons are exported as their bounding box and points
+/// are skipped. `classes.txt` lists the class names, line N naming class N. With `annotator`
+/// set, only that annotator's regions are exported; otherwise the regions of every annotator.
+///
+/// Images that would get the same label file (e.g. `a.jpg` and `a.png`, or two images outside
+/// of `directory` with the same name) or one named like `classes.txt` fail the export with the
+/// `conflict` code, listing them, before anything is written.
+///
+/// Runs on the worker pool; a `request_id` makes it cancellable.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref(), output_dir = %output_dir), err(Debug))]
+pub async fn export_yolo(
+    directory: String,
+    filename: Option<String>,
+    annotator: Option<String>,
+    output_dir: String,
+    request_id: Option<String>,
+) -> Result<DetectionExport, HitoError> {
+    let cancel = Request::start(request_id);
+    run_blocking(move || {
+        let output_dir = scope::check(&output_dir)?;
+        let hito_path = get_hito_file_path(&directory, filename.as_deref())?;
+        let data = read_hito_file(&hito_path)?;
+        let classes = detection_classes(&data);
+        let mut report = DetectionExport { image_count: 0, annotation_count: 0, skipped_points: 0, skipped_images: Vec::new() };
+
+        let mut labels = Vec::new();
+        for image in detection_images(&data, &classes, annotator.as_deref()) {
+            cancel.token().check()?;
+            let (lines, skipped_points) = yolo_lines(&image.regions);
+            report.skipped_points += skipped_points;
+            if lines.is_empty() {
+                continue;
+            }
+            report.image_count += 1;
+            report.annotation_count += image.regions.len() - skipped_points;
+            labels.push((yolo_label_path(&output_dir, image.path, Path::new(&directory)), image.path, lines));
+        }
+
+        let classes_path = output_dir.join("classes.txt");
+        let targets: Vec<(PathBuf, &str)> = labels.iter().map(|(label_path, image_path, _)| (label_path.clone(), *image_path)).collect();
+        let collisions = yolo_collisions(&targets, &classes_path);
+        if !collisions.is_empty() {
+            let groups: Vec<String> = collisions.iter().map(|images| images.join(", ")).collect();
+            return Err(HitoError::new(
+                ErrorCode::Conflict,
+                format!("Images would overwrite each other's YOLO labels or classes.txt: {}", groups.join("; ")),
+            )
+            .with_path(&output_dir));
+        }
+
+        fs::create_dir_all(&output_dir).map_err(|e| HitoError::io("Failed to create directory", &output_dir, e))?;
+        let names: String = classes.iter().map(|(_, name)| format!("{}\n", name)).collect();
+        fs::write(&classes_path, names).map_err(|e| HitoError::io("Failed to write YOLO classes", &classes_path, e))?;
+
+        for (label_path, _, lines) in labels {
+            cancel.token().check()?;
+            if let Some(parent) = label_path.parent() {
+                fs::create_dir_all(parent).map_err(|e| HitoError::io("Failed to create directory", parent, e))?;
+            }
+            fs::write(&label_path, lines).map_err(|e| HitoError::io("Failed to write YOLO labels", &label_path, e))?;
+        }
+        Ok(report)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scope::granted_temp_dir;
+    use crate::{add_region, save_hito_config, set_image_note, CategoryData};
+    use tauri::async_runtime::block_on;
+
+    fn assignment(category_id: &str) -> CategoryAssignment {
+        CategoryAssignment {
+            category_id: category_id.to_string(),
+            assigned_at: "2024-01-01T10:00:00Z".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_export_includes_notes() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        let categories = vec![CategoryData {
+            id: "cat".to_string(),
+            name: "Cat".to_string(),
+            color: "#000000".to_string(),
+            mutually_exclusive_with: None,
+        }];
+        save_hito_config(
+            directory.clone(),
+            vec![("/a.jpg".to_string(), vec![assignment("cat"), assignment("dog")])],
+            None,
+            Some(categories),
+            None,
+            Some("alice".to_string()),
+        )
+        .unwrap();
+        set_image_note(directory.clone(), None, "/a.jpg".to_string(), "blurry, but usable".to_string(), Some("alice".to_string()))
+            .unwrap();
+        set_image_note(directory.clone(), None, "/a.jpg".to_string(), "duplicate?".to_string(), Some("bob".to_string())).unwrap();
+        set_image_note(directory.clone(), None, "/b.jpg".to_string(), "no labels yet".to_string(), None).unwrap();
+
+        let output = temp_dir.path().join("labels.csv");
+        let count = block_on(export_labels_csv(directory.clone(), None, None, output.to_str().unwrap().to_string(), None)).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "image_path,category_ids,category_names,notes\n\
+             /a.jpg,cat;dog,Cat;dog,\"alice: blurry, but usable | bob: duplicate?\"\n\
+             /b.jpg,,,no labels yet\n"
+        );
+
+        let count = block_on(export_labels_csv(directory, None, Some("bob".to_string()), output.to_str().unwrap().to_string(), None)).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "image_path,category_ids,category_names,notes\n/a.jpg,,,bob: duplicate?\n"
+        );
+    }
+
+    /// Header of a PNG of the given size, enough for reading its dimensions.
+    fn png_header(width: u32, height: u32) -> Vec<u8> {
+        let mut png = vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44, 0x52];
+        png.extend(width.to_be_bytes());
+        png.extend(height.to_be_bytes());
+        png.extend([0x08, 0x02, 0x00, 0x00, 0x00, 0x90, 0x77, 0x53, 0xDE]);
+        png
+    }
+
+    #[test]
+    fn test_detection_exports() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        let image = temp_dir.path().join("sub").join("a.png");
+        fs::write(&image, png_header(200, 100)).unwrap();
+        let image = image.to_str().unwrap().to_string();
+        let missing = temp_dir.path().join("missing.png").to_str().unwrap().to_string();
+
+        let region = |path: &str, category_id: &str, geometry: Geometry| {
+            add_region(directory.clone(), None, path.to_string(), category_id.to_string(), geometry, None).unwrap();
+        };
+        region(&image, "cat", Geometry::Bbox { x: 0.1, y: 0.2, width: 0.5, height: 0.5 });
+        region(&image, "dog", Geometry::Polygon { points: vec![[0.0, 0.0], [0.5, 0.0], [0.5, 1.0]] });
+        region(&image, "dog", Geometry::Point { x: 0.5, y: 0.5 });
+        region(&missing, "cat", Geometry::Bbox { x: 0.0, y: 0.0, width: 1.0, height: 1.0 });
+
+        let output = temp_dir.path().join("coco.json").to_str().unwrap().to_string();
+        let report = block_on(export_coco(directory.clone(), None, None, output.clone(), None)).unwrap();
+        assert_eq!((report.image_count, report.annotation_count, report.skipped_points), (1, 2, 1));
+        assert_eq!(report.skipped_images, vec![missing.clone()]);
+
+        let coco: serde_json::Value = serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(coco["images"][0]["file_name"], "sub/a.png");
+        assert_eq!((coco["images"][0]["width"].as_u64(), coco["images"][0]["height"].as_u64()), (Some(200), Some(100)));
+        assert_eq!(coco["categories"][0]["name"], "cat");
+        assert_eq!(coco["categories"][1]["name"], "dog");
+        let boxed = &coco["annotations"][0];
+        assert_eq!(boxed["category_id"], 1);
+        let bbox: Vec<f64> = serde_json::from_value(boxed["bbox"].clone()).unwrap();
+        assert!(bbox.iter().zip([20.0, 20.0, 100.0, 50.0]).all(|(a, b)| (a - b).abs() < 1e-9));
+        let polygon = &coco["annotations"][1];
+        assert_eq!(polygon["category_id"], 2);
+        assert_eq!(polygon["segmentation"][0], serde_json::json!([0.0, 0.0, 100.0, 0.0, 100.0, 100.0]));
+        assert_eq!(polygon["area"], 5000.0);
+
+        let yolo_dir = temp_dir.path().join("yolo");
+        let report = block_on(export_yolo(directory, None, None, yolo_dir.to_str().unwrap().to_string(), None)).unwrap();
+        assert_eq!((report.image_count, report.annotation_count, report.skipped_points), (2, 3, 1));
+        assert_eq!(fs::read_to_string(yolo_dir.join("classes.txt")).unwrap(), "cat\ndog\n");
+        assert_eq!(
+            fs::read_to_string(yolo_dir.join("sub").join("a.txt")).unwrap(),
+            "0 0.350000 0.450000 0.500000 0.500000\n1 0.250000 0.500000 0.500000 1.000000\n"
+        );
+        assert_eq!(fs::read_to_string(yolo_dir.join("missing.txt")).unwrap(), "0 0.500000 0.500000 1.000000 1.000000\n");
+    }
+
+    #[test]
+    fn test_yolo_refuses_colliding_labels() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        let image = |name: &str| temp_dir.path().join(name).to_str().unwrap().to_string();
+        let label = |filename: &str, name: &str| {
+            let bbox = Geometry::Bbox { x: 0.0, y: 0.0, width: 1.0, height: 1.0 };
+            add_region(directory.clone(), Some(filename.to_string()), image(name), "cat".to_string(), bbox, None).unwrap();
+        };
+        let export = |filename: &str| {
+            let output = temp_dir.path().join(filename).with_extension("yolo");
+            let result = block_on(export_yolo(
+                directory.clone(),
+                Some(filename.to_string()),
+                None,
+                output.to_str().unwrap().to_string(),
+                None,
+            ));
+            (result, output)
+        };
+
+        label("unique.json", "a.jpg");
+        label("unique.json", "b.jpg");
+        assert!(export("unique.json").0.is_ok());
+
+        label("stems.json", "a.jpg");
+        label("stems.json", "a.png");
+        let (result, output) = export("stems.json");
+        let error = result.err().unwrap();
+        assert_eq!(error.code, ErrorCode::Conflict);
+        assert!(error.message.contains(&image("a.jpg")) && error.message.contains(&image("a.png")));
+        assert!(!output.exists());
+
+        label("classes.json", "Classes.jpg");
+        assert_eq!(export("classes.json").0.err().unwrap().code, ErrorCode::Conflict);
+    }
+}
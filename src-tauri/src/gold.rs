@@ -0,0 +1,268 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::HitoError;
+use crate::{load_hito_config, scope};
+
+/// Known-answer images of a directory, stored in `.hito-gold.json` next to the data file.
+#[derive(Serialize, Deserialize, Default)]
+struct GoldFile {
+    images: Vec<(String, Vec<String>)>, // image path -> expected category IDs
+}
+
+#[derive(Serialize)]
+pub struct GoldCategoryScore {
+    category_id: String,
+    true_positives: usize,
+    false_positives: usize,
+    false_negatives: usize,
+    precision: Option<f64>, // None when the category was never assigned on gold images
+    recall: Option<f64>,    // None when the category is never expected
+}
+
+#[derive(Serialize)]
+pub struct GoldMiss {
+    image_path: String,
+    expected: Vec<String>,
+    actual: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct GoldScore {
+    gold_count: usize,
+    scored_count: usize, // Gold images the annotator labelled
+    correct_count: usize,
+    accuracy: Option<f64>, // correct / scored, None when nothing was scored
+    categories: Vec<GoldCategoryScore>,
+    misses: Vec<GoldMiss>,
+    unlabelled: Vec<String>, // Gold images the annotator has not labelled yet
+}
+
+fn get_gold_file_path(directory: &str) -> Result<PathBuf, HitoError> {
+    // Canonical like `get_hito_file_path`, so every spelling of a directory shares one file and lock
+    Ok(scope::check(directory)?.join(".hito-gold.json"))
+}
+
+fn read_gold_file(gold_path: &Path) -> Result<GoldFile, HitoError> {
+    if !gold_path.exists() {
+        return Ok(GoldFile::default());
+    }
+
+    let content = fs::read_to_string(gold_path)
+        .map_err(|e| HitoError::io("Failed to read gold set file", gold_path, e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| HitoError::invalid_data(format!("Failed to parse gold set file: {}", e)).with_path(gold_path))
+}
+
+/// Declare the gold set of a directory (image path -> expected category IDs).
+///
+/// An empty list removes the gold set file.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, images = images.len()), err(Debug))]
+pub fn save_gold_set(directory: String, images: Vec<(String, Vec<String>)>) -> Result<(), HitoError> {
+    let gold_path = get_gold_file_path(&directory)?;
+
+    if images.is_empty() {
+        if gold_path.exists() {
+            fs::remove_file(&gold_path)
+                .map_err(|e| HitoError::io("Failed to remove gold set file", &gold_path, e))?;
+        }
+        return Ok(());
+    }
+
+    let json_content = serde_json::to_string_pretty(&GoldFile { images })
+        .map_err(|e| HitoError::internal(format!("Failed to serialize gold set: {}", e)))?;
+    fs::write(&gold_path, json_content)
+        .map_err(|e| HitoError::io("Failed to write gold set file", &gold_path, e))
+}
+
+/// Load the gold set of a directory, empty if none has been declared.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory), err(Debug))]
+pub fn load_gold_set(directory: String) -> Result<Vec<(String, Vec<String>)>, HitoError> {
+    Ok(read_gold_file(&get_gold_file_path(&directory)?)?.images)
+}
+
+/// Compare labels against the gold set.
+///
+/// Gold images without any label are reported as `unlabelled` and left out of the scores.
+fn score(gold: &[(String, Vec<String>)], labels: &BTreeMap<String, BTreeSet<String>>) -> GoldScore {
+    let mut counts: BTreeMap<String, (usize, usize, usize)> = BTreeMap::new();
+    let mut misses = Vec::new();
+    let mut unlabelled = Vec::new();
+    let mut scored_count = 0;
+
+    for (path, expected) in gold {
+        let expected: BTreeSet<String> = expected.iter().cloned().collect();
+        let Some(actual) = labels.get(path).filter(|actual| !actual.is_empty()) else {
+            unlabelled.push(path.clone());
+            continue;
+        };
+        scored_count += 1;
+
+        for category_id in expected.union(actual) {
+            let entry = counts.entry(category_id.clone()).or_default();
+            match (expected.contains(category_id), actual.contains(category_id)) {
+                (true, true) => entry.0 += 1,
+                (false, true) => entry.1 += 1,
+                (true, false) => entry.2 += 1,
+                (false, false) => {}
+            }
+        }
+
+        if expected != *actual {
+            misses.push(GoldMiss {
+                image_path: path.clone(),
+                expected: expected.into_iter().collect(),
+                actual: actual.iter().cloned().collect(),
+            });
+        }
+    }
+
+    let ratio = |num: usize, den: usize| (den > 0).then(|| num as f64 / den as f64);
+    let correct_count = scored_count - misses.len();
+
+    GoldScore {
+        gold_count: gold.len(),
+        scored_count,
+        correct_count,
+        accuracy: ratio(correct_count, scored_count),
+        categories: counts
+            .into_iter()
+            .map(|(category_id, (tp, fp, fn_))| GoldCategoryScore {
+                category_id,
+                true_positives: tp,
+                false_positives: fp,
+                false_negatives: fn_,
+                precision: ratio(tp, tp + fp),
+                recall: ratio(tp, tp + fn_),
+            })
+            .collect(),
+        misses,
+        unlabelled,
+    }
+}
+
+/// Score an annotator's `image_categories` against the directory's gold set.
+///
+/// Labels are read from `filename` (default `.hito.json`); when `annotator` is given only that
+/// annotator's assignments are scored.
+///
+/// # Returns
+///
+/// A `GoldScore` with accuracy (exact match of the category set), per-category precision/recall,
+/// and the list of misses.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref()), err(Debug))]
+pub fn score_gold_set(
+    directory: String,
+    filename: Option<String>,
+    annotator: Option<String>,
+) -> Result<GoldScore, HitoError> {
+    let gold = read_gold_file(&get_gold_file_path(&directory)?)?.images;
+    if gold.is_empty() {
+        return Err(HitoError::not_found("No gold set declared for this directory").with_path(&directory));
+    }
+
+    let data = load_hito_config(directory, filename, annotator)?;
+    let labels = data
+        .image_categories
+        .into_iter()
+        .map(|(path, assignments)| (path, assignments.into_iter().map(|a| a.category_id).collect()))
+        .collect();
+
+    Ok(score(&gold, &labels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{save_hito_config, CategoryAssignment};
+    use crate::scope::granted_temp_dir;
+
+    fn gold(images: &[(&str, &[&str])]) -> Vec<(String, Vec<String>)> {
+        images
+            .iter()
+            .map(|(path, categories)| (path.to_string(), categories.iter().map(|c| c.to_string()).collect()))
+            .collect()
+    }
+
+    fn gold_labels(images: &[(&str, &[&str])]) -> BTreeMap<String, BTreeSet<String>> {
+        images
+            .iter()
+            .map(|(path, categories)| (path.to_string(), categories.iter().map(|c| c.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_score_reports_accuracy_precision_recall_and_misses() {
+        let gold = gold(&[("/a.jpg", &["cat"]), ("/b.jpg", &["dog"]), ("/c.jpg", &["cat"]), ("/d.jpg", &["dog"])]);
+        let labels = gold_labels(&[("/a.jpg", &["cat"]), ("/b.jpg", &["cat"]), ("/c.jpg", &["cat"])]);
+
+        let result = score(&gold, &labels);
+
+        assert_eq!(result.gold_count, 4);
+        assert_eq!(result.scored_count, 3);
+        assert_eq!(result.correct_count, 2);
+        assert!((result.accuracy.unwrap() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(result.unlabelled, vec!["/d.jpg"]);
+        assert_eq!(result.misses.len(), 1);
+        assert_eq!(result.misses[0].image_path, "/b.jpg");
+        assert_eq!(result.misses[0].expected, vec!["dog"]);
+        assert_eq!(result.misses[0].actual, vec!["cat"]);
+
+        let cat = result.categories.iter().find(|c| c.category_id == "cat").unwrap();
+        assert!((cat.precision.unwrap() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(cat.recall, Some(1.0));
+        let dog = result.categories.iter().find(|c| c.category_id == "dog").unwrap();
+        assert_eq!(dog.precision, None);
+        assert_eq!(dog.recall, Some(0.0));
+    }
+
+    #[test]
+    fn test_save_load_and_score_gold_set() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+
+        assert!(score_gold_set(directory.clone(), None, None).is_err());
+
+        save_gold_set(directory.clone(), gold(&[("/a.jpg", &["cat"])])).unwrap();
+        assert_eq!(load_gold_set(directory.clone()).unwrap().len(), 1);
+
+        save_hito_config(
+            directory.clone(),
+            vec![(
+                "/a.jpg".to_string(),
+                vec![CategoryAssignment {
+                    category_id: "cat".to_string(),
+                    assigned_at: "2024-01-01T00:00:00Z".to_string(),
+                    ..Default::default()
+                }],
+            )],
+            None,
+            None,
+            None,
+            Some("alice".to_string()),
+        )
+        .unwrap();
+
+        let result = score_gold_set(directory.clone(), None, Some("alice".to_string())).unwrap();
+        assert_eq!(result.accuracy, Some(1.0));
+
+        let result = score_gold_set(directory.clone(), None, Some("bob".to_string())).unwrap();
+        assert_eq!(result.scored_count, 0);
+        assert_eq!(result.accuracy, None);
+
+        // Another spelling of the directory reads the same gold set
+        let sub = temp_dir.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        let dotted = format!("{}/..", sub.to_str().unwrap());
+        assert_eq!(load_gold_set(dotted).unwrap().len(), 1);
+
+        save_gold_set(directory.clone(), Vec::new()).unwrap();
+        assert!(load_gold_set(directory).unwrap().is_empty());
+    }
+}
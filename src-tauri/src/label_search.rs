@@ -0,0 +1,319 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::error::HitoError;
+use crate::requests::{run_blocking, CancelToken, Request};
+use crate::{load_app_data, read_hito_file, scope, AppData, CategoryData};
+
+const DEFAULT_DATA_FILE_NAME: &str = ".hito.json";
+
+/// What to look for; every given criterion has to match. Matching is case-insensitive.
+struct LabelSearch {
+    category: Option<String>, // Category name or ID, matched exactly
+    note: Option<String>, // Substring of any note of the image
+    filename: Option<String>, // Substring of the image's file name
+}
+
+#[derive(Serialize)]
+pub struct LabelHit {
+    image_path: String,
+    categories: Vec<String>, // Names of all categories of the image (IDs when the name is unknown)
+    notes: Vec<String>, // Note texts of the image
+}
+
+#[derive(Serialize)]
+pub struct DirectoryHits {
+    directory: String,
+    data_file: String,
+    hits: Vec<LabelHit>,
+}
+
+#[derive(Serialize)]
+pub struct SkippedDataFile {
+    data_file: String,
+    error: HitoError,
+}
+
+#[derive(Serialize)]
+pub struct LabelSearchResult {
+    directories: Vec<DirectoryHits>, // Only directories with at least one hit
+    searched: usize, // Number of data files read
+    skipped: Vec<SkippedDataFile>, // Data files that could not be read
+}
+
+/// Default-named data files anywhere under `root`, checking `cancel` between directories.
+///
+/// Hidden directories and symlinked directories are not entered.
+pub(crate) fn find_data_files(root: &Path, found: &mut Vec<PathBuf>, cancel: &CancelToken) -> Result<(), HitoError> {
+    cancel.check()?;
+    let Ok(entries) = fs::read_dir(root) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if file_type.is_dir() && !name.starts_with('.') {
+            find_data_files(&path, found, cancel)?;
+        } else if file_type.is_file() && name == DEFAULT_DATA_FILE_NAME {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Search data files, given as `(directory, data file)` pairs, for images matching `search`.
+fn search_data_files(
+    data_files: &[(String, PathBuf)],
+    search: &LabelSearch,
+    fallback_categories: &[CategoryData],
+    cancel: &CancelToken,
+) -> Result<LabelSearchResult, HitoError> {
+    let category = search.category.as_deref().map(str::to_lowercase);
+    let note = search.note.as_deref().map(str::to_lowercase);
+    let filename = search.filename.as_deref().map(str::to_lowercase);
+
+    let mut result = LabelSearchResult { directories: Vec::new(), searched: 0, skipped: Vec::new() };
+    for (directory, data_file) in data_files {
+        cancel.check()?;
+        // Saved locations are granted on startup (see `scope::grant_saved`); others stay out of reach
+        let data = match scope::check(data_file).and_then(|_| read_hito_file(data_file)) {
+            Ok(data) => data,
+            Err(error) => {
+                result.skipped.push(SkippedDataFile { data_file: data_file.display().to_string(), error });
+                continue;
+            }
+        };
+        result.searched += 1;
+
+        let category_names: HashMap<&str, &str> = data
+            .categories
+            .as_deref()
+            .unwrap_or(fallback_categories)
+            .iter()
+            .map(|c| (c.id.as_str(), c.name.as_str()))
+            .collect();
+
+        // Images keyed by path: (category IDs, note texts)
+        let mut images: BTreeMap<&str, (Vec<&str>, Vec<&str>)> = BTreeMap::new();
+        for (path, assignments) in &data.image_categories {
+            let entry = images.entry(path.as_str()).or_default();
+            for assignment in assignments {
+                if !entry.0.contains(&assignment.category_id.as_str()) {
+                    entry.0.push(&assignment.category_id);
+                }
+            }
+        }
+        for (path, notes) in data.image_notes.iter().flatten() {
+            images.entry(path.as_str()).or_default().1.extend(notes.iter().map(|n| n.text.as_str()));
+        }
+
+        let hits: Vec<LabelHit> = images
+            .into_iter()
+            .filter(|(path, (category_ids, notes))| {
+                category.as_ref().is_none_or(|wanted| {
+                    category_ids.iter().any(|id| {
+                        id.to_lowercase() == *wanted
+                            || category_names.get(id).is_some_and(|name| name.to_lowercase() == *wanted)
+                    })
+                }) && note.as_ref().is_none_or(|wanted| notes.iter().any(|text| text.to_lowercase().contains(wanted)))
+                    && filename.as_ref().is_none_or(|wanted| {
+                        Path::new(path)
+                            .file_name()
+                            .is_some_and(|name| name.to_string_lossy().to_lowercase().contains(wanted))
+                    })
+            })
+            .map(|(path, (category_ids, notes))| LabelHit {
+                image_path: path.to_string(),
+                categories: category_ids
+                    .iter()
+                    .map(|id| category_names.get(id).unwrap_or(id).to_string())
+                    .collect(),
+                notes: notes.into_iter().map(str::to_string).collect(),
+            })
+            .collect();
+
+        if !hits.is_empty() {
+            result.directories.push(DirectoryHits {
+                directory: directory.clone(),
+                data_file: data_file.display().to_string(),
+                hits,
+            });
+        }
+    }
+    Ok(result)
+}
+
+/// Search every known data file for images by category name, note text or file name.
+///
+/// Searches the data files registered with `save_data_file_path` plus every `.hito.json` found
+/// under the search roots (see `set_search_roots`). At least one criterion must be given; all given
+/// criteria have to match.
+///
+/// Runs on the worker pool, as it walks the search roots; a `request_id` makes it cancellable.
+///
+/// # Returns
+///
+/// Hits grouped by directory, and the data files that could not be read.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(filename = filename.as_deref()), err(Debug))]
+pub async fn search_labels(
+    app: AppHandle,
+    category: Option<String>,
+    note: Option<String>,
+    filename: Option<String>,
+    request_id: Option<String>,
+) -> Result<LabelSearchResult, HitoError> {
+    let non_empty = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let search = LabelSearch { category: non_empty(category), note: non_empty(note), filename: non_empty(filename) };
+    if search.category.is_none() && search.note.is_none() && search.filename.is_none() {
+        return Err(HitoError::invalid_input("Search needs a category, note or filename"));
+    }
+
+    let app_data = load_app_data(app)?;
+    let cancel = Request::start(request_id);
+    run_blocking(move || search_known_data_files(app_data, &search, cancel.token())).await
+}
+
+/// Blocking part of `search_labels`: collects the registered data files and those under the
+/// search roots of `app_data`, then searches them.
+fn search_known_data_files(
+    app_data: AppData,
+    search: &LabelSearch,
+    cancel: &CancelToken,
+) -> Result<LabelSearchResult, HitoError> {
+    let mut data_files: Vec<(String, PathBuf)> = app_data
+        .data_file_paths
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(directory, data_file)| (directory, PathBuf::from(data_file)))
+        .collect();
+    for root in app_data.search_roots.unwrap_or_default() {
+        if scope::check(&root).is_err() {
+            continue;
+        }
+        let mut found = Vec::new();
+        find_data_files(Path::new(&root), &mut found, cancel)?;
+        data_files.extend(found.into_iter().filter_map(|data_file| {
+            let directory = data_file.parent()?.to_str()?.to_string();
+            Some((directory, data_file))
+        }));
+    }
+
+    // The same file may be both registered and under a root
+    let mut seen = HashSet::new();
+    data_files.retain(|(_, data_file)| seen.insert(fs::canonicalize(data_file).unwrap_or_else(|_| data_file.clone())));
+    data_files.sort();
+
+    search_data_files(&data_files, search, &app_data.categories, cancel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::requests::cancel_request;
+    use crate::{save_hito_config, set_image_note, CategoryAssignment};
+    use crate::scope::granted_temp_dir;
+
+    fn category(id: &str, name: &str) -> CategoryData {
+        CategoryData {
+            id: id.to_string(),
+            name: name.to_string(),
+            color: "#000000".to_string(),
+            mutually_exclusive_with: None,
+        }
+    }
+
+    fn label(directory: &Path, image: &str, category_id: &str, categories: Option<Vec<CategoryData>>) -> String {
+        let image_path = directory.join(image).to_str().unwrap().to_string();
+        let assignment = CategoryAssignment {
+            category_id: category_id.to_string(),
+            assigned_at: "2024-01-01T00:00:00Z".to_string(),
+            ..Default::default()
+        };
+        save_hito_config(
+            directory.to_str().unwrap().to_string(),
+            vec![(image_path.clone(), vec![assignment])],
+            None,
+            categories,
+            None,
+            None,
+        )
+        .unwrap();
+        image_path
+    }
+
+    fn search(category: Option<&str>, note: Option<&str>, filename: Option<&str>) -> LabelSearch {
+        LabelSearch {
+            category: category.map(str::to_string),
+            note: note.map(str::to_string),
+            filename: filename.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_find_data_files_under_root() {
+        let temp_dir = granted_temp_dir();
+        let nested = temp_dir.path().join("a/b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir_all(temp_dir.path().join(".cache")).unwrap();
+        fs::write(nested.join(".hito.json"), "{}").unwrap();
+        fs::write(temp_dir.path().join(".cache/.hito.json"), "{}").unwrap();
+        fs::write(temp_dir.path().join("a/other.json"), "{}").unwrap();
+
+        let mut found = Vec::new();
+        find_data_files(temp_dir.path(), &mut found, &CancelToken::default()).unwrap();
+        assert_eq!(found, vec![nested.join(".hito.json")]);
+
+        let request = Request::start(Some("find-data-files-test".to_string()));
+        assert!(cancel_request("find-data-files-test".to_string()));
+        let error = find_data_files(temp_dir.path(), &mut Vec::new(), request.token()).err().unwrap();
+        assert_eq!(error.code, ErrorCode::Cancelled);
+    }
+
+    #[test]
+    fn test_search_groups_hits_by_directory() {
+        let temp_dir = granted_temp_dir();
+        let one = temp_dir.path().join("one");
+        let two = temp_dir.path().join("two");
+        fs::create_dir_all(&one).unwrap();
+        fs::create_dir_all(&two).unwrap();
+
+        label(&one, "part_1.jpg", "c1", Some(vec![category("c1", "Defect")]));
+        // No categories in the file: names come from the global categories
+        label(&two, "part_2.jpg", "g1", None);
+        let noted = two.join("part_3.jpg").to_str().unwrap().to_string();
+        set_image_note(two.to_str().unwrap().to_string(), None, noted.clone(), "Scratch near edge".to_string(), None)
+            .unwrap();
+
+        let data_files = vec![
+            (one.to_str().unwrap().to_string(), one.join(".hito.json")),
+            (two.to_str().unwrap().to_string(), two.join(".hito.json")),
+            ("missing".to_string(), temp_dir.path().join("missing/.hito.json")),
+        ];
+        let global = vec![category("g1", "defect")];
+
+        let result = search_data_files(&data_files, &search(Some("DEFECT"), None, None), &global, &CancelToken::default()).unwrap();
+        assert_eq!(result.searched, 3);
+        assert_eq!(result.directories.len(), 2);
+        assert_eq!(result.directories[0].hits[0].categories, vec!["Defect"]);
+        assert_eq!(result.directories[1].hits[0].categories, vec!["defect"]);
+
+        let result = search_data_files(&data_files, &search(None, Some("scratch"), None), &global, &CancelToken::default()).unwrap();
+        assert_eq!(result.directories.len(), 1);
+        assert_eq!(result.directories[0].hits[0].image_path, noted);
+        assert!(result.directories[0].hits[0].categories.is_empty());
+
+        let result = search_data_files(&data_files, &search(Some("defect"), None, Some("part_2")), &global, &CancelToken::default()).unwrap();
+        assert_eq!(result.directories.len(), 1);
+        assert_eq!(result.directories[0].directory, two.to_str().unwrap());
+    }
+}
@@ -1,22 +1,53 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::UNIX_EPOCH;
-use std::collections::HashMap;
-use std::sync::{Mutex, OnceLock};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
 use base64::{Engine as _, engine::general_purpose};
 use serde::{Deserialize, Serialize};
 use chrono;
 use tauri::{AppHandle, Manager};
 
+mod agreement;
+mod batches;
+mod consensus;
+mod dates;
+mod diagnostics;
+mod directory_session;
+mod error;
+mod events;
+mod export;
+mod gold;
+mod label_search;
+mod metadata_index;
+mod name_filter;
+mod natural_sort;
+mod presets;
+mod query;
+mod random;
+mod requests;
+mod scope;
+mod sessions;
+mod view_state;
+mod workspaces;
+
+use error::{ErrorCode, HitoError};
+use requests::{run_blocking, CancelToken, Request};
+
 // Type alias for data file path mapping (directory -> data file path)
 type DataFileMap = HashMap<String, String>;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
 struct ImagePath {
     path: String,
     size: Option<u64>, // File size in bytes
     created_at: Option<String>, // ISO 8601 datetime string
+    #[serde(default)]
+    modified_at: Option<String>, // ISO 8601 datetime string
+    #[serde(default)]
+    captured_at: Option<String>, // EXIF capture time as ISO 8601, filled in by sort_images when filtered on
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
@@ -42,7 +73,7 @@ struct DirectoryContents {
 ///
 /// # Returns
 ///
-/// `Ok(String)` containing the parent directory path on success; `Err(String)` with one of:
+/// `Ok(String)` containing the parent directory path on success; an `invalid_input` `HitoError` with one of:
 /// - `"File has no parent directory"` if the path has no parent.
 /// - `"Failed to convert path to string"` if the parent path cannot be converted to UTF-8.
 ///
@@ -54,16 +85,51 @@ struct DirectoryContents {
 /// // Example: get_parent_directory(PathBuf::from("/tmp/project/src/main.rs"))
 /// ```
 #[tauri::command]
-fn get_parent_directory(file_path: PathBuf) -> Result<String, String> {
+#[tracing::instrument(skip_all, fields(file_path = %file_path.display()), err(Debug))]
+fn get_parent_directory(file_path: PathBuf) -> Result<String, HitoError> {
     if let Some(parent) = file_path.parent() {
         if let Some(parent_str) = parent.to_str() {
             Ok(parent_str.to_string())
         } else {
-            Err("Failed to convert path to string".to_string())
+            Err(HitoError::invalid_input("Failed to convert path to string").with_path(&file_path))
         }
     } else {
-        Err("File has no parent directory".to_string())
+        Err(HitoError::invalid_input("File has no parent directory").with_path(&file_path))
+    }
+}
+
+/// Check that `image_path` is in scope, exists and is a file.
+fn check_is_file(image_path: &str) -> Result<(), HitoError> {
+    let file_path = Path::new(image_path);
+    scope::check(file_path)?;
+    
+    if !file_path.exists() {
+        return Err(HitoError::not_found(format!("Image does not exist: {}", image_path)).with_path(image_path));
+    }
+    
+    if !file_path.is_file() {
+        return Err(HitoError::invalid_input(format!("Path is not a file: {}", image_path)).with_path(image_path));
+    }
+    
+    Ok(())
+}
+
+/// Check that `destination_dir` is in scope, exists and is a directory.
+fn check_destination_dir(destination_dir: &str) -> Result<(), HitoError> {
+    let dest_dir = Path::new(destination_dir);
+    scope::check(dest_dir)?;
+    
+    if !dest_dir.exists() {
+        return Err(HitoError::not_found(format!("Destination directory does not exist: {}", destination_dir))
+            .with_path(destination_dir));
+    }
+    
+    if !dest_dir.is_dir() {
+        return Err(HitoError::invalid_input(format!("Destination is not a directory: {}", destination_dir))
+            .with_path(destination_dir));
     }
+    
+    Ok(())
 }
 
 /// Collects directory and image file paths from a directory and returns them separately.
@@ -72,20 +138,71 @@ fn get_parent_directory(file_path: PathBuf) -> Result<String, String> {
 /// then images. Image files must have common image extensions (`jpg`, `jpeg`, `png`, `gif`,
 /// `bmp`, `webp`, `svg`, `ico`). Results are sorted by path.
 ///
+/// If `data_file_path` points to a task batch file (one with `batch_images`), only the images of
+/// that batch are returned. A data file that cannot be read is logged and all images are returned.
+///
 /// # Returns
 ///
-/// `Ok(DirectoryContents)` with directories and images when successful; `Err(String)` with an
-/// explanatory message if the path does not exist, is not a directory, or cannot be read.
+/// `Ok(DirectoryContents)` with directories and images when successful; a `HitoError` if the path
+/// does not exist (`not_found`), is not a directory (`invalid_input`), or cannot be read.
+///
+/// The scan runs on the worker pool. With a `request_id`, it can be stopped with `cancel_request`
+/// (e.g. when navigating away), and then fails with the `cancelled` code.
+///
+/// If the directory is in the metadata index, images whose size and modification time still match
+/// their indexed entry come with the indexed EXIF capture time, so sorting or filtering by it does
+/// not read those files again. Changed and new images are listed without one, as usual.
 #[tauri::command]
-fn list_images(path: String) -> Result<DirectoryContents, String> {
+#[tracing::instrument(skip_all, fields(path = %path, data_file_path = data_file_path.as_deref(), request_id = request_id.as_deref()), err(Debug))]
+async fn list_images(
+    app: AppHandle,
+    index: tauri::State<'_, metadata_index::AppIndex>,
+    path: String,
+    data_file_path: Option<String>,
+    request_id: Option<String>,
+) -> Result<DirectoryContents, HitoError> {
+    list_directory(path, data_file_path, request_id, Some((app, index.inner().clone()))).await
+}
+
+/// `list_images`, reusing the metadata of `index` when given.
+async fn list_directory(
+    path: String,
+    data_file_path: Option<String>,
+    request_id: Option<String>,
+    index: Option<(AppHandle, metadata_index::AppIndex)>,
+) -> Result<DirectoryContents, HitoError> {
+    let request = Request::start(request_id);
+    run_blocking(move || {
+        let indexed = index
+            .map(|(app, index)| index.cached_images(&app, &path))
+            .unwrap_or_default();
+        scan_directory(path, data_file_path, &indexed, request.token())
+    })
+    .await
+}
+
+/// Blocking part of `list_images` without the metadata index, checking `cancel` between entries.
+fn read_directory(path: String, data_file_path: Option<String>, cancel: &CancelToken) -> Result<DirectoryContents, HitoError> {
+    scan_directory(path, data_file_path, &HashMap::new(), cancel)
+}
+
+/// List `path`, taking the capture time of unchanged images from `indexed` (keyed by image path).
+/// Every entry is stat'ed at most once.
+fn scan_directory(
+    path: String,
+    data_file_path: Option<String>,
+    indexed: &HashMap<String, ImagePath>,
+    cancel: &CancelToken,
+) -> Result<DirectoryContents, HitoError> {
     let dir_path = Path::new(&path);
+    scope::check(dir_path)?;
     
     if !dir_path.exists() {
-        return Err(format!("Path does not exist: {}", path));
+        return Err(HitoError::not_found(format!("Path does not exist: {}", path)).with_path(&path));
     }
     
     if !dir_path.is_dir() {
-        return Err(format!("Path is not a directory: {}", path));
+        return Err(HitoError::invalid_input(format!("Path is not a directory: {}", path)).with_path(&path));
     }
     
     let image_extensions = ["jpg", "jpeg", "png", "gif", "bmp", "webp", "svg", "ico"];
@@ -95,11 +212,17 @@ fn list_images(path: String) -> Result<DirectoryContents, String> {
     match fs::read_dir(dir_path) {
         Ok(entries) => {
             for entry in entries {
+                cancel.check()?;
                 if let Ok(entry) = entry {
                     let file_path = entry.path();
+                    // The entry type comes with the listing on most platforms; only symlinks need a stat
+                    let (is_dir, is_file) = match entry.file_type() {
+                        Ok(file_type) if !file_type.is_symlink() => (file_type.is_dir(), file_type.is_file()),
+                        _ => (file_path.is_dir(), file_path.is_file()),
+                    };
                     
                     // Check if it's a directory
-                    if file_path.is_dir() {
+                    if is_dir {
                         if let Some(path_str) = file_path.to_str() {
                             // Get creation time if available
                             let created_at = fs::metadata(&file_path)
@@ -124,7 +247,7 @@ fn list_images(path: String) -> Result<DirectoryContents, String> {
                                 created_at,
                             });
                         }
-                    } else if file_path.is_file() {
+                    } else if is_file {
                         // Check if it's an image file
                         if let Some(extension) = file_path.extension() {
                             let ext_str = extension.to_string_lossy().to_lowercase();
@@ -150,10 +273,30 @@ fn list_images(path: String) -> Result<DirectoryContents, String> {
                                             })
                                             .flatten();
                                         
+                                        let modified_at = metadata
+                                            .modified()
+                                            .ok()
+                                            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                                            .and_then(|duration| {
+                                                chrono::DateTime::<chrono::Utc>::from_timestamp(
+                                                    duration.as_secs() as i64,
+                                                    duration.subsec_nanos()
+                                                )
+                                            })
+                                            .map(|dt| dt.to_rfc3339());
+                                        
+                                        // The indexed capture time still holds if the file has not changed since
+                                        let captured_at = indexed
+                                            .get(path_str)
+                                            .filter(|image| image.size == Some(file_size) && image.modified_at == modified_at)
+                                            .and_then(|image| image.captured_at.clone());
+                                        
                                         images.push(ImagePath {
                                             path: path_str.to_string(),
                                             size: Some(file_size),
                                             created_at,
+                                            modified_at,
+                                            captured_at, // Otherwise read from EXIF only when needed (see sort_images)
                                         });
                                     }
                                 }
@@ -166,12 +309,27 @@ fn list_images(path: String) -> Result<DirectoryContents, String> {
             // For now, just keep them in path order as a default
             directories.sort_by(|a, b| a.path.cmp(&b.path));
             images.sort_by(|a, b| a.path.cmp(&b.path));
+            
+            if let Some(data_file_path) = data_file_path {
+                scope::check(&data_file_path)?;
+                // A data file that cannot be read should not hide the directory; it is listed unfiltered
+                match read_hito_file(Path::new(&data_file_path)) {
+                    Ok(data) => {
+                        if let Some(batch_images) = data.batch_images {
+                            let batch: HashSet<String> = batch_images.into_iter().collect();
+                            images.retain(|image| batch.contains(&image.path));
+                        }
+                    }
+                    Err(e) => tracing::warn!(path = %data_file_path, error = ?e, "Failed to read data file, listing all images"),
+                }
+            }
+            
             Ok(DirectoryContents {
                 directories,
                 images,
             })
         }
-        Err(e) => Err(format!("Failed to read directory: {}", e)),
+        Err(e) => Err(HitoError::io("Failed to read directory", &path, e)),
     }
 }
 
@@ -183,23 +341,26 @@ fn list_images(path: String) -> Result<DirectoryContents, String> {
 /// # Returns
 ///
 /// On success, a `String` containing a data URL in the form `data:<mime_type>;base64,<base64_data>`.
-/// On failure, an `Err(String)` describing the error (missing file, not a file, or read error).
+/// On failure, a `HitoError` describing the error (missing file, not a file, or read error).
 ///
 /// # Examples
 ///
 /// This is a Tauri command that must be called from the frontend.
 /// The function returns a data URL string like `"data:image/png;base64,..."` on success.
+///
+/// The file is read on the worker pool; a `request_id` makes the load cancellable, as in `list_images`.
 #[tauri::command]
-fn load_image(image_path: String) -> Result<String, String> {
+#[tracing::instrument(level = "debug", skip_all, fields(image_path = %image_path, request_id = request_id.as_deref()), err(Debug))]
+async fn load_image(image_path: String, request_id: Option<String>) -> Result<String, HitoError> {
+    let request = Request::start(request_id);
+    run_blocking(move || read_image(image_path, request.token())).await
+}
+
+/// Blocking part of `load_image`.
+fn read_image(image_path: String, cancel: &CancelToken) -> Result<String, HitoError> {
+    cancel.check()?;
     let file_path = Path::new(&image_path);
-    
-    if !file_path.exists() {
-        return Err(format!("Image does not exist: {}", image_path));
-    }
-    
-    if !file_path.is_file() {
-        return Err(format!("Path is not a file: {}", image_path));
-    }
+    check_is_file(&image_path)?;
     
     // Determine MIME type from extension
     let mime_type = if let Some(extension) = file_path.extension() {
@@ -220,11 +381,13 @@ fn load_image(image_path: String) -> Result<String, String> {
     
     match fs::read(&file_path) {
         Ok(file_data) => {
+            // Skip the encoding when the view went away during the read
+            cancel.check()?;
             let base64_data = general_purpose::STANDARD.encode(&file_data);
             let data_url = format!("data:{};base64,{}", mime_type, base64_data);
             Ok(data_url)
         }
-        Err(e) => Err(format!("Failed to read image: {}", e)),
+        Err(e) => Err(HitoError::io("Failed to read image", &image_path, e)),
     }
 }
 
@@ -235,23 +398,40 @@ fn load_image(image_path: String) -> Result<String, String> {
 ///
 /// # Returns
 ///
-/// `Ok(())` on success, `Err(String)` with an error message if the file cannot be deleted.
+/// `Ok(())` on success, a `HitoError` if the file cannot be deleted.
+///
+/// The deletion is recorded in the event log of `data_file_path` (default: `.hito.json` next to
+/// the image) when that data file exists.
 #[tauri::command]
-fn delete_image(image_path: String) -> Result<(), String> {
+#[tracing::instrument(skip_all, fields(image_path = %image_path, data_file_path = data_file_path.as_deref()), err(Debug))]
+async fn delete_image(image_path: String, data_file_path: Option<String>, annotator: Option<String>) -> Result<(), HitoError> {
+    run_blocking(move || trash_image(image_path, data_file_path, annotator)).await
+}
+
+/// Blocking part of `delete_image`.
+fn trash_image(image_path: String, data_file_path: Option<String>, annotator: Option<String>) -> Result<(), HitoError> {
     let file_path = Path::new(&image_path);
+    check_is_file(&image_path)?;
+    data_file_path.as_ref().map(scope::check).transpose()?;
     
-    if !file_path.exists() {
-        return Err(format!("Image does not exist: {}", image_path));
+    if let Err(e) = trash::delete(file_path) {
+        return Err(HitoError::new(ErrorCode::Io, format!("Failed to delete image: {}", e)).with_path(&image_path));
     }
-    
-    if !file_path.is_file() {
-        return Err(format!("Path is not a file: {}", image_path));
+    if let Some(directory) = file_path.parent() {
+        directory_session::mark_changed(directory);
     }
     
-    match trash::delete(&file_path) {
-        Ok(_) => Ok(()),
-        Err(e) => Err(format!("Failed to delete image: {}", e)),
+    let event = events::LabelEvent {
+        kind: "delete".to_string(),
+        image_path: Some(image_path.clone()),
+        annotator,
+        ..Default::default()
+    };
+    // The image is already in the trash; a missing log entry must not report the delete as failed
+    if let Err(e) = events::record_file_event(&image_path, data_file_path.as_deref(), event) {
+        tracing::warn!(error = ?e, "Deleted image but failed to record event");
     }
+    Ok(())
 }
 
 /// Copies an image file to a destination directory.
@@ -265,42 +445,33 @@ fn delete_image(image_path: String) -> Result<(), String> {
 ///
 /// # Returns
 ///
-/// `Ok(())` on success, `Err(String)` with an error message if the file cannot be copied.
+/// `Ok(())` on success, a `HitoError` if the file cannot be copied.
 #[tauri::command]
-fn copy_image(image_path: String, destination_dir: String) -> Result<(), String> {
+#[tracing::instrument(skip_all, fields(image_path = %image_path, destination_dir = %destination_dir), err(Debug))]
+async fn copy_image(image_path: String, destination_dir: String) -> Result<(), HitoError> {
+    run_blocking(move || copy_image_file(image_path, destination_dir)).await
+}
+
+/// Blocking part of `copy_image`.
+fn copy_image_file(image_path: String, destination_dir: String) -> Result<(), HitoError> {
     let source_path = Path::new(&image_path);
     let dest_dir = Path::new(&destination_dir);
-    
-    if !source_path.exists() {
-        return Err(format!("Image does not exist: {}", image_path));
-    }
-    
-    if !source_path.is_file() {
-        return Err(format!("Path is not a file: {}", image_path));
-    }
-    
-    if !dest_dir.exists() {
-        return Err(format!("Destination directory does not exist: {}", destination_dir));
-    }
-    
-    if !dest_dir.is_dir() {
-        return Err(format!("Destination is not a directory: {}", destination_dir));
-    }
+    check_is_file(&image_path)?;
+    check_destination_dir(&destination_dir)?;
     
     // Get the filename from the source path
     let filename = match source_path.file_name() {
         Some(name) => name,
-        None => return Err(format!("Failed to get filename from: {}", image_path)),
+        None => return Err(HitoError::invalid_input(format!("Failed to get filename from: {}", image_path)).with_path(&image_path)),
     };
     
     // Construct the destination path
     let dest_path = dest_dir.join(filename);
     
     // Copy the file
-    match fs::copy(source_path, &dest_path) {
-        Ok(_) => Ok(()),
-        Err(e) => Err(format!("Failed to copy image: {}", e)),
-    }
+    fs::copy(source_path, &dest_path).map_err(|e| HitoError::io("Failed to copy image", &dest_path, e))?;
+    directory_session::mark_changed(dest_dir);
+    Ok(())
 }
 
 /// Moves an image file to a destination directory.
@@ -315,39 +486,47 @@ fn copy_image(image_path: String, destination_dir: String) -> Result<(), String>
 ///
 /// # Returns
 ///
-/// `Ok(())` on success, `Err(String)` with an error message if the file cannot be moved.
+/// `Ok(())` on success, a `HitoError` if the file cannot be moved.
+///
+/// The move is recorded in the event log of `data_file_path` (default: `.hito.json` next to the
+/// source image) when that data file exists.
+///
+/// File operations run on the worker pool and are not cancellable, so a move is never left half done.
 #[tauri::command]
-fn move_image(image_path: String, destination_dir: String) -> Result<(), String> {
+#[tracing::instrument(skip_all, fields(image_path = %image_path, destination_dir = %destination_dir, data_file_path = data_file_path.as_deref()), err(Debug))]
+async fn move_image(
+    image_path: String,
+    destination_dir: String,
+    data_file_path: Option<String>,
+    annotator: Option<String>,
+) -> Result<(), HitoError> {
+    run_blocking(move || move_image_file(image_path, destination_dir, data_file_path, annotator)).await
+}
+
+/// Blocking part of `move_image`.
+fn move_image_file(
+    image_path: String,
+    destination_dir: String,
+    data_file_path: Option<String>,
+    annotator: Option<String>,
+) -> Result<(), HitoError> {
     let source_path = Path::new(&image_path);
     let dest_dir = Path::new(&destination_dir);
-    
-    if !source_path.exists() {
-        return Err(format!("Image does not exist: {}", image_path));
-    }
-    
-    if !source_path.is_file() {
-        return Err(format!("Path is not a file: {}", image_path));
-    }
-    
-    if !dest_dir.exists() {
-        return Err(format!("Destination directory does not exist: {}", destination_dir));
-    }
-    
-    if !dest_dir.is_dir() {
-        return Err(format!("Destination is not a directory: {}", destination_dir));
-    }
+    check_is_file(&image_path)?;
+    check_destination_dir(&destination_dir)?;
+    data_file_path.as_ref().map(scope::check).transpose()?;
     
     // Get the filename from the source path
     let filename = match source_path.file_name() {
         Some(name) => name,
-        None => return Err(format!("Failed to get filename from: {}", image_path)),
+        None => return Err(HitoError::invalid_input(format!("Failed to get filename from: {}", image_path)).with_path(&image_path)),
     };
     
     // Construct the destination path
     let dest_path = dest_dir.join(filename);
     
     // Move the file (rename is used for moving files on the same filesystem)
-    match fs::rename(source_path, &dest_path) {
+    let moved = match fs::rename(source_path, &dest_path) {
         Ok(_) => Ok(()),
         Err(e) => {
             // Check if this is a cross-device error (rename fails across filesystems)
@@ -376,19 +555,37 @@ fn move_image(image_path: String, destination_dir: String) -> Result<(), String>
                     Ok(_) => {
                         // Copy succeeded, now delete the source
                         fs::remove_file(source_path)
-                            .map_err(|del_err| format!("Copied file but failed to remove source: {}", del_err))
+                            .map_err(|del_err| HitoError::io("Copied file but failed to remove source", source_path, del_err))
                     }
-                    Err(copy_err) => Err(format!("Failed to move image across filesystems: {}", copy_err)),
+                    Err(copy_err) => Err(HitoError::io("Failed to move image across filesystems", &dest_path, copy_err)),
                 }
             } else {
                 // Not a cross-device error, return the original error
-                Err(format!("Failed to move image: {}", e))
+                Err(HitoError::io("Failed to move image", source_path, e))
             }
         }
+    };
+    moved?;
+    if let Some(source_dir) = source_path.parent() {
+        directory_session::mark_changed(source_dir);
+    }
+    directory_session::mark_changed(dest_dir);
+    
+    let event = events::LabelEvent {
+        kind: "move".to_string(),
+        image_path: Some(dest_path.to_string_lossy().to_string()),
+        from_path: Some(image_path.clone()),
+        annotator,
+        ..Default::default()
+    };
+    // The image has already moved; a missing log entry must not report the move as failed
+    if let Err(e) = events::record_file_event(&image_path, data_file_path.as_deref(), event) {
+        tracing::warn!(error = ?e, "Moved image but failed to record event");
     }
+    Ok(())
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 struct CategoryData {
     id: String,
     name: String,
@@ -397,7 +594,21 @@ struct CategoryData {
     mutually_exclusive_with: Option<Vec<String>>,
 }
 
-#[derive(Serialize, Deserialize)]
+// Scalar attribute scored per image (e.g. a 1-5 star rating or "blur: 0-3")
+#[derive(Serialize, Deserialize, Clone)]
+struct AttributeDefinition {
+    id: String,
+    name: String,
+    kind: String, // "rating", "numeric", or "enum"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    options: Option<Vec<String>>, // Labels for "enum" attributes; values are indices into this list
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 struct HotkeyData {
     id: String,
     key: String,
@@ -405,30 +616,122 @@ struct HotkeyData {
     action: String,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 struct CategoryAssignment {
     category_id: String,
     assigned_at: String, // ISO 8601 datetime string
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    regions: Option<Vec<Region>>, // Where in the image the category applies (None = whole image)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    annotator: Option<String>, // Who made the assignment (None for files predating multi-annotator mode)
+}
+
+// Geometry in normalized image coordinates (0.0-1.0, origin at the top-left corner)
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Geometry {
+    Bbox { x: f64, y: f64, width: f64, height: f64 },
+    Polygon { points: Vec<[f64; 2]> },
+    Point { x: f64, y: f64 },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Region {
+    id: String,
+    geometry: Geometry,
+    created_at: String, // ISO 8601 datetime string
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct AttributeValue {
+    attribute_id: String,
+    value: f64,
+    assigned_at: String, // ISO 8601 datetime string
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct ImageNote {
+    text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    created_at: String, // ISO 8601 datetime string
 }
 
 #[derive(Serialize, Deserialize)]
+struct SortKey {
+    key: String, // Any `sort_option` of sort_images
+    direction: Option<String>, // "ascending" (default) or "descending"
+}
+
+/// Whether `key` is a sort key `sort_images` understands.
+fn is_sort_key(key: &str) -> bool {
+    matches!(key, "name" | "size" | "dateCreated" | "dateModified" | "lastCategorized" | "category" | "random")
+        || key.starts_with("attribute:")
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
 struct FilterOptions {
     category_id: Option<String>, // None or empty string = no filter, "uncategorized" = special filter
+    category_ids: Option<Vec<String>>, // May include "uncategorized"; empty list = no filter
+    category_match: Option<String>, // "any" (default), "all", or "none" for category_ids
+    min_labels: Option<usize>, // Inclusive bounds on the number of distinct categories per image
+    max_labels: Option<usize>,
     name_pattern: Option<String>,
-    name_operator: Option<String>, // "contains", "startsWith", "endsWith", "exact"
+    name_operator: Option<String>, // "contains", "startsWith", "endsWith", "exact", "regex", "glob"
+    name_match_path: Option<bool>, // Match name_pattern against the path relative to base_directory
+    name_case_sensitive: Option<bool>, // Default: case-insensitive
+    base_directory: Option<String>, // Root that relative paths are taken from (the browsed directory)
     size_operator: Option<String>, // "largerThan", "lessThan", "between"
     size_value: Option<String>,
     size_value2: Option<String>,
+    has_note: Option<bool>, // Some(true) = only images with notes, Some(false) = only images without
+    note_pattern: Option<String>, // Case-insensitive substring match on note text
+    attribute_filters: Option<Vec<AttributeFilter>>, // All ranges must match
+    annotator: Option<String>, // Only images with at least one assignment by this annotator
+    date_filters: Option<Vec<DateFilter>>, // All must match
+    query: Option<String>, // Boolean query, e.g. "(cat a OR cat b) AND NOT cat c AND size > 500KB" (see query.rs)
 }
 
-// File structure for .hito.json (contains image assignments, categories, and hotkeys)
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
+struct DateFilter {
+    field: String, // "created", "modified", "captured" (EXIF) or "categorized" (latest assigned_at)
+    operator: String, // "before", "after", "between" or "within"
+    value: String, // Date (RFC 3339, or YYYY-MM-DD in local time; as an end, a YYYY-MM-DD date includes that day), or a duration like "2h" or "7d" for "within"
+    value2: Option<String>, // Second date for "between"
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct AttributeFilter {
+    attribute_id: String,
+    min: Option<f64>, // Inclusive lower bound
+    max: Option<f64>, // Inclusive upper bound
+}
+
+// File structure for .hito.json (contains image assignments, categories, hotkeys, notes, and attributes)
+#[derive(Serialize, Deserialize, Default)]
 struct HitoFile {
     image_categories: Vec<(String, Vec<CategoryAssignment>)>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     categories: Option<Vec<CategoryData>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     hotkeys: Option<Vec<HotkeyData>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    image_notes: Option<Vec<(String, Vec<ImageNote>)>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    attributes: Option<Vec<AttributeDefinition>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    image_attributes: Option<Vec<(String, Vec<AttributeValue>)>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    review: Option<Vec<ReviewItem>>, // Images flagged for manual review when merging label files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    batch_images: Option<Vec<String>>, // Restricts the view to these images (task batch files)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct ReviewItem {
+    image_path: String,
+    category_ids: Vec<String>,
+    reason: String, // "tie" or "disagreement"
 }
 
 // App data structure for categories and hotkeys (stored in app data directory)
@@ -437,24 +740,34 @@ struct AppData {
     categories: Vec<CategoryData>,
     hotkeys: Vec<HotkeyData>,
     data_file_paths: Option<DataFileMap>, // directory -> data file path mapping
+    annotator: Option<String>, // Identity stamped on new category assignments
+    search_roots: Option<Vec<String>>, // Directories searched for data files by search_labels
+    workspaces: Option<Vec<workspaces::Workspace>>,
+    presets: Option<Vec<presets::Preset>>, // Reusable category and hotkey sets
+    recent_directories: Option<Vec<String>>, // Most recently browsed first
+    view_states: Option<HashMap<String, view_state::ViewState>>, // directory -> how it was last viewed
 }
 
 /// Get the path to the .hito.json file in the directory.
 /// If filename is provided, use it; otherwise default to ".hito.json".
-fn get_hito_file_path(directory: &str, filename: Option<&str>) -> PathBuf {
+///
+/// Fails when `directory` is outside the opened directories or `filename` is not a plain file name.
+fn get_hito_file_path(directory: &str, filename: Option<&str>) -> Result<PathBuf, HitoError> {
     let dir_path = Path::new(directory);
     let file_name = filename.unwrap_or(".hito.json");
-    dir_path.join(file_name)
+    scope::check_file_name(file_name)?;
+    // Canonical, so every spelling of a directory maps to the same data file lock
+    Ok(scope::check(dir_path)?.join(file_name))
 }
 
 /// Get the path to the app data file.
-fn get_app_data_path(app: &AppHandle) -> Result<PathBuf, String> {
+fn get_app_data_path(app: &AppHandle) -> Result<PathBuf, HitoError> {
     let app_data_dir = app.path().app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        .map_err(|e| HitoError::internal(format!("Failed to get app data directory: {}", e)))?;
     
     // Ensure the directory exists
     fs::create_dir_all(&app_data_dir)
-        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+        .map_err(|e| HitoError::io("Failed to create app data directory", &app_data_dir, e))?;
     
     Ok(app_data_dir.join("app-config.json"))
 }
@@ -472,73 +785,110 @@ fn get_app_data_mutex() -> &'static Mutex<()> {
 /// This function ensures that concurrent writes to the app data file are serialized
 /// to prevent lost updates. The `update_fn` closure receives the current AppData
 /// (or default if the file doesn't exist) and should return the updated AppData.
-fn update_app_data_sync<F>(app: &AppHandle, update_fn: F) -> Result<(), String>
+fn update_app_data_sync<F>(app: &AppHandle, update_fn: F) -> Result<(), HitoError>
 where
-    F: FnOnce(AppData) -> Result<AppData, String>,
+    F: FnOnce(AppData) -> Result<AppData, HitoError>,
 {
     let app_data_path = get_app_data_path(app)?;
     let mutex = get_app_data_mutex();
     
     // Lock the mutex to serialize all file operations
-    let _guard = mutex.lock().map_err(|e| format!("Failed to acquire app data lock: {}", e))?;
+    let _guard = mutex.lock()?;
     
     // Read current app data (or default if file doesn't exist)
-    let current_data = if app_data_path.exists() {
-        match fs::read_to_string(&app_data_path) {
-            Ok(content) => {
-                serde_json::from_str::<AppData>(&content)
-                    .map_err(|e| format!("Failed to parse app data file: {}", e))?
-            }
-            Err(e) => return Err(format!("Failed to read app data file: {}", e)),
-        }
-    } else {
-        AppData::default()
-    };
+    let current_data = read_app_data_file(&app_data_path)?;
     
     // Apply the update function
     let updated_data = update_fn(current_data)?;
     
     // Write the updated data back to the file
     let json_content = serde_json::to_string_pretty(&updated_data)
-        .map_err(|e| format!("Failed to serialize app data: {}", e))?;
+        .map_err(|e| HitoError::internal(format!("Failed to serialize app data: {}", e)))?;
     
     fs::write(&app_data_path, json_content)
-        .map_err(|e| format!("Failed to write app data file: {}", e))?;
+        .map_err(|e| HitoError::io("Failed to write app data file", &app_data_path, e))?;
     
     Ok(())
 }
 
+/// Read and parse app-config.json, returning the default `AppData` if it doesn't exist.
+///
+/// Callers must hold the app data mutex.
+fn read_app_data_file(app_data_path: &Path) -> Result<AppData, HitoError> {
+    if !app_data_path.exists() {
+        return Ok(AppData::default());
+    }
+    
+    match fs::read_to_string(app_data_path) {
+        Ok(content) => serde_json::from_str::<AppData>(&content).map_err(|e| {
+            HitoError::invalid_data(format!("Failed to parse app data file: {}", e)).with_path(app_data_path)
+        }),
+        Err(e) => Err(HitoError::io("Failed to read app data file", app_data_path, e)),
+    }
+}
+
 /// Load categories and hotkeys from app data directory.
 #[tauri::command]
-fn load_app_data(app: AppHandle) -> Result<AppData, String> {
+#[tracing::instrument(skip_all, err(Debug))]
+fn load_app_data(app: AppHandle) -> Result<AppData, HitoError> {
     let app_data_path = get_app_data_path(&app)?;
     let mutex = get_app_data_mutex();
     
     // Lock the mutex to ensure we don't read during a write
-    let _guard = mutex.lock().map_err(|e| format!("Failed to acquire app data lock: {}", e))?;
+    let _guard = mutex.lock()?;
     
-    if !app_data_path.exists() {
-        return Ok(AppData::default());
-    }
+    read_app_data_file(&app_data_path)
+}
+
+/// Set the annotator identity stamped on new category assignments (None clears it).
+#[tauri::command]
+#[tracing::instrument(skip_all, err(Debug))]
+fn set_annotator(app: AppHandle, annotator: Option<String>) -> Result<(), HitoError> {
+    let annotator = annotator
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
     
-    match fs::read_to_string(&app_data_path) {
-        Ok(content) => {
-            match serde_json::from_str::<AppData>(&content) {
-                Ok(data) => Ok(data),
-                Err(e) => Err(format!("Failed to parse app data file: {}", e)),
-            }
-        }
-        Err(e) => Err(format!("Failed to read app data file: {}", e)),
+    update_app_data_sync(&app, |mut app_data| {
+        app_data.annotator = annotator;
+        Ok(app_data)
+    })
+}
+
+/// Set the directories `search_labels` scans for `.hito.json` files, in addition to the
+/// registered data file paths.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(roots = roots.len()), err(Debug))]
+fn set_search_roots(app: AppHandle, roots: Vec<String>) -> Result<(), HitoError> {
+    let mut roots: Vec<String> = roots
+        .into_iter()
+        .map(|root| root.trim().to_string())
+        .filter(|root| !root.is_empty())
+        .collect();
+    roots.sort();
+    roots.dedup();
+    for root in &roots {
+        scope::check(root)?;
     }
+    
+    update_app_data_sync(&app, |mut app_data| {
+        app_data.search_roots = (!roots.is_empty()).then_some(roots);
+        Ok(app_data)
+    })
 }
 
 /// Save data file path mapping for a directory.
 #[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, data_file_path = %data_file_path), err(Debug))]
 fn save_data_file_path(
     app: AppHandle,
     directory: String,
     data_file_path: String,
-) -> Result<(), String> {
+) -> Result<(), HitoError> {
+    scope::check(&directory)?;
+    if !data_file_path.is_empty() {
+        scope::check(&data_file_path)?;
+    }
+    
     update_app_data_sync(&app, |mut app_data| {
         // Initialize or update data_file_paths
         app_data.data_file_paths
@@ -549,53 +899,35 @@ fn save_data_file_path(
 }
 
 /// Get data file path for a directory.
+///
+/// Falls back to the label store of the workspace containing the directory (see `create_workspace`).
 #[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory), err(Debug))]
 fn get_data_file_path(
     app: AppHandle,
     directory: String,
-) -> Result<Option<String>, String> {
+) -> Result<Option<String>, HitoError> {
     let app_data_path = get_app_data_path(&app)?;
     let mutex = get_app_data_mutex();
     
     // Lock the mutex to ensure we don't read during a write
-    let _guard = mutex.lock().map_err(|e| format!("Failed to acquire app data lock: {}", e))?;
-    
-    if !app_data_path.exists() {
-        return Ok(None);
-    }
+    let _guard = mutex.lock()?;
     
-    match fs::read_to_string(&app_data_path) {
-        Ok(content) => {
-            match serde_json::from_str::<AppData>(&content) {
-                Ok(data) => {
-                    if let Some(paths) = data.data_file_paths {
-                        Ok(paths.get(&directory).cloned())
-                    } else {
-                        Ok(None)
-                    }
-                }
-                Err(e) => Err(format!(
-                    "Failed to parse app data file at {}: {}",
-                    app_data_path.display(),
-                    e
-                )),
-            }
-        }
-        Err(e) => Err(format!(
-            "Failed to read app data file at {}: {}",
-            app_data_path.display(),
-            e
-        )),
+    let data = read_app_data_file(&app_data_path)?;
+    if let Some(path) = data.data_file_paths.as_ref().and_then(|paths| paths.get(&directory)) {
+        return Ok(Some(path.clone()));
     }
+    Ok(workspaces::workspace_for_directory(&data, &directory).map(|workspace| workspace.data_file_path.clone()))
 }
 
 /// Save categories and hotkeys to app data directory.
 #[tauri::command]
+#[tracing::instrument(skip_all, err(Debug))]
 fn save_app_data(
     app: AppHandle,
     categories: Vec<CategoryData>,
     hotkeys: Vec<HotkeyData>,
-) -> Result<(), String> {
+) -> Result<(), HitoError> {
     update_app_data_sync(&app, |mut app_data| {
         // Update categories and hotkeys while preserving data_file_paths
         app_data.categories = categories;
@@ -604,83 +936,808 @@ fn save_app_data(
     })
 }
 
-/// Load image category assignments from .hito.json in the specified directory.
-#[tauri::command]
-fn load_hito_config(directory: String, filename: Option<String>) -> Result<HitoFile, String> {
-    let hito_path = get_hito_file_path(&directory, filename.as_deref());
-    
+/// Read and parse a .hito.json file, returning an empty `HitoFile` if it doesn't exist.
+fn read_hito_file(hito_path: &Path) -> Result<HitoFile, HitoError> {
     if !hito_path.exists() {
-        return Ok(HitoFile {
-            image_categories: Vec::new(),
-            categories: None,
-            hotkeys: None,
-        });
+        return Ok(HitoFile::default());
     }
     
-    match fs::read_to_string(&hito_path) {
+    match fs::read_to_string(hito_path) {
         Ok(content) => {
             match serde_json::from_str::<HitoFile>(&content) {
                 Ok(data) => Ok(data),
-                Err(e) => Err(format!("Failed to parse .hito.json file: {}", e)),
+                Err(e) => Err(HitoError::invalid_data(format!("Failed to parse .hito.json file: {}", e)).with_path(hito_path)),
             }
         }
-        Err(e) => Err(format!("Failed to read .hito.json file: {}", e)),
+        Err(e) => Err(HitoError::io("Failed to read .hito.json file", hito_path, e)),
     }
 }
 
+/// Serialize a `HitoFile` and write it to `hito_path`.
+fn write_hito_file(hito_path: &Path, data: &HitoFile) -> Result<(), HitoError> {
+    let json_content = serde_json::to_string_pretty(data)
+        .map_err(|e| HitoError::internal(format!("Failed to serialize .hito.json: {}", e)))?;
+    
+    fs::write(hito_path, json_content)
+        .map_err(|e| HitoError::io("Failed to write .hito.json file", hito_path, e))?;
+    directory_session::mark_changed(hito_path);
+    
+    Ok(())
+}
+
+/// Serialize a `HitoFile` and write it to `hito_path`, which must not exist yet.
+///
+/// The file is created atomically, so a file that appears in the meantime is never overwritten;
+/// an existing file fails with a `conflict` error.
+fn create_hito_file(hito_path: &Path, data: &HitoFile) -> Result<(), HitoError> {
+    use std::io::Write;
+
+    let json_content = serde_json::to_string_pretty(data)
+        .map_err(|e| HitoError::internal(format!("Failed to serialize .hito.json: {}", e)))?;
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(hito_path)
+        .map_err(|e| HitoError::io("Failed to create data file", hito_path, e))?;
+    file.write_all(json_content.as_bytes())
+        .map_err(|e| HitoError::io("Failed to write data file", hito_path, e))?;
+    directory_session::mark_changed(hito_path);
+
+    Ok(())
+}
+
+/// Locks of the data files being written, keyed by path (see `get_data_file_lock`)
+static DATA_FILE_LOCKS: OnceLock<Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>> = OnceLock::new();
+
+/// Get the lock of a data file.
+///
+/// Commands that read, modify and write a data file hold it for the whole cycle, so concurrent
+/// commands on the same file (e.g. a note and a label save) cannot overwrite each other's changes.
+fn get_data_file_lock(hito_path: &Path) -> Arc<Mutex<()>> {
+    let mut locks = DATA_FILE_LOCKS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    locks.entry(hito_path.to_path_buf()).or_default().clone()
+}
+
+/// Collapse assignments from several annotators into one assignment per category.
+///
+/// For each image, the most recent assignment of every category is kept.
+fn merge_annotator_assignments(
+    image_categories: Vec<(String, Vec<CategoryAssignment>)>,
+) -> Vec<(String, Vec<CategoryAssignment>)> {
+    image_categories
+        .into_iter()
+        .map(|(path, assignments)| {
+            let mut merged: Vec<CategoryAssignment> = Vec::new();
+            for assignment in assignments {
+                match merged.iter_mut().find(|a| a.category_id == assignment.category_id) {
+                    // RFC 3339 timestamps in UTC compare correctly as strings
+                    Some(existing) if existing.assigned_at < assignment.assigned_at => *existing = assignment,
+                    Some(_) => {}
+                    None => merged.push(assignment),
+                }
+            }
+            (path, merged)
+        })
+        .collect()
+}
+
+/// Load image category assignments from .hito.json in the specified directory.
+///
+/// With `annotator` set, only that annotator's assignments are returned. Otherwise the merged
+/// view is returned, with one assignment per category (the most recent one) for each image.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref()), err(Debug))]
+fn load_hito_config(
+    directory: String,
+    filename: Option<String>,
+    annotator: Option<String>,
+) -> Result<HitoFile, HitoError> {
+    let hito_path = get_hito_file_path(&directory, filename.as_deref())?;
+    let mut data = read_hito_file(&hito_path)?;
+    
+    data.image_categories = match annotator {
+        Some(annotator) => data.image_categories
+            .into_iter()
+            .map(|(path, assignments)| {
+                let own = assignments
+                    .into_iter()
+                    .filter(|a| a.annotator.as_deref() == Some(annotator.as_str()))
+                    .collect::<Vec<_>>();
+                (path, own)
+            })
+            .filter(|(_, assignments)| !assignments.is_empty())
+            .collect(),
+        None => merge_annotator_assignments(data.image_categories),
+    };
+    
+    Ok(data)
+}
+
+/// Apply a save of the merged view (see `load_hito_config`) to the stored assignments.
+///
+/// The merged view shows one assignment per category, so for each image the stored assignments
+/// of every category still shown are kept, whichever annotator made them, and only categories
+/// removed from the view are unassigned. Shown assignments that are not stored yet are added; one
+/// without an annotator only when its category is not stored for the image at all.
+fn merge_into_stored_assignments(
+    stored: &[(String, Vec<CategoryAssignment>)],
+    image_categories: Vec<(String, Vec<CategoryAssignment>)>,
+) -> Vec<(String, Vec<CategoryAssignment>)> {
+    image_categories
+        .into_iter()
+        .map(|(path, shown)| {
+            let mut merged: Vec<CategoryAssignment> = stored
+                .iter()
+                .filter(|(p, _)| *p == path)
+                .flat_map(|(_, assignments)| assignments.iter())
+                .filter(|a| shown.iter().any(|s| s.category_id == a.category_id))
+                .cloned()
+                .collect();
+            for assignment in shown {
+                let stored = merged.iter().any(|a| {
+                    a.category_id == assignment.category_id
+                        && (assignment.annotator.is_none() || a.annotator == assignment.annotator)
+                });
+                if !stored {
+                    merged.push(assignment);
+                }
+            }
+            (path, merged)
+        })
+        .filter(|(_, assignments)| !assignments.is_empty())
+        .collect()
+}
+
 /// Save image category assignments, categories, and hotkeys to .hito.json in the specified directory.
+///
+/// Notes and attributes are not part of the payload; anything already stored in the file is preserved.
+///
+/// When `annotator` is set, `image_categories` is treated as that annotator's view: assignments
+/// without an annotator are stamped with it, the annotator's previously saved assignments are
+/// replaced (keeping their regions when the incoming assignment has none), and assignments made
+/// by other annotators are kept untouched. Otherwise it is treated
+/// as the merged view, so assignments of other annotators that the view collapsed are not lost.
+///
+/// Every added or removed assignment and category is appended to the data file's event log
+/// (see `get_label_history` and `get_session_stats`).
 #[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, image_categories = image_categories.len(), filename = filename.as_deref()), err(Debug))]
 fn save_hito_config(
     directory: String,
     image_categories: Vec<(String, Vec<CategoryAssignment>)>,
     filename: Option<String>,
     categories: Option<Vec<CategoryData>>,
     hotkeys: Option<Vec<HotkeyData>>,
-) -> Result<(), String> {
-    let hito_path = get_hito_file_path(&directory, filename.as_deref());
+    annotator: Option<String>,
+) -> Result<(), HitoError> {
+    let hito_path = get_hito_file_path(&directory, filename.as_deref())?;
+    let lock = get_data_file_lock(&hito_path);
+    let _guard = lock.lock()?;
+    
+    // Keep data written through other commands (notes, attributes, regions); an unreadable file is
+    // not overwritten, as that would lose them
+    let existing = read_hito_file(&hito_path)?;
+    
+    let now = chrono::Utc::now().to_rfc3339();
+    let log_path = events::get_event_log_path(&directory, filename.as_deref())?;
+    let mut label_events: Vec<events::LabelEvent> = events::initial_snapshot(&log_path, &existing, &now)
+        .into_iter()
+        .chain(events::diff_categories(existing.categories.as_deref(), categories.as_deref(), annotator.as_deref(), &now))
+        .collect();
+    
+    let image_categories = match &annotator {
+        Some(annotator) => {
+            let mut merged: Vec<(String, Vec<CategoryAssignment>)> = existing.image_categories
+                .iter()
+                .map(|(path, assignments)| {
+                    let others = assignments
+                        .iter()
+                        .filter(|a| a.annotator.as_deref() != Some(annotator.as_str()))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    (path.clone(), others)
+                })
+                .collect();
+            
+            for (path, assignments) in image_categories {
+                let stored = existing.image_categories
+                    .iter()
+                    .find(|(p, _)| *p == path)
+                    .map(|(_, stored)| stored.as_slice())
+                    .unwrap_or_default();
+                let own = assignments
+                    .into_iter()
+                    .map(|mut a| {
+                        a.annotator.get_or_insert_with(|| annotator.clone());
+                        // The frontend does not send regions; keep the ones saved through add_region
+                        if a.regions.is_none() {
+                            a.regions = stored
+                                .iter()
+                                .find(|s| s.category_id == a.category_id && s.annotator == a.annotator)
+                                .and_then(|s| s.regions.clone());
+                        }
+                        a
+                    })
+                    .filter(|a| a.annotator.as_deref() == Some(annotator.as_str()));
+                
+                match merged.iter_mut().find(|(p, _)| *p == path) {
+                    Some((_, entries)) => entries.extend(own),
+                    None => merged.push((path, own.collect())),
+                }
+            }
+            
+            merged.retain(|(_, assignments)| !assignments.is_empty());
+            merged
+        }
+        None => merge_into_stored_assignments(&existing.image_categories, image_categories),
+    };
+    
+    // Other annotators' assignments are untouched by an annotator's save
+    label_events.extend(events::diff_assignments(&existing.image_categories, &image_categories, annotator.as_deref(), &now));
     
     let data = HitoFile {
         image_categories,
         categories,
         hotkeys,
+        ..existing
     };
     
-    let json_content = serde_json::to_string_pretty(&data)
-        .map_err(|e| format!("Failed to serialize .hito.json: {}", e))?;
+    write_hito_file(&hito_path, &data)?;
+    // The labels are already saved; a missing log entry must not report the save as failed
+    if let Err(e) = events::append_events(&log_path, &label_events) {
+        tracing::warn!(error = ?e, "Saved labels but failed to record events");
+    }
+    Ok(())
+}
+
+/// List the annotators that have made assignments in a data file, sorted by name.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref()), err(Debug))]
+fn list_annotators(directory: String, filename: Option<String>) -> Result<Vec<String>, HitoError> {
+    let hito_path = get_hito_file_path(&directory, filename.as_deref())?;
+    let data = read_hito_file(&hito_path)?;
+    
+    let mut annotators: Vec<String> = data.image_categories
+        .into_iter()
+        .flat_map(|(_, assignments)| assignments.into_iter().filter_map(|a| a.annotator))
+        .collect();
+    annotators.sort();
+    annotators.dedup();
+    Ok(annotators)
+}
+
+/// Set the note left by `author` on an image, replacing any previous note by the same author.
+///
+/// An empty (or whitespace-only) `text` removes the author's note. Each image holds at most one
+/// note per author; notes without an author share a single slot.
+///
+/// # Returns
+///
+/// The notes for `image_path` after the update.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref(), image_path = %image_path), err(Debug))]
+fn set_image_note(
+    directory: String,
+    filename: Option<String>,
+    image_path: String,
+    text: String,
+    author: Option<String>,
+) -> Result<Vec<ImageNote>, HitoError> {
+    let hito_path = get_hito_file_path(&directory, filename.as_deref())?;
+    let lock = get_data_file_lock(&hito_path);
+    let _guard = lock.lock()?;
+    let mut data = read_hito_file(&hito_path)?;
+    let image_notes = data.image_notes.get_or_insert_with(Vec::new);
+    
+    let index = match image_notes.iter().position(|(path, _)| *path == image_path) {
+        Some(index) => index,
+        None => {
+            image_notes.push((image_path, Vec::new()));
+            image_notes.len() - 1
+        }
+    };
+    
+    let notes = &mut image_notes[index].1;
+    notes.retain(|note| note.author != author);
+    if !text.trim().is_empty() {
+        notes.push(ImageNote {
+            text,
+            author,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+    let updated_notes = notes.clone();
+    
+    // Drop images that no longer have any notes so the file doesn't accumulate empty entries
+    if updated_notes.is_empty() {
+        image_notes.remove(index);
+    }
+    if image_notes.is_empty() {
+        data.image_notes = None;
+    }
+    
+    write_hito_file(&hito_path, &data)?;
+    Ok(updated_notes)
+}
+
+/// Get all notes attached to an image, oldest first.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref(), image_path = %image_path), err(Debug))]
+fn get_image_notes(
+    directory: String,
+    filename: Option<String>,
+    image_path: String,
+) -> Result<Vec<ImageNote>, HitoError> {
+    let hito_path = get_hito_file_path(&directory, filename.as_deref())?;
+    let data = read_hito_file(&hito_path)?;
+    
+    Ok(data.image_notes
+        .unwrap_or_default()
+        .into_iter()
+        .find(|(path, _)| *path == image_path)
+        .map(|(_, notes)| notes)
+        .unwrap_or_default())
+}
+
+/// Validate a value against an attribute definition.
+///
+/// Ratings must be whole numbers (1-5 unless `min`/`max` say otherwise), enum values must be
+/// a valid index into `options`, and numeric values must fall within `min`/`max` when set.
+fn validate_attribute_value(definition: &AttributeDefinition, value: f64) -> Result<(), HitoError> {
+    if !value.is_finite() {
+        return Err(HitoError::invalid_input(format!("Invalid value for attribute {}: {}", definition.name, value)));
+    }
+    
+    let (min, max) = match definition.kind.as_str() {
+        "rating" => {
+            if value.fract() != 0.0 {
+                return Err(HitoError::invalid_input(format!("Rating for {} must be a whole number", definition.name)));
+            }
+            (Some(definition.min.unwrap_or(1.0)), Some(definition.max.unwrap_or(5.0)))
+        }
+        "enum" => {
+            let option_count = definition.options.as_ref().map_or(0, |options| options.len());
+            if value.fract() != 0.0 || value < 0.0 || value >= option_count as f64 {
+                return Err(HitoError::invalid_input(format!("Invalid option index for {}: {}", definition.name, value)));
+            }
+            (None, None)
+        }
+        "numeric" => (definition.min, definition.max),
+        other => return Err(HitoError::invalid_input(format!("Unknown attribute kind: {}", other))),
+    };
+    
+    if min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max) {
+        return Err(HitoError::invalid_input(format!(
+            "Value {} for {} is out of range ({} to {})",
+            value,
+            definition.name,
+            min.map_or("-".to_string(), |v| v.to_string()),
+            max.map_or("-".to_string(), |v| v.to_string()),
+        )));
+    }
+    
+    Ok(())
+}
+
+/// Save attribute definitions to .hito.json, keeping all other data in the file.
+///
+/// Values of attributes that are no longer defined are removed from every image.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref()), err(Debug))]
+fn save_attribute_definitions(
+    directory: String,
+    filename: Option<String>,
+    attributes: Vec<AttributeDefinition>,
+) -> Result<(), HitoError> {
+    let hito_path = get_hito_file_path(&directory, filename.as_deref())?;
+    let lock = get_data_file_lock(&hito_path);
+    let _guard = lock.lock()?;
+    let mut data = read_hito_file(&hito_path)?;
+    
+    if let Some(image_attributes) = data.image_attributes.as_mut() {
+        for (_, values) in image_attributes.iter_mut() {
+            values.retain(|v| attributes.iter().any(|a| a.id == v.attribute_id));
+        }
+        image_attributes.retain(|(_, values)| !values.is_empty());
+    }
+    if data.image_attributes.as_ref().is_some_and(|values| values.is_empty()) {
+        data.image_attributes = None;
+    }
+    
+    data.attributes = if attributes.is_empty() { None } else { Some(attributes) };
+    write_hito_file(&hito_path, &data)
+}
+
+/// Set (or clear, when `value` is `None`) an attribute value on an image.
+///
+/// The value is validated against the attribute's definition in the data file.
+///
+/// # Returns
+///
+/// The attribute values for `image_path` after the update.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref(), image_path = %image_path), err(Debug))]
+fn set_image_attribute(
+    directory: String,
+    filename: Option<String>,
+    image_path: String,
+    attribute_id: String,
+    value: Option<f64>,
+) -> Result<Vec<AttributeValue>, HitoError> {
+    let hito_path = get_hito_file_path(&directory, filename.as_deref())?;
+    let lock = get_data_file_lock(&hito_path);
+    let _guard = lock.lock()?;
+    let mut data = read_hito_file(&hito_path)?;
+    
+    let definition = data.attributes
+        .as_ref()
+        .and_then(|attributes| attributes.iter().find(|a| a.id == attribute_id))
+        .ok_or_else(|| HitoError::not_found(format!("Attribute not found: {}", attribute_id)))?;
+    if let Some(value) = value {
+        validate_attribute_value(definition, value)?;
+    }
+    
+    let image_attributes = data.image_attributes.get_or_insert_with(Vec::new);
+    let index = match image_attributes.iter().position(|(path, _)| *path == image_path) {
+        Some(index) => index,
+        None => {
+            image_attributes.push((image_path, Vec::new()));
+            image_attributes.len() - 1
+        }
+    };
+    
+    let values = &mut image_attributes[index].1;
+    values.retain(|v| v.attribute_id != attribute_id);
+    if let Some(value) = value {
+        values.push(AttributeValue {
+            attribute_id,
+            value,
+            assigned_at: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+    let updated_values = values.clone();
+    
+    if updated_values.is_empty() {
+        image_attributes.remove(index);
+    }
+    if image_attributes.is_empty() {
+        data.image_attributes = None;
+    }
+    
+    write_hito_file(&hito_path, &data)?;
+    Ok(updated_values)
+}
+
+/// Get all attribute values set on an image.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref(), image_path = %image_path), err(Debug))]
+fn get_image_attributes(
+    directory: String,
+    filename: Option<String>,
+    image_path: String,
+) -> Result<Vec<AttributeValue>, HitoError> {
+    let hito_path = get_hito_file_path(&directory, filename.as_deref())?;
+    let data = read_hito_file(&hito_path)?;
+    
+    Ok(data.image_attributes
+        .unwrap_or_default()
+        .into_iter()
+        .find(|(path, _)| *path == image_path)
+        .map(|(_, values)| values)
+        .unwrap_or_default())
+}
+
+/// Counter to keep region IDs unique when several are created within the same nanosecond.
+static REGION_ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Generate a new unique region ID.
+fn new_region_id() -> String {
+    let timestamp = chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default();
+    let counter = REGION_ID_COUNTER.fetch_add(1, AtomicOrdering::Relaxed);
+    format!("region_{}_{}", timestamp, counter)
+}
+
+/// Check that a geometry is well-formed and lies within normalized image coordinates.
+fn validate_geometry(geometry: &Geometry) -> Result<(), HitoError> {
+    let in_range = |v: f64| (0.0..=1.0).contains(&v);
+    
+    match geometry {
+        Geometry::Bbox { x, y, width, height } => {
+            if !(in_range(*x) && in_range(*y)) || *width <= 0.0 || *height <= 0.0
+                || !in_range(x + width) || !in_range(y + height)
+            {
+                return Err(HitoError::invalid_input("Bounding box must have a positive size and lie within 0.0-1.0"));
+            }
+        }
+        Geometry::Polygon { points } => {
+            if points.len() < 3 {
+                return Err(HitoError::invalid_input("Polygon must have at least 3 points"));
+            }
+            if !points.iter().all(|[x, y]| in_range(*x) && in_range(*y)) {
+                return Err(HitoError::invalid_input("Polygon points must lie within 0.0-1.0"));
+            }
+        }
+        Geometry::Point { x, y } => {
+            if !(in_range(*x) && in_range(*y)) {
+                return Err(HitoError::invalid_input("Point must lie within 0.0-1.0"));
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Find the category assignment holding the region with `region_id` among an image's assignments.
+fn find_region_assignment_mut<'a>(
+    data: &'a mut HitoFile,
+    image_path: &str,
+    region_id: &str,
+) -> Result<&'a mut CategoryAssignment, HitoError> {
+    data.image_categories
+        .iter_mut()
+        .filter(|(path, _)| path == image_path)
+        .flat_map(|(_, assignments)| assignments.iter_mut())
+        .find(|a| a.regions.as_ref().is_some_and(|regions| regions.iter().any(|r| r.id == region_id)))
+        .ok_or_else(|| HitoError::not_found(format!("Region not found: {}", region_id)))
+}
+
+/// Add a region to an image's category assignment.
+///
+/// The region goes on `annotator`'s assignment of `category_id` (the unattributed one when
+/// `annotator` is `None`); if there is none yet, the category is assigned as well.
+///
+/// # Returns
+///
+/// The newly created region (including its generated ID).
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref(), image_path = %image_path), err(Debug))]
+fn add_region(
+    directory: String,
+    filename: Option<String>,
+    image_path: String,
+    category_id: String,
+    geometry: Geometry,
+    annotator: Option<String>,
+) -> Result<Region, HitoError> {
+    validate_geometry(&geometry)?;
+    
+    let hito_path = get_hito_file_path(&directory, filename.as_deref())?;
+    let lock = get_data_file_lock(&hito_path);
+    let _guard = lock.lock()?;
+    let mut data = read_hito_file(&hito_path)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let log_path = events::get_event_log_path(&directory, filename.as_deref())?;
+    let mut label_events: Vec<events::LabelEvent> = events::initial_snapshot(&log_path, &data, &now).into_iter().collect();
+    
+    let image_index = match data.image_categories.iter().position(|(path, _)| *path == image_path) {
+        Some(index) => index,
+        None => {
+            data.image_categories.push((image_path.clone(), Vec::new()));
+            data.image_categories.len() - 1
+        }
+    };
+    
+    let assignments = &mut data.image_categories[image_index].1;
+    let assignment_index = match assignments.iter().position(|a| a.category_id == category_id && a.annotator == annotator) {
+        Some(index) => index,
+        None => {
+            let assignment = CategoryAssignment {
+                category_id,
+                assigned_at: now.clone(),
+                regions: None,
+                annotator: annotator.clone(),
+            };
+            label_events.extend(events::diff_assignments(
+                &[],
+                &[(image_path.clone(), vec![assignment.clone()])],
+                annotator.as_deref(),
+                &now,
+            ));
+            assignments.push(assignment);
+            assignments.len() - 1
+        }
+    };
+    
+    let region = Region {
+        id: new_region_id(),
+        geometry,
+        created_at: now.clone(),
+    };
+    let assignment = &mut assignments[assignment_index];
+    assignment.regions
+        .get_or_insert_with(Vec::new)
+        .push(region.clone());
+    label_events.push(events::region_event("region_add", &image_path, assignment, &region, &now));
+    
+    write_hito_file(&hito_path, &data)?;
+    // The region is already saved; a missing log entry must not report the change as failed
+    if let Err(e) = events::append_events(&log_path, &label_events) {
+        tracing::warn!(error = ?e, "Added region but failed to record event");
+    }
+    Ok(region)
+}
+
+/// Replace the geometry of an existing region.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref(), image_path = %image_path), err(Debug))]
+fn update_region(
+    directory: String,
+    filename: Option<String>,
+    image_path: String,
+    region_id: String,
+    geometry: Geometry,
+) -> Result<Region, HitoError> {
+    validate_geometry(&geometry)?;
     
-    fs::write(&hito_path, json_content)
-        .map_err(|e| format!("Failed to write .hito.json file: {}", e))?;
+    let hito_path = get_hito_file_path(&directory, filename.as_deref())?;
+    let lock = get_data_file_lock(&hito_path);
+    let _guard = lock.lock()?;
+    let mut data = read_hito_file(&hito_path)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let log_path = events::get_event_log_path(&directory, filename.as_deref())?;
+    let mut label_events: Vec<events::LabelEvent> = events::initial_snapshot(&log_path, &data, &now).into_iter().collect();
     
+    let assignment = find_region_assignment_mut(&mut data, &image_path, &region_id)?;
+    let region = assignment.regions.iter_mut().flatten().find(|r| r.id == region_id)
+        .ok_or_else(|| HitoError::not_found(format!("Region not found: {}", region_id)))?;
+    region.geometry = geometry;
+    let updated = region.clone();
+    label_events.push(events::region_event("region_update", &image_path, assignment, &updated, &now));
+    
+    write_hito_file(&hito_path, &data)?;
+    // The region is already saved; a missing log entry must not report the change as failed
+    if let Err(e) = events::append_events(&log_path, &label_events) {
+        tracing::warn!(error = ?e, "Updated region but failed to record event");
+    }
+    Ok(updated)
+}
+
+/// Remove a region. The category assignment itself is kept (as a whole-image label).
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref(), image_path = %image_path), err(Debug))]
+fn remove_region(
+    directory: String,
+    filename: Option<String>,
+    image_path: String,
+    region_id: String,
+) -> Result<(), HitoError> {
+    let hito_path = get_hito_file_path(&directory, filename.as_deref())?;
+    let lock = get_data_file_lock(&hito_path);
+    let _guard = lock.lock()?;
+    let mut data = read_hito_file(&hito_path)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let log_path = events::get_event_log_path(&directory, filename.as_deref())?;
+    let mut label_events: Vec<events::LabelEvent> = events::initial_snapshot(&log_path, &data, &now).into_iter().collect();
+    
+    let assignment = find_region_assignment_mut(&mut data, &image_path, &region_id)?;
+    if let Some(regions) = assignment.regions.as_mut() {
+        if let Some(index) = regions.iter().position(|r| r.id == region_id) {
+            let removed = regions.remove(index);
+            label_events.push(events::region_event("region_remove", &image_path, assignment, &removed, &now));
+        }
+        if assignment.regions.as_ref().is_some_and(|regions| regions.is_empty()) {
+            assignment.regions = None;
+        }
+    }
+    
+    write_hito_file(&hito_path, &data)?;
+    // The region is already removed; a missing log entry must not report the change as failed
+    if let Err(e) = events::append_events(&log_path, &label_events) {
+        tracing::warn!(error = ?e, "Removed region but failed to record event");
+    }
     Ok(())
 }
 
+/// Filter and sort options of `sort_images` and of directory session queries.
+#[derive(Deserialize, Default)]
+struct SortOptions {
+    sort_option: Option<String>, // Default "name"; see `sort_images`
+    sort_direction: Option<String>, // "ascending" (default) or "descending"
+    sort_keys: Option<Vec<SortKey>>,
+    random_seed: Option<u64>, // Required by the "random" sort key
+    filter_options: Option<FilterOptions>,
+}
+
+/// Labels of the images being filtered and sorted, keyed by image path.
+#[derive(Default)]
+struct ImageLabels {
+    categories: HashMap<String, Vec<CategoryAssignment>>,
+    notes: HashMap<String, Vec<ImageNote>>,
+    attributes: HashMap<String, Vec<AttributeValue>>,
+}
+
+impl ImageLabels {
+    fn new(
+        image_categories: Vec<(String, Vec<CategoryAssignment>)>,
+        image_notes: Option<Vec<(String, Vec<ImageNote>)>>,
+        image_attributes: Option<Vec<(String, Vec<AttributeValue>)>>,
+    ) -> Self {
+        ImageLabels {
+            categories: image_categories.into_iter().collect(),
+            notes: image_notes.unwrap_or_default().into_iter().collect(),
+            attributes: image_attributes.unwrap_or_default().into_iter().collect(),
+        }
+    }
+}
+
+/// Arguments of `sort_images`.
+#[derive(Deserialize)]
+struct SortRequest {
+    images: Vec<ImagePath>,
+    #[serde(default)]
+    image_categories: Vec<(String, Vec<CategoryAssignment>)>, // For filtering and lastCategorized sorting
+    image_notes: Option<Vec<(String, Vec<ImageNote>)>>, // For note filtering
+    image_attributes: Option<Vec<(String, Vec<AttributeValue>)>>, // For attribute filtering and sorting
+    #[serde(flatten)]
+    options: SortOptions,
+}
+
 /// Filter and sort images based on the specified filter and sort options.
 ///
 /// # Parameters
-/// * `images` - Vector of images with metadata to filter and sort
-/// * `sort_option` - Sort option: "name", "dateCreated", "lastCategorized", or "size"
-/// * `sort_direction` - Sort direction: "ascending" or "descending"
-/// * `image_categories` - Map of image path to category assignments (for filtering and lastCategorized sorting)
-/// * `filter_options` - Optional filter options (if None, no filtering is applied)
+/// * `request` - The images, their labels, and the options:
+///   * `sort_option` - Sort option: "name" (natural order, so img2 before img10), "dateCreated",
+///     "dateModified", "lastCategorized", "size", "category", "random", or "attribute:<id>"
+///   * `sort_direction` - Sort direction: "ascending" or "descending"
+///   * `filter_options` - Optional filter options (if None, no filtering is applied); the individual
+///     fields are shorthands that are combined with `query` using AND
+///   * `sort_keys` - Optional sort keys in priority order (e.g. category, then dateCreated, then name);
+///     when given they replace `sort_option` and `sort_direction`
+///   * `random_seed` - Seed for the "random" sort key, so the shuffled order stays the same when the
+///     view is sorted again; required when sorting randomly
 ///
 /// # Returns
-/// Filtered and sorted vector of images
+/// Filtered and sorted vector of images, or an `invalid_input` `HitoError` whose `field` names the filter field that could not
+/// be applied (malformed query, invalid regex or glob pattern, unknown match mode or sort key, missing random seed).
+/// Images that tie on every sort key are ordered by path.
+///
+/// Runs on the worker pool (capture-date filters read every file); a `request_id` makes it
+/// cancellable, as in `list_images`.
 #[tauri::command]
-fn sort_images(
-    images: Vec<ImagePath>,
-    sort_option: String,
-    sort_direction: String,
-    image_categories: Vec<(String, Vec<CategoryAssignment>)>,
-    filter_options: Option<FilterOptions>,
-) -> Result<Vec<ImagePath>, String> {
-    // Convert image_categories to a HashMap for faster lookup
-    let category_map: std::collections::HashMap<String, Vec<CategoryAssignment>> = 
-        image_categories.into_iter().collect();
+#[tracing::instrument(skip_all, fields(images = request.images.len()), err(Debug))]
+async fn sort_images(request: SortRequest, request_id: Option<String>) -> Result<Vec<ImagePath>, HitoError> {
+    let cancel = Request::start(request_id);
+    run_blocking(move || {
+        let SortRequest { images, image_categories, image_notes, image_attributes, options } = request;
+        let labels = ImageLabels::new(image_categories, image_notes, image_attributes);
+        filter_and_sort_images(&images, &labels, options, cancel.token())
+    })
+    .await
+}
+
+/// Blocking part of `sort_images`. Filters and sorts by reference and clones only the matches.
+fn filter_and_sort_images(
+    images: &[ImagePath],
+    labels: &ImageLabels,
+    options: SortOptions,
+    cancel: &CancelToken,
+) -> Result<Vec<ImagePath>, HitoError> {
+    cancel.check()?;
+    let SortOptions { sort_option, sort_direction, sort_keys, random_seed, filter_options } = options;
+    let (category_map, note_map, attribute_map) = (&labels.categories, &labels.notes, &labels.attributes);
+    let get_attribute_value = |path: &str, attribute_id: &str| -> Option<f64> {
+        attribute_map.get(path)
+            .and_then(|values| values.iter().find(|v| v.attribute_id == attribute_id))
+            .map(|v| v.value)
+    };
     
     // Filter first (more efficient than sorting then filtering)
-    let mut filtered_images: Vec<ImagePath> = images;
+    let mut filtered_images: Vec<&ImagePath> = images.iter().collect();
+    // Capture times read for the date filters, returned with the matching images
+    let mut capture_times: HashMap<&str, String> = HashMap::new();
     
     if let Some(filters) = filter_options {
+        // Apply the query first so a malformed query fails before any other work
+        if let Some(query) = filters.query.as_deref().filter(|q| !q.trim().is_empty()) {
+            let query = query::Query::parse(query)?;
+            filtered_images.retain(|img| query.matches(img, category_map));
+        }
+        
+
         // Apply category filter
         if let Some(category_id) = filters.category_id {
             if !category_id.is_empty() {
@@ -700,26 +1757,55 @@ fn sort_images(
             }
         }
         
+        // Apply multi-category filter
+        if let Some(category_ids) = filters.category_ids.filter(|ids| !ids.is_empty()) {
+            let mode = filters.category_match.as_deref().unwrap_or("any");
+            if !["any", "all", "none"].contains(&mode) {
+                return Err(HitoError::invalid_field("category_match", format!("Unknown category match mode: {}", mode)));
+            }
+            
+            filtered_images.retain(|img| {
+                let assignments = category_map.get(&img.path).map(Vec::as_slice).unwrap_or_default();
+                let has = |category_id: &String| {
+                    if category_id == "uncategorized" {
+                        assignments.is_empty()
+                    } else {
+                        assignments.iter().any(|a| a.category_id == *category_id)
+                    }
+                };
+                
+                match mode {
+                    "all" => category_ids.iter().all(has),
+                    "none" => !category_ids.iter().any(has),
+                    _ => category_ids.iter().any(has),
+                }
+            });
+        }
+        
+        // Apply label count filter (distinct categories, so duplicate assignments count once)
+        if filters.min_labels.is_some() || filters.max_labels.is_some() {
+            filtered_images.retain(|img| {
+                let count = category_map.get(&img.path).map_or(0, |assignments| {
+                    assignments.iter().map(|a| a.category_id.as_str()).collect::<HashSet<_>>().len()
+                });
+                filters.min_labels.is_none_or(|min| count >= min)
+                    && filters.max_labels.is_none_or(|max| count <= max)
+            });
+        }
+        
         // Apply name filter
         if let Some(name_pattern) = filters.name_pattern {
             if !name_pattern.is_empty() {
-                let pattern = name_pattern.to_lowercase();
                 let operator = filters.name_operator.as_deref().unwrap_or("contains");
+                let target = name_filter::NameTarget {
+                    full_path: filters.name_match_path.unwrap_or(false),
+                    base_directory: filters.base_directory,
+                    case_sensitive: filters.name_case_sensitive.unwrap_or(false),
+                };
+                let matcher = name_filter::NameMatcher::compile(&name_pattern, operator, target.case_sensitive)
+                    .map_err(|message| HitoError::invalid_field("name_pattern", message))?;
                 
-                filtered_images.retain(|img| {
-                    let file_name = Path::new(&img.path)
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .map(|s| s.to_lowercase())
-                        .unwrap_or_default();
-                    
-                    match operator {
-                        "startsWith" => file_name.starts_with(&pattern),
-                        "endsWith" => file_name.ends_with(&pattern),
-                        "exact" => file_name == pattern,
-                        _ => file_name.contains(&pattern), // "contains" or default
-                    }
-                });
+                filtered_images.retain(|img| matcher.is_match(&img.path, &target));
             }
         }
         
@@ -765,106 +1851,255 @@ fn sort_images(
                 }
             }
         }
+        
+        // Apply annotator filter
+        if let Some(annotator) = filters.annotator {
+            if !annotator.is_empty() {
+                filtered_images.retain(|img| {
+                    category_map.get(&img.path).is_some_and(|assignments| {
+                        assignments.iter().any(|a| a.annotator.as_deref() == Some(annotator.as_str()))
+                    })
+                });
+            }
+        }
+        
+        // Apply note filters
+        let has_notes = |path: &str| {
+            note_map.get(path).is_some_and(|notes| notes.iter().any(|note| !note.text.trim().is_empty()))
+        };
+        
+        if let Some(has_note) = filters.has_note {
+            filtered_images.retain(|img| has_notes(&img.path) == has_note);
+        }
+        
+        if let Some(note_pattern) = filters.note_pattern {
+            if !note_pattern.is_empty() {
+                let pattern = note_pattern.to_lowercase();
+                filtered_images.retain(|img| {
+                    note_map.get(&img.path).is_some_and(|notes| {
+                        notes.iter().any(|note| note.text.to_lowercase().contains(&pattern))
+                    })
+                });
+            }
+        }
+        
+        // Apply attribute range filters (images without a value never match)
+        for attribute_filter in filters.attribute_filters.unwrap_or_default() {
+            filtered_images.retain(|img| {
+                get_attribute_value(&img.path, &attribute_filter.attribute_id).is_some_and(|value| {
+                    attribute_filter.min.is_none_or(|min| value >= min)
+                        && attribute_filter.max.is_none_or(|max| value <= max)
+                })
+            });
+        }
+        
+        // Apply date filters (images without the date never match)
+        let date_filters = filters.date_filters.unwrap_or_default();
+        if !date_filters.is_empty() {
+            let now = chrono::Utc::now();
+            let mut ranges = Vec::new();
+            for date_filter in &date_filters {
+                if !["created", "modified", "captured", "categorized"].contains(&date_filter.field.as_str()) {
+                    return Err(HitoError::invalid_field("date_filters", format!("Unknown date field: {}", date_filter.field)));
+                }
+                
+                let invalid = |message: String| HitoError::invalid_field("date_filters", message);
+                let date = |value: &str| dates::parse_date(value).map_err(invalid);
+                // A date-only end includes that whole day (see dates::parse_end_date)
+                let end_date = |value: &str, inclusive: bool| dates::parse_end_date(value, inclusive).map_err(invalid);
+                // (inclusive start, end, whether the end is inclusive)
+                let range = match date_filter.operator.as_str() {
+                    "before" => {
+                        let (end, inclusive) = end_date(&date_filter.value, false)?;
+                        (None, Some(end), inclusive)
+                    }
+                    "after" => (Some(date(&date_filter.value)?), None, true),
+                    "between" => {
+                        let second = date_filter.value2.as_deref()
+                            .ok_or_else(|| HitoError::invalid_field("date_filters", "\"between\" needs a second date"))?;
+                        let (first, second) = if date(&date_filter.value)? <= date(second)? {
+                            (date_filter.value.as_str(), second)
+                        } else {
+                            (second, date_filter.value.as_str())
+                        };
+                        let (end, inclusive) = end_date(second, true)?;
+                        (Some(date(first)?), Some(end), inclusive)
+                    }
+                    "within" => {
+                        let duration = dates::parse_duration(&date_filter.value)
+                            .map_err(|message| HitoError::invalid_field("date_filters", message))?;
+                        (Some(now - duration), None, true)
+                    }
+                    other => return Err(HitoError::invalid_field("date_filters", format!("Unknown date operator: {}", other))),
+                };
+                ranges.push((date_filter.field.as_str(), range));
+            }
+            
+            if ranges.iter().any(|(field, _)| *field == "captured") {
+                for img in filtered_images.iter().filter(|img| img.captured_at.is_none()) {
+                    cancel.check()?;
+                    // Files outside the opened directories are not read, so they have no capture time
+                    if scope::check(&img.path).is_ok() {
+                        if let Some(captured_at) = dates::capture_time(&img.path) {
+                            capture_times.insert(&img.path, captured_at);
+                        }
+                    }
+                }
+            }
+            
+            filtered_images.retain(|img| {
+                ranges.iter().all(|(field, (start, end, end_inclusive))| {
+                    let value = match *field {
+                        "created" => img.created_at.as_deref().and_then(dates::parse_timestamp),
+                        "modified" => img.modified_at.as_deref().and_then(dates::parse_timestamp),
+                        "captured" => img.captured_at.as_deref()
+                            .or_else(|| capture_times.get(img.path.as_str()).map(String::as_str))
+                            .and_then(dates::parse_timestamp),
+                        _ => category_map.get(&img.path).and_then(|assignments| {
+                            assignments.iter().filter_map(|a| dates::parse_timestamp(&a.assigned_at)).max()
+                        }),
+                    };
+                    value.is_some_and(|value| {
+                        start.is_none_or(|start| value >= start)
+                            && end.is_none_or(|end| if *end_inclusive { value <= end } else { value < end })
+                    })
+                })
+            });
+        }
     }
     
     let mut sorted_images = filtered_images;
     
-    // Determine if we should reverse the sort order
-    let is_descending = sort_direction == "descending";
-    
-    match sort_option.as_str() {
-        "name" => {
-            sorted_images.sort_by(|a, b| {
-                let name_a = Path::new(&a.path)
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("")
-                    .to_lowercase();
-                let name_b = Path::new(&b.path)
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("")
-                    .to_lowercase();
-                let ordering = name_a.cmp(&name_b);
-                if is_descending {
-                    ordering.reverse()
-                } else {
-                    ordering
-                }
-            });
+    cancel.check()?;
+    
+    // Sort keys in priority order; the single `sort_option` is used when none are given
+    let explicit_keys = sort_keys.as_ref().is_some_and(|keys| !keys.is_empty());
+    let keys = match sort_keys.filter(|keys| !keys.is_empty()) {
+        Some(keys) => keys,
+        None => vec![SortKey {
+            key: sort_option.unwrap_or_else(|| "name".to_string()),
+            direction: Some(sort_direction.unwrap_or_else(|| "ascending".to_string())),
+        }],
+    };
+    let mut known_keys = Vec::new();
+    for sort_key in keys {
+        if is_sort_key(&sort_key.key) {
+            known_keys.push(sort_key);
+        } else if explicit_keys {
+            return Err(HitoError::invalid_field("sort_keys", format!("Unknown sort key: {}", sort_key.key)));
         }
-        "size" => {
-            sorted_images.sort_by(|a, b| {
-                let size_a = a.size.unwrap_or(0);
-                let size_b = b.size.unwrap_or(0);
-                let ordering = size_a.cmp(&size_b);
-                if is_descending {
-                    ordering.reverse()
-                } else {
-                    ordering
+    }
+    let into_owned = |images: Vec<&ImagePath>| -> Vec<ImagePath> {
+        images.into_iter()
+            .map(|img| {
+                let mut img = img.clone();
+                if img.captured_at.is_none() {
+                    img.captured_at = capture_times.get(img.path.as_str()).cloned();
                 }
-            });
+                img
+            })
+            .collect()
+    };
+    if known_keys.is_empty() {
+        // Unknown sort option, return as-is
+        return Ok(into_owned(sorted_images));
+    }
+    
+    // A fresh seed on every call would reshuffle the view each time it is sorted again
+    let seed = match random_seed {
+        Some(seed) => seed,
+        None if known_keys.iter().any(|sort_key| sort_key.key == "random") => {
+            return Err(HitoError::invalid_field("random_seed", "Sorting randomly needs a random_seed"));
         }
-        "dateCreated" => {
-            sorted_images.sort_by(|a, b| {
-                let date_a = a.created_at.as_ref()
-                    .and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok())
-                    .map(|dt| dt.timestamp());
-                let date_b = b.created_at.as_ref()
-                    .and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok())
-                    .map(|dt| dt.timestamp());
+        None => 0,
+    };
+    let file_name = |image: &ImagePath| -> String {
+        Path::new(&image.path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    let timestamp = |date: &Option<String>| -> Option<i64> {
+        date.as_ref()
+            .and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok())
+            .map(|dt| dt.timestamp())
+    };
+    let get_latest_assignment = |path: &str| -> i64 {
+        category_map.get(path)
+            .and_then(|assignments| {
+                assignments.iter()
+                    .filter_map(|assignment| {
+                        chrono::DateTime::parse_from_rfc3339(&assignment.assigned_at)
+                            .ok()
+                            .map(|dt| dt.timestamp())
+                    })
+                    .max()
+            })
+            .unwrap_or(0) // Uncategorized images get 0, matching JavaScript behavior
+    };
+    let get_category_ids = |path: &str| -> Vec<&str> {
+        let mut ids: Vec<&str> = category_map.get(path)
+            .map(|assignments| assignments.iter().map(|a| a.category_id.as_str()).collect())
+            .unwrap_or_default();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    };
+    // Missing values sort last when ascending (first when descending), like dateCreated always has
+    let compare_present = |a: Option<i64>, b: Option<i64>| match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    };
+    
+    let compare = |sort_key: &SortKey, a: &ImagePath, b: &ImagePath| -> std::cmp::Ordering {
+        let is_descending = sort_key.direction.as_deref() == Some("descending");
+        let directed = |ordering: std::cmp::Ordering| if is_descending { ordering.reverse() } else { ordering };
+        
+        match sort_key.key.as_str() {
+            "name" => directed(natural_sort::natural_cmp(&file_name(a), &file_name(b))),
+            "size" => directed(a.size.unwrap_or(0).cmp(&b.size.unwrap_or(0))),
+            "dateCreated" => directed(compare_present(timestamp(&a.created_at), timestamp(&b.created_at))),
+            "dateModified" => directed(compare_present(timestamp(&a.modified_at), timestamp(&b.modified_at))),
+            "lastCategorized" => directed(get_latest_assignment(&a.path).cmp(&get_latest_assignment(&b.path))),
+            "category" => {
+                // Groups images with the same labels; uncategorized images last in either direction
+                let (ids_a, ids_b) = (get_category_ids(&a.path), get_category_ids(&b.path));
+                match (ids_a.is_empty(), ids_b.is_empty()) {
+                    (false, false) => directed(ids_a.cmp(&ids_b)),
+                    (false, true) => std::cmp::Ordering::Less,
+                    (true, false) => std::cmp::Ordering::Greater,
+                    (true, true) => std::cmp::Ordering::Equal,
+                }
+            }
+            "random" => random::rank(seed, &a.path).cmp(&random::rank(seed, &b.path)),
+            key => {
+                let attribute_id = key.strip_prefix("attribute:").unwrap_or_default();
+                let value_a = get_attribute_value(&a.path, attribute_id);
+                let value_b = get_attribute_value(&b.path, attribute_id);
                 
-                // Sort by date, with None values last
-                let ordering = match (date_a, date_b) {
-                    (Some(a), Some(b)) => a.cmp(&b),
+                // Sort by value, with images missing the attribute last in either direction
+                match (value_a, value_b) {
+                    (Some(a), Some(b)) => directed(a.total_cmp(&b)),
                     (Some(_), None) => std::cmp::Ordering::Less,
                     (None, Some(_)) => std::cmp::Ordering::Greater,
                     (None, None) => std::cmp::Ordering::Equal,
-                };
-                if is_descending {
-                    ordering.reverse()
-                } else {
-                    ordering
-                }
-            });
-        }
-        "lastCategorized" => {
-            sorted_images.sort_by(|a, b| {
-                let get_latest_assignment = |path: &str| -> i64 {
-                    category_map.get(path)
-                        .and_then(|assignments| {
-                            if assignments.is_empty() {
-                                None
-                            } else {
-                                assignments.iter()
-                                    .filter_map(|assignment| {
-                                        chrono::DateTime::parse_from_rfc3339(&assignment.assigned_at)
-                                            .ok()
-                                            .map(|dt| dt.timestamp())
-                                    })
-                                    .max()
-                            }
-                        })
-                        .unwrap_or(0) // Uncategorized images get 0, matching JavaScript behavior
-                };
-                
-                let date_a = get_latest_assignment(&a.path);
-                let date_b = get_latest_assignment(&b.path);
-                
-                let ordering = date_a.cmp(&date_b);
-                if is_descending {
-                    ordering.reverse()
-                } else {
-                    ordering
                 }
-            });
-        }
-        _ => {
-            // Unknown sort option, return as-is
+            }
         }
-    }
+    };
+    
+    // Ties on every key fall back to the full path so the order never depends on the input order
+    sorted_images.sort_by(|a, b| {
+        known_keys.iter()
+            .map(|sort_key| compare(sort_key, a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.path.cmp(&b.path))
+    });
     
-    Ok(sorted_images)
+    Ok(into_owned(sorted_images))
 }
 
 /// Initializes and runs the Tauri application with configured plugins and invoke handlers.
@@ -887,8 +2122,42 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_store::Builder::default().build())
-        .invoke_handler(tauri::generate_handler![list_images, load_image, get_parent_directory, delete_image, copy_image, move_image, load_app_data, save_app_data, save_data_file_path, get_data_file_path, load_hito_config, save_hito_config, sort_images])
-        .setup(|_app| {
+        .manage(directory_session::DirectorySessions::default())
+        .manage(metadata_index::AppIndex::default())
+        .invoke_handler(tauri::generate_handler![list_images, load_image, get_parent_directory, delete_image, copy_image, move_image, load_app_data, save_app_data, save_data_file_path, get_data_file_path, load_hito_config, save_hito_config, sort_images, set_image_note, get_image_notes, save_attribute_definitions, set_image_attribute, get_image_attributes, add_region, update_region, remove_region, set_annotator, list_annotators, agreement::compute_agreement, consensus::merge_label_files, gold::save_gold_set, gold::load_gold_set, gold::score_gold_set, batches::create_task_batches, sessions::get_session_stats, sessions::export_session_events_csv, export::export_labels_csv, export::export_coco, export::export_yolo, events::get_label_history, events::replay_label_log, query::validate_query, requests::cancel_request, scope::pick_directory, scope::pick_save_file, scope::pick_open_file, scope::get_opened_directories, diagnostics::export_diagnostics, directory_session::open_directory_session, directory_session::reload_directory_session, directory_session::close_directory_session, directory_session::query_directory_session, directory_session::query_directory_session_indices, metadata_index::index_directory, metadata_index::get_indexed_images, metadata_index::search_index_labels, set_search_roots, label_search::search_labels, workspaces::create_workspace, workspaces::open_workspace, workspaces::list_workspaces, presets::save_preset, presets::list_presets, presets::apply_preset, presets::export_preset, presets::import_preset, view_state::get_recent_directories, view_state::add_recent_directory, view_state::remove_recent_directory, view_state::open_recent_directory, view_state::save_view_state, view_state::get_view_state])
+        .on_window_event(|_window, event| {
+            // Dropped folders (or the folders of dropped files) join the scope before the frontend
+            // sees the drop and lists them
+            if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                for path in paths {
+                    // A path that cannot be opened fails again, with its error, when it is listed
+                    let _ = scope::grant(path);
+                }
+            }
+        })
+        .setup(|app| {
+            // Logging is best effort: the app still runs when the log file cannot be opened
+            match diagnostics::init_logging(app.handle()) {
+                Ok(guard) => {
+                    app.manage(guard);
+                }
+                Err(e) => eprintln!("{}", e),
+            }
+            tracing::info!(version = env!("CARGO_PKG_VERSION"), "Hito started");
+            
+            // Workspace label stores are created by the app itself, so they are always in scope
+            match workspaces::store_directory(app.handle()).and_then(|store_dir| scope::grant(&store_dir)) {
+                Ok(_) => {}
+                Err(e) => tracing::warn!(error = ?e, "Failed to open the workspaces directory"),
+            }
+            
+            // Search roots and custom data files stay usable across restarts, so search_labels
+            // can read them without the user reopening every folder
+            match load_app_data(app.handle().clone()) {
+                Ok(app_data) => scope::grant_saved(&app_data),
+                Err(e) => tracing::warn!(error = ?e, "Failed to restore the saved data file locations"),
+            }
+            
             // File drops in Tauri 2.0 are handled through the event system
             // JavaScript will listen for tauri://drag-drop events
             Ok(())
@@ -902,8 +2171,37 @@ mod tests {
     use super::*;
     use std::fs;
     use std::io::Write;
+    use tauri::async_runtime::block_on;
+    use crate::scope::granted_temp_dir;
     use tempfile::TempDir;
 
+    /// `sort_images` with the options every caller passes, without a request.
+    fn filter_sort(
+        images: Vec<ImagePath>,
+        sort_option: String,
+        sort_direction: String,
+        image_categories: Vec<(String, Vec<CategoryAssignment>)>,
+        filter_options: Option<FilterOptions>,
+    ) -> Result<Vec<ImagePath>, HitoError> {
+        sort_with_labels(images, sort_option, sort_direction, filter_options, ImageLabels::new(image_categories, None, None))
+    }
+
+    fn sort_with_labels(
+        images: Vec<ImagePath>,
+        sort_option: String,
+        sort_direction: String,
+        filter_options: Option<FilterOptions>,
+        labels: ImageLabels,
+    ) -> Result<Vec<ImagePath>, HitoError> {
+        let options = SortOptions {
+            sort_option: Some(sort_option),
+            sort_direction: Some(sort_direction),
+            filter_options,
+            ..Default::default()
+        };
+        filter_and_sort_images(&images, &labels, options, &CancelToken::default())
+    }
+
     #[test]
     fn test_get_parent_directory() {
         // Test normal path
@@ -935,21 +2233,24 @@ mod tests {
                 path: "/test/zebra.jpg".to_string(),
                 size: Some(1000),
                 created_at: None,
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/apple.jpg".to_string(),
                 size: Some(2000),
                 created_at: None,
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/banana.jpg".to_string(),
                 size: Some(1500),
                 created_at: None,
+                ..Default::default()
             },
         ];
 
         // Test ascending sort
-        let result = sort_images(
+        let result = filter_sort(
             images.clone(),
             "name".to_string(),
             "ascending".to_string(),
@@ -963,7 +2264,7 @@ mod tests {
         assert_eq!(result[2].path, "/test/zebra.jpg");
 
         // Test descending sort
-        let result = sort_images(
+        let result = filter_sort(
             images,
             "name".to_string(),
             "descending".to_string(),
@@ -984,21 +2285,24 @@ mod tests {
                 path: "/test/large.jpg".to_string(),
                 size: Some(3000),
                 created_at: None,
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/small.jpg".to_string(),
                 size: Some(1000),
                 created_at: None,
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/medium.jpg".to_string(),
                 size: Some(2000),
                 created_at: None,
+                ..Default::default()
             },
         ];
 
         // Test ascending sort
-        let result = sort_images(
+        let result = filter_sort(
             images.clone(),
             "size".to_string(),
             "ascending".to_string(),
@@ -1012,7 +2316,7 @@ mod tests {
         assert_eq!(result[2].size, Some(3000));
 
         // Test descending sort
-        let result = sort_images(
+        let result = filter_sort(
             images,
             "size".to_string(),
             "descending".to_string(),
@@ -1033,21 +2337,24 @@ mod tests {
                 path: "/test/new.jpg".to_string(),
                 size: Some(1000),
                 created_at: Some("2024-01-03T00:00:00Z".to_string()),
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/old.jpg".to_string(),
                 size: Some(2000),
                 created_at: Some("2024-01-01T00:00:00Z".to_string()),
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/middle.jpg".to_string(),
                 size: Some(1500),
                 created_at: Some("2024-01-02T00:00:00Z".to_string()),
+                ..Default::default()
             },
         ];
 
         // Test ascending sort
-        let result = sort_images(
+        let result = filter_sort(
             images.clone(),
             "dateCreated".to_string(),
             "ascending".to_string(),
@@ -1061,7 +2368,7 @@ mod tests {
         assert_eq!(result[2].path, "/test/new.jpg");
 
         // Test descending sort
-        let result = sort_images(
+        let result = filter_sort(
             images,
             "dateCreated".to_string(),
             "descending".to_string(),
@@ -1082,16 +2389,19 @@ mod tests {
                 path: "/test/img1.jpg".to_string(),
                 size: Some(1000),
                 created_at: None,
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/img2.jpg".to_string(),
                 size: Some(2000),
                 created_at: None,
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/img3.jpg".to_string(),
                 size: Some(1500),
                 created_at: None,
+                ..Default::default()
             },
         ];
 
@@ -1101,6 +2411,7 @@ mod tests {
                 vec![CategoryAssignment {
                     category_id: "cat1".to_string(),
                     assigned_at: "2024-01-03T00:00:00Z".to_string(),
+                    ..Default::default()
                 }],
             ),
             (
@@ -1108,13 +2419,14 @@ mod tests {
                 vec![CategoryAssignment {
                     category_id: "cat1".to_string(),
                     assigned_at: "2024-01-01T00:00:00Z".to_string(),
+                    ..Default::default()
                 }],
             ),
             // img3 has no categories (uncategorized)
         ];
 
         // Test ascending sort (uncategorized first, then by date)
-        let result = sort_images(
+        let result = filter_sort(
             images.clone(),
             "lastCategorized".to_string(),
             "ascending".to_string(),
@@ -1129,7 +2441,7 @@ mod tests {
         assert_eq!(result[2].path, "/test/img1.jpg"); // newer category date
 
         // Test descending sort
-        let result = sort_images(
+        let result = filter_sort(
             images,
             "lastCategorized".to_string(),
             "descending".to_string(),
@@ -1150,16 +2462,19 @@ mod tests {
                 path: "/test/img1.jpg".to_string(),
                 size: Some(1000),
                 created_at: None,
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/img2.jpg".to_string(),
                 size: Some(2000),
                 created_at: None,
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/img3.jpg".to_string(),
                 size: Some(1500),
                 created_at: None,
+                ..Default::default()
             },
         ];
 
@@ -1169,6 +2484,7 @@ mod tests {
                 vec![CategoryAssignment {
                     category_id: "cat1".to_string(),
                     assigned_at: "2024-01-01T00:00:00Z".to_string(),
+                    ..Default::default()
                 }],
             ),
             (
@@ -1176,6 +2492,7 @@ mod tests {
                 vec![CategoryAssignment {
                     category_id: "cat2".to_string(),
                     assigned_at: "2024-01-01T00:00:00Z".to_string(),
+                    ..Default::default()
                 }],
             ),
             // img3 has no categories
@@ -1189,9 +2506,10 @@ mod tests {
             size_operator: None,
             size_value: None,
             size_value2: None,
+            ..Default::default()
         };
 
-        let result = sort_images(
+        let result = filter_sort(
             images.clone(),
             "name".to_string(),
             "ascending".to_string(),
@@ -1211,9 +2529,10 @@ mod tests {
             size_operator: None,
             size_value: None,
             size_value2: None,
+            ..Default::default()
         };
 
-        let result = sort_images(
+        let result = filter_sort(
             images,
             "name".to_string(),
             "ascending".to_string(),
@@ -1233,16 +2552,19 @@ mod tests {
                 path: "/test/apple.jpg".to_string(),
                 size: Some(1000),
                 created_at: None,
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/banana.jpg".to_string(),
                 size: Some(2000),
                 created_at: None,
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/grape.jpg".to_string(),
                 size: Some(1500),
                 created_at: None,
+                ..Default::default()
             },
         ];
 
@@ -1254,9 +2576,10 @@ mod tests {
             size_operator: None,
             size_value: None,
             size_value2: None,
+            ..Default::default()
         };
 
-        let result = sort_images(
+        let result = filter_sort(
             images.clone(),
             "name".to_string(),
             "ascending".to_string(),
@@ -1277,9 +2600,10 @@ mod tests {
             size_operator: None,
             size_value: None,
             size_value2: None,
+            ..Default::default()
         };
 
-        let result = sort_images(
+        let result = filter_sort(
             images.clone(),
             "name".to_string(),
             "ascending".to_string(),
@@ -1299,9 +2623,10 @@ mod tests {
             size_operator: None,
             size_value: None,
             size_value2: None,
+            ..Default::default()
         };
 
-        let result = sort_images(
+        let result = filter_sort(
             images.clone(),
             "name".to_string(),
             "ascending".to_string(),
@@ -1321,9 +2646,10 @@ mod tests {
             size_operator: None,
             size_value: None,
             size_value2: None,
+            ..Default::default()
         };
 
-        let result = sort_images(
+        let result = filter_sort(
             images,
             "name".to_string(),
             "ascending".to_string(),
@@ -1343,16 +2669,19 @@ mod tests {
                 path: "/test/small.jpg".to_string(),
                 size: Some(1024), // 1 KB
                 created_at: None,
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/medium.jpg".to_string(),
                 size: Some(5120), // 5 KB
                 created_at: None,
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/large.jpg".to_string(),
                 size: Some(10240), // 10 KB
                 created_at: None,
+                ..Default::default()
             },
         ];
 
@@ -1364,9 +2693,10 @@ mod tests {
             size_operator: Some("largerThan".to_string()),
             size_value: Some("3".to_string()), // 3 KB
             size_value2: None,
+            ..Default::default()
         };
 
-        let result = sort_images(
+        let result = filter_sort(
             images.clone(),
             "name".to_string(),
             "ascending".to_string(),
@@ -1387,9 +2717,10 @@ mod tests {
             size_operator: Some("lessThan".to_string()),
             size_value: Some("3".to_string()), // 3 KB
             size_value2: None,
+            ..Default::default()
         };
 
-        let result = sort_images(
+        let result = filter_sort(
             images.clone(),
             "name".to_string(),
             "ascending".to_string(),
@@ -1409,9 +2740,10 @@ mod tests {
             size_operator: Some("between".to_string()),
             size_value: Some("2".to_string()), // 2 KB
             size_value2: Some("8".to_string()), // 8 KB
+            ..Default::default()
         };
 
-        let result = sort_images(
+        let result = filter_sort(
             images,
             "name".to_string(),
             "ascending".to_string(),
@@ -1431,16 +2763,19 @@ mod tests {
                 path: "/test/apple.jpg".to_string(),
                 size: Some(1024),
                 created_at: None,
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/banana.jpg".to_string(),
                 size: Some(5120),
                 created_at: None,
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/grape.jpg".to_string(),
                 size: Some(10240),
                 created_at: None,
+                ..Default::default()
             },
         ];
 
@@ -1449,6 +2784,7 @@ mod tests {
             vec![CategoryAssignment {
                 category_id: "fruit".to_string(),
                 assigned_at: "2024-01-01T00:00:00Z".to_string(),
+                ..Default::default()
             }],
         )];
 
@@ -1460,9 +2796,10 @@ mod tests {
             size_operator: Some("lessThan".to_string()),
             size_value: Some("5".to_string()), // 5 KB
             size_value2: None,
+            ..Default::default()
         };
 
-        let result = sort_images(
+        let result = filter_sort(
             images,
             "name".to_string(),
             "ascending".to_string(),
@@ -1477,7 +2814,7 @@ mod tests {
 
     #[test]
     fn test_list_images_with_temp_dir() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let test_dir = temp_dir.path();
 
         // Create subdirectories
@@ -1498,7 +2835,7 @@ mod tests {
         file3.write_all(b"not an image").unwrap();
         drop(file3);
 
-        let result = list_images(test_dir.to_str().unwrap().to_string()).unwrap();
+        let result = block_on(list_directory(test_dir.to_str().unwrap().to_string(), None, None, None)).unwrap();
 
         // Should find 2 images
         assert_eq!(result.images.len(), 2);
@@ -1519,29 +2856,31 @@ mod tests {
 
     #[test]
     fn test_list_images_nonexistent_path() {
-        let result = list_images("/nonexistent/path/that/does/not/exist".to_string());
+        let temp_dir = granted_temp_dir();
+        let path = temp_dir.path().join("nonexistent/path");
+        let result = block_on(list_directory(path.to_str().unwrap().to_string(), None, None, None));
         match result {
-            Err(e) => assert!(e.contains("does not exist")),
+            Err(e) => assert!(e.message.contains("does not exist")),
             Ok(_) => panic!("Expected error for nonexistent path"),
         }
     }
 
     #[test]
     fn test_list_images_not_a_directory() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let test_file = temp_dir.path().join("file.txt");
         fs::File::create(&test_file).unwrap();
 
-        let result = list_images(test_file.to_str().unwrap().to_string());
+        let result = block_on(list_directory(test_file.to_str().unwrap().to_string(), None, None, None));
         match result {
-            Err(e) => assert!(e.contains("not a directory")),
+            Err(e) => assert!(e.message.contains("not a directory")),
             Ok(_) => panic!("Expected error for file path"),
         }
     }
 
     #[test]
     fn test_load_image_with_temp_file() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let test_file = temp_dir.path().join("test.png");
 
         // Create a minimal PNG file (1x1 pixel PNG)
@@ -1553,7 +2892,7 @@ mod tests {
         ];
         fs::write(&test_file, &png_data).unwrap();
 
-        let result = load_image(test_file.to_str().unwrap().to_string()).unwrap();
+        let result = block_on(load_image(test_file.to_str().unwrap().to_string(), None)).unwrap();
 
         assert!(result.starts_with("data:image/png;base64,"));
         assert!(result.len() > 30); // Should have base64 data
@@ -1561,14 +2900,16 @@ mod tests {
 
     #[test]
     fn test_load_image_nonexistent() {
-        let result = load_image("/nonexistent/image.jpg".to_string());
+        let temp_dir = granted_temp_dir();
+        let image = temp_dir.path().join("nonexistent.jpg");
+        let result = block_on(load_image(image.to_str().unwrap().to_string(), None));
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("does not exist"));
+        assert!(result.unwrap_err().message.contains("does not exist"));
     }
 
     #[test]
     fn test_load_image_mime_types() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
 
         // Test different image extensions
         let extensions = vec![
@@ -1586,7 +2927,7 @@ mod tests {
             let test_file = temp_dir.path().join(format!("test.{}", ext));
             fs::File::create(&test_file).unwrap();
 
-            let result = load_image(test_file.to_str().unwrap().to_string()).unwrap();
+            let result = block_on(load_image(test_file.to_str().unwrap().to_string(), None)).unwrap();
             assert!(
                 result.starts_with(&format!("data:{};base64,", expected_mime)),
                 "Failed for extension: {}",
@@ -1603,10 +2944,16 @@ mod tests {
                 vec![CategoryAssignment {
                     category_id: "cat1".to_string(),
                     assigned_at: "2024-01-01T00:00:00Z".to_string(),
+                    ..Default::default()
                 }],
             )],
             categories: None,
             hotkeys: None,
+            image_notes: None,
+            attributes: None,
+            image_attributes: None,
+            review: None,
+            batch_images: None,
         };
 
         let json = serde_json::to_string_pretty(&hito_file).unwrap();
@@ -1643,6 +2990,12 @@ mod tests {
                 map.insert("/test/dir".to_string(), "/custom/path.json".to_string());
                 map
             }),
+            annotator: Some("alice".to_string()),
+            search_roots: None,
+            workspaces: None,
+            presets: None,
+            recent_directories: None,
+            view_states: None,
         };
 
         let json = serde_json::to_string_pretty(&app_data).unwrap();
@@ -1659,11 +3012,12 @@ mod tests {
                 .get("/test/dir"),
             Some(&"/custom/path.json".to_string())
         );
+        assert_eq!(deserialized.annotator.as_deref(), Some("alice"));
     }
 
     #[test]
     fn test_delete_image() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let test_file = temp_dir.path().join("test.jpg");
         
         // Create a test file
@@ -1671,7 +3025,7 @@ mod tests {
         assert!(test_file.exists());
 
         // Delete the image
-        let result = delete_image(test_file.to_str().unwrap().to_string());
+        let result = block_on(delete_image(test_file.to_str().unwrap().to_string(), None, None));
         assert!(result.is_ok());
         
         // Verify the file was actually removed from the original path
@@ -1682,32 +3036,34 @@ mod tests {
 
     #[test]
     fn test_delete_image_nonexistent() {
-        let result = delete_image("/nonexistent/image.jpg".to_string());
+        let temp_dir = granted_temp_dir();
+        let image = temp_dir.path().join("nonexistent.jpg");
+        let result = block_on(delete_image(image.to_str().unwrap().to_string(), None, None));
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("does not exist"));
+        assert!(result.unwrap_err().message.contains("does not exist"));
     }
 
     #[test]
     fn test_delete_image_not_a_file() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let test_dir = temp_dir.path().join("subdir");
         fs::create_dir_all(&test_dir).unwrap();
 
-        let result = delete_image(test_dir.to_str().unwrap().to_string());
+        let result = block_on(delete_image(test_dir.to_str().unwrap().to_string(), None, None));
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("not a file"));
+        assert!(result.unwrap_err().message.contains("not a file"));
     }
 
     #[test]
     fn test_load_hito_config_nonexistent() {
-        let temp_dir = TempDir::new().unwrap();
-        let result = load_hito_config(temp_dir.path().to_str().unwrap().to_string(), None).unwrap();
+        let temp_dir = granted_temp_dir();
+        let result = load_hito_config(temp_dir.path().to_str().unwrap().to_string(), None, None).unwrap();
         assert_eq!(result.image_categories.len(), 0);
     }
 
     #[test]
     fn test_load_hito_config_with_file() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let hito_file = temp_dir.path().join(".hito.json");
         
         let test_data = HitoFile {
@@ -1716,23 +3072,29 @@ mod tests {
                 vec![CategoryAssignment {
                     category_id: "cat1".to_string(),
                     assigned_at: "2024-01-01T00:00:00Z".to_string(),
+                    ..Default::default()
                 }],
             )],
             categories: None,
             hotkeys: None,
+            image_notes: None,
+            attributes: None,
+            image_attributes: None,
+            review: None,
+            batch_images: None,
         };
         
         let json = serde_json::to_string_pretty(&test_data).unwrap();
         fs::write(&hito_file, json).unwrap();
 
-        let result = load_hito_config(temp_dir.path().to_str().unwrap().to_string(), None).unwrap();
+        let result = load_hito_config(temp_dir.path().to_str().unwrap().to_string(), None, None).unwrap();
         assert_eq!(result.image_categories.len(), 1);
         assert_eq!(result.image_categories[0].0, "/test/image.jpg");
     }
 
     #[test]
     fn test_load_hito_config_custom_filename() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let custom_file = temp_dir.path().join("custom.json");
         
         let test_data = HitoFile {
@@ -1741,10 +3103,16 @@ mod tests {
                 vec![CategoryAssignment {
                     category_id: "cat1".to_string(),
                     assigned_at: "2024-01-01T00:00:00Z".to_string(),
+                    ..Default::default()
                 }],
             )],
             categories: None,
             hotkeys: None,
+            image_notes: None,
+            attributes: None,
+            image_attributes: None,
+            review: None,
+            batch_images: None,
         };
         
         let json = serde_json::to_string_pretty(&test_data).unwrap();
@@ -1753,26 +3121,27 @@ mod tests {
         let result = load_hito_config(
             temp_dir.path().to_str().unwrap().to_string(),
             Some("custom.json".to_string()),
+            None,
         ).unwrap();
         assert_eq!(result.image_categories.len(), 1);
     }
 
     #[test]
     fn test_load_hito_config_invalid_json() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let hito_file = temp_dir.path().join(".hito.json");
         fs::write(&hito_file, "invalid json").unwrap();
 
-        let result = load_hito_config(temp_dir.path().to_str().unwrap().to_string(), None);
+        let result = load_hito_config(temp_dir.path().to_str().unwrap().to_string(), None, None);
         match result {
-            Err(e) => assert!(e.contains("Failed to parse")),
+            Err(e) => assert!(e.message.contains("Failed to parse")),
             Ok(_) => panic!("Expected error for invalid JSON"),
         }
     }
 
     #[test]
     fn test_save_hito_config() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let hito_file = temp_dir.path().join(".hito.json");
 
         let image_categories = vec![(
@@ -1780,6 +3149,7 @@ mod tests {
             vec![CategoryAssignment {
                 category_id: "cat1".to_string(),
                 assigned_at: "2024-01-01T00:00:00Z".to_string(),
+                ..Default::default()
             }],
         )];
 
@@ -1789,6 +3159,7 @@ mod tests {
             None,
             None,
             None,
+            None,
         );
         assert!(result.is_ok());
         assert!(hito_file.exists());
@@ -1801,7 +3172,7 @@ mod tests {
 
     #[test]
     fn test_save_hito_config_custom_filename() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let custom_file = temp_dir.path().join("custom.json");
 
         let image_categories = vec![(
@@ -1809,6 +3180,7 @@ mod tests {
             vec![CategoryAssignment {
                 category_id: "cat1".to_string(),
                 assigned_at: "2024-01-01T00:00:00Z".to_string(),
+                ..Default::default()
             }],
         )];
 
@@ -1818,6 +3190,7 @@ mod tests {
             Some("custom.json".to_string()),
             None,
             None,
+            None,
         );
         assert!(result.is_ok());
         assert!(custom_file.exists());
@@ -1830,11 +3203,12 @@ mod tests {
                 path: "/test/img1.jpg".to_string(),
                 size: Some(1000),
                 created_at: None,
+                ..Default::default()
             },
         ];
 
         // Test with unknown sort option
-        let result = sort_images(
+        let result = filter_sort(
             images.clone(),
             "unknown".to_string(),
             "ascending".to_string(),
@@ -1850,7 +3224,7 @@ mod tests {
 
     #[test]
     fn test_list_images_files_without_extension() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let test_dir = temp_dir.path();
 
         // Create file without extension (should be ignored)
@@ -1863,7 +3237,7 @@ mod tests {
         file2.write_all(b"fake image").unwrap();
         drop(file2);
 
-        let result = list_images(test_dir.to_str().unwrap().to_string()).unwrap();
+        let result = block_on(list_directory(test_dir.to_str().unwrap().to_string(), None, None, None)).unwrap();
 
         // Should only find the image file
         assert_eq!(result.images.len(), 1);
@@ -1872,8 +3246,8 @@ mod tests {
 
     #[test]
     fn test_list_images_empty_directory() {
-        let temp_dir = TempDir::new().unwrap();
-        let result = list_images(temp_dir.path().to_str().unwrap().to_string()).unwrap();
+        let temp_dir = granted_temp_dir();
+        let result = block_on(list_directory(temp_dir.path().to_str().unwrap().to_string(), None, None, None)).unwrap();
 
         assert_eq!(result.images.len(), 0);
         assert_eq!(result.directories.len(), 0);
@@ -1882,13 +3256,13 @@ mod tests {
     #[test]
     fn test_load_image_read_error() {
         // Test with a directory instead of a file (should fail with "not a file")
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let test_dir = temp_dir.path().join("subdir");
         fs::create_dir_all(&test_dir).unwrap();
 
-        let result = load_image(test_dir.to_str().unwrap().to_string());
+        let result = block_on(load_image(test_dir.to_str().unwrap().to_string(), None));
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("not a file"));
+        assert!(result.unwrap_err().message.contains("not a file"));
     }
 
     #[test]
@@ -1898,6 +3272,7 @@ mod tests {
                 path: "/test/image.jpg".to_string(),
                 size: Some(1000),
                 created_at: None,
+                ..Default::default()
             },
         ];
 
@@ -1909,9 +3284,10 @@ mod tests {
             size_operator: None,
             size_value: None,
             size_value2: None,
+            ..Default::default()
         };
 
-        let result = sort_images(
+        let result = filter_sort(
             images,
             "name".to_string(),
             "ascending".to_string(),
@@ -1930,6 +3306,7 @@ mod tests {
                 path: "/test/image.jpg".to_string(),
                 size: Some(1000),
                 created_at: None,
+                ..Default::default()
             },
         ];
 
@@ -1941,9 +3318,10 @@ mod tests {
             size_operator: None,
             size_value: None,
             size_value2: None,
+            ..Default::default()
         };
 
-        let result = sort_images(
+        let result = filter_sort(
             images,
             "name".to_string(),
             "ascending".to_string(),
@@ -1962,6 +3340,7 @@ mod tests {
                 path: "/test/image.jpg".to_string(),
                 size: Some(1000),
                 created_at: None,
+                ..Default::default()
             },
         ];
 
@@ -1973,9 +3352,10 @@ mod tests {
             size_operator: Some("largerThan".to_string()),
             size_value: Some("".to_string()),
             size_value2: None,
+            ..Default::default()
         };
 
-        let result = sort_images(
+        let result = filter_sort(
             images,
             "name".to_string(),
             "ascending".to_string(),
@@ -1994,6 +3374,7 @@ mod tests {
                 path: "/test/image.jpg".to_string(),
                 size: Some(1000),
                 created_at: None,
+                ..Default::default()
             },
         ];
 
@@ -2005,9 +3386,10 @@ mod tests {
             size_operator: Some("between".to_string()),
             size_value: Some("2".to_string()),
             size_value2: Some("".to_string()),
+            ..Default::default()
         };
 
-        let result = sort_images(
+        let result = filter_sort(
             images,
             "name".to_string(),
             "ascending".to_string(),
@@ -2026,6 +3408,7 @@ mod tests {
                 path: "/test/image.jpg".to_string(),
                 size: Some(1000),
                 created_at: None,
+                ..Default::default()
             },
         ];
 
@@ -2037,9 +3420,10 @@ mod tests {
             size_operator: Some("between".to_string()),
             size_value: Some("2".to_string()),
             size_value2: Some("invalid".to_string()),
+            ..Default::default()
         };
 
-        let result = sort_images(
+        let result = filter_sort(
             images,
             "name".to_string(),
             "ascending".to_string(),
@@ -2058,16 +3442,18 @@ mod tests {
                 path: "/test/img1.jpg".to_string(),
                 size: None, // No size
                 created_at: None,
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/img2.jpg".to_string(),
                 size: Some(1000),
                 created_at: None,
+                ..Default::default()
             },
         ];
 
         // Sort by size - images with None should be treated as 0
-        let result = sort_images(
+        let result = filter_sort(
             images,
             "size".to_string(),
             "ascending".to_string(),
@@ -2087,16 +3473,18 @@ mod tests {
                 path: "/test/img1.jpg".to_string(),
                 size: Some(1000),
                 created_at: None, // No date
+                ..Default::default()
             },
             ImagePath {
                 path: "/test/img2.jpg".to_string(),
                 size: Some(2000),
                 created_at: Some("2024-01-01T00:00:00Z".to_string()),
+                ..Default::default()
             },
         ];
 
         // Sort by date - images with None should be last
-        let result = sort_images(
+        let result = filter_sort(
             images,
             "dateCreated".to_string(),
             "ascending".to_string(),
@@ -2124,7 +3512,7 @@ mod tests {
         // The function should return an error because the parent path contains invalid UTF-8
         let result = get_parent_directory(path_buf);
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Failed to convert path to string");
+        assert_eq!(result.unwrap_err().message, "Failed to convert path to string");
     }
 
     #[test]
@@ -2138,7 +3526,7 @@ mod tests {
 
     #[test]
     fn test_copy_image() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let source_file = temp_dir.path().join("source.jpg");
         let dest_dir = temp_dir.path().join("dest");
         let dest_file = dest_dir.join("source.jpg");
@@ -2149,10 +3537,10 @@ mod tests {
         assert!(source_file.exists());
         
         // Copy the image
-        let result = copy_image(
+        let result = block_on(copy_image(
             source_file.to_str().unwrap().to_string(),
             dest_dir.to_str().unwrap().to_string(),
-        );
+        ));
         assert!(result.is_ok());
         
         // Verify the destination file exists and has the same content
@@ -2167,67 +3555,67 @@ mod tests {
 
     #[test]
     fn test_copy_image_nonexistent_source() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let dest_dir = temp_dir.path().join("dest");
         fs::create_dir_all(&dest_dir).unwrap();
         
-        let result = copy_image(
-            "/nonexistent/image.jpg".to_string(),
+        let result = block_on(copy_image(
+            temp_dir.path().join("nonexistent.jpg").to_str().unwrap().to_string(),
             dest_dir.to_str().unwrap().to_string(),
-        );
+        ));
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("does not exist"));
+        assert!(result.unwrap_err().message.contains("does not exist"));
     }
 
     #[test]
     fn test_copy_image_not_a_file() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let source_dir = temp_dir.path().join("source_dir");
         let dest_dir = temp_dir.path().join("dest");
         fs::create_dir_all(&source_dir).unwrap();
         fs::create_dir_all(&dest_dir).unwrap();
         
-        let result = copy_image(
+        let result = block_on(copy_image(
             source_dir.to_str().unwrap().to_string(),
             dest_dir.to_str().unwrap().to_string(),
-        );
+        ));
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("not a file"));
+        assert!(result.unwrap_err().message.contains("not a file"));
     }
 
     #[test]
     fn test_copy_image_nonexistent_destination() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let source_file = temp_dir.path().join("source.jpg");
         fs::write(&source_file, b"fake image data").unwrap();
         
-        let result = copy_image(
+        let result = block_on(copy_image(
             source_file.to_str().unwrap().to_string(),
-            "/nonexistent/destination".to_string(),
-        );
+            temp_dir.path().join("nonexistent").to_str().unwrap().to_string(),
+        ));
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("does not exist"));
+        assert!(result.unwrap_err().message.contains("does not exist"));
     }
 
     #[test]
     fn test_copy_image_destination_not_a_directory() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let source_file = temp_dir.path().join("source.jpg");
         let dest_file = temp_dir.path().join("dest.txt");
         fs::write(&source_file, b"fake image data").unwrap();
         fs::write(&dest_file, b"not a directory").unwrap();
         
-        let result = copy_image(
+        let result = block_on(copy_image(
             source_file.to_str().unwrap().to_string(),
             dest_file.to_str().unwrap().to_string(),
-        );
+        ));
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("not a directory"));
+        assert!(result.unwrap_err().message.contains("not a directory"));
     }
 
     #[test]
     fn test_copy_image_overwrites_existing() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let source_file = temp_dir.path().join("source.jpg");
         let dest_dir = temp_dir.path().join("dest");
         let dest_file = dest_dir.join("source.jpg");
@@ -2240,10 +3628,10 @@ mod tests {
         fs::write(&dest_file, b"old image data").unwrap();
         
         // Copy should overwrite the existing file
-        let result = copy_image(
+        let result = block_on(copy_image(
             source_file.to_str().unwrap().to_string(),
             dest_dir.to_str().unwrap().to_string(),
-        );
+        ));
         assert!(result.is_ok());
         
         // Verify the destination file has the new content
@@ -2253,7 +3641,7 @@ mod tests {
 
     #[test]
     fn test_move_image() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let source_file = temp_dir.path().join("source.jpg");
         let dest_dir = temp_dir.path().join("dest");
         let dest_file = dest_dir.join("source.jpg");
@@ -2264,10 +3652,12 @@ mod tests {
         assert!(source_file.exists());
         
         // Move the image
-        let result = move_image(
+        let result = block_on(move_image(
             source_file.to_str().unwrap().to_string(),
             dest_dir.to_str().unwrap().to_string(),
-        );
+            None,
+            None,
+        ));
         assert!(result.is_ok());
         
         // Verify the destination file exists and has the same content
@@ -2281,67 +3671,75 @@ mod tests {
 
     #[test]
     fn test_move_image_nonexistent_source() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let dest_dir = temp_dir.path().join("dest");
         fs::create_dir_all(&dest_dir).unwrap();
         
-        let result = move_image(
-            "/nonexistent/image.jpg".to_string(),
+        let result = block_on(move_image(
+            temp_dir.path().join("nonexistent.jpg").to_str().unwrap().to_string(),
             dest_dir.to_str().unwrap().to_string(),
-        );
+            None,
+            None,
+        ));
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("does not exist"));
+        assert!(result.unwrap_err().message.contains("does not exist"));
     }
 
     #[test]
     fn test_move_image_not_a_file() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let source_dir = temp_dir.path().join("source_dir");
         let dest_dir = temp_dir.path().join("dest");
         fs::create_dir_all(&source_dir).unwrap();
         fs::create_dir_all(&dest_dir).unwrap();
         
-        let result = move_image(
+        let result = block_on(move_image(
             source_dir.to_str().unwrap().to_string(),
             dest_dir.to_str().unwrap().to_string(),
-        );
+            None,
+            None,
+        ));
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("not a file"));
+        assert!(result.unwrap_err().message.contains("not a file"));
     }
 
     #[test]
     fn test_move_image_nonexistent_destination() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let source_file = temp_dir.path().join("source.jpg");
         fs::write(&source_file, b"fake image data").unwrap();
         
-        let result = move_image(
+        let result = block_on(move_image(
             source_file.to_str().unwrap().to_string(),
-            "/nonexistent/destination".to_string(),
-        );
+            temp_dir.path().join("nonexistent").to_str().unwrap().to_string(),
+            None,
+            None,
+        ));
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("does not exist"));
+        assert!(result.unwrap_err().message.contains("does not exist"));
     }
 
     #[test]
     fn test_move_image_destination_not_a_directory() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let source_file = temp_dir.path().join("source.jpg");
         let dest_file = temp_dir.path().join("dest.txt");
         fs::write(&source_file, b"fake image data").unwrap();
         fs::write(&dest_file, b"not a directory").unwrap();
         
-        let result = move_image(
+        let result = block_on(move_image(
             source_file.to_str().unwrap().to_string(),
             dest_file.to_str().unwrap().to_string(),
-        );
+            None,
+            None,
+        ));
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("not a directory"));
+        assert!(result.unwrap_err().message.contains("not a directory"));
     }
 
     #[test]
     fn test_move_image_overwrites_existing() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let source_file = temp_dir.path().join("source.jpg");
         let dest_dir = temp_dir.path().join("dest");
         let dest_file = dest_dir.join("source.jpg");
@@ -2354,10 +3752,12 @@ mod tests {
         fs::write(&dest_file, b"old image data").unwrap();
         
         // Move should overwrite the existing file
-        let result = move_image(
+        let result = block_on(move_image(
             source_file.to_str().unwrap().to_string(),
             dest_dir.to_str().unwrap().to_string(),
-        );
+            None,
+            None,
+        ));
         assert!(result.is_ok());
         
         // Verify the destination file has the new content
@@ -2370,7 +3770,7 @@ mod tests {
 
     #[test]
     fn test_move_image_preserves_filename() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let source_file = temp_dir.path().join("original_name.jpg");
         let dest_dir = temp_dir.path().join("dest");
         let dest_file = dest_dir.join("original_name.jpg");
@@ -2378,10 +3778,12 @@ mod tests {
         fs::create_dir_all(&dest_dir).unwrap();
         fs::write(&source_file, b"fake image data").unwrap();
         
-        let result = move_image(
+        let result = block_on(move_image(
             source_file.to_str().unwrap().to_string(),
             dest_dir.to_str().unwrap().to_string(),
-        );
+            None,
+            None,
+        ));
         assert!(result.is_ok());
         
         // Verify the filename is preserved
@@ -2391,7 +3793,7 @@ mod tests {
 
     #[test]
     fn test_copy_image_preserves_filename() {
-        let temp_dir = TempDir::new().unwrap();
+        let temp_dir = granted_temp_dir();
         let source_file = temp_dir.path().join("original_name.jpg");
         let dest_dir = temp_dir.path().join("dest");
         let dest_file = dest_dir.join("original_name.jpg");
@@ -2399,10 +3801,10 @@ mod tests {
         fs::create_dir_all(&dest_dir).unwrap();
         fs::write(&source_file, b"fake image data").unwrap();
         
-        let result = copy_image(
+        let result = block_on(copy_image(
             source_file.to_str().unwrap().to_string(),
             dest_dir.to_str().unwrap().to_string(),
-        );
+        ));
         assert!(result.is_ok());
         
         // Verify the filename is preserved
@@ -2411,4 +3813,1165 @@ mod tests {
         assert!(source_file.exists());
     }
 
+    #[test]
+    fn test_set_and_get_image_notes() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+
+        let notes = set_image_note(
+            directory.clone(),
+            None,
+            "/test/image.jpg".to_string(),
+            "blurry but usable".to_string(),
+            Some("alice".to_string()),
+        )
+        .unwrap();
+        assert_eq!(notes.len(), 1);
+
+        set_image_note(
+            directory.clone(),
+            None,
+            "/test/image.jpg".to_string(),
+            "possible duplicate".to_string(),
+            Some("bob".to_string()),
+        )
+        .unwrap();
+
+        // Same author replaces their previous note
+        let notes = set_image_note(
+            directory.clone(),
+            None,
+            "/test/image.jpg".to_string(),
+            "blurry, reject".to_string(),
+            Some("alice".to_string()),
+        )
+        .unwrap();
+        assert_eq!(notes.len(), 2);
+
+        let notes = get_image_notes(directory.clone(), None, "/test/image.jpg".to_string()).unwrap();
+        assert_eq!(notes.len(), 2);
+        assert!(notes.iter().any(|n| n.author.as_deref() == Some("alice") && n.text == "blurry, reject"));
+        assert!(notes.iter().any(|n| n.author.as_deref() == Some("bob")));
+
+        // Images without notes return an empty list
+        let notes = get_image_notes(directory, None, "/test/other.jpg".to_string()).unwrap();
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn test_set_image_note_empty_text_removes_note() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+
+        set_image_note(
+            directory.clone(),
+            None,
+            "/test/image.jpg".to_string(),
+            "check later".to_string(),
+            None,
+        )
+        .unwrap();
+
+        let notes = set_image_note(
+            directory.clone(),
+            None,
+            "/test/image.jpg".to_string(),
+            "   ".to_string(),
+            None,
+        )
+        .unwrap();
+        assert!(notes.is_empty());
+
+        let loaded = load_hito_config(directory, None, None).unwrap();
+        assert!(loaded.image_notes.is_none());
+    }
+
+    #[test]
+    fn test_concurrent_note_writes_are_not_lost() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+
+        let writers: Vec<_> = (0..8)
+            .map(|n| {
+                let directory = directory.clone();
+                std::thread::spawn(move || {
+                    set_image_note(directory, None, "/test/image.jpg".to_string(), format!("note {}", n), Some(format!("author{}", n)))
+                        .unwrap();
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let notes = get_image_notes(directory, None, "/test/image.jpg".to_string()).unwrap();
+        assert_eq!(notes.len(), 8);
+    }
+
+    #[test]
+    fn test_save_hito_config_keeps_unreadable_file() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        let hito_path = temp_dir.path().join(".hito.json");
+        fs::write(&hito_path, r#"{"image_categories": [], "image_notes": [["/a.jpg", [{"#).unwrap();
+
+        let error = save_hito_config(directory, vec![], None, None, None, None).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidData);
+        assert!(fs::read_to_string(&hito_path).unwrap().contains("image_notes"));
+    }
+
+    #[test]
+    fn test_save_hito_config_preserves_notes() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+
+        set_image_note(
+            directory.clone(),
+            None,
+            "/test/image.jpg".to_string(),
+            "edge case".to_string(),
+            None,
+        )
+        .unwrap();
+
+        save_hito_config(directory.clone(), Vec::new(), None, None, None, None).unwrap();
+
+        let notes = get_image_notes(directory, None, "/test/image.jpg".to_string()).unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].text, "edge case");
+    }
+
+    #[test]
+    fn test_filter_by_notes() {
+        let images = vec![
+            ImagePath {
+                path: "/test/img1.jpg".to_string(),
+                size: Some(1000),
+                created_at: None,
+                ..Default::default()
+            },
+            ImagePath {
+                path: "/test/img2.jpg".to_string(),
+                size: Some(2000),
+                created_at: None,
+                ..Default::default()
+            },
+        ];
+
+        let image_notes = vec![(
+            "/test/img1.jpg".to_string(),
+            vec![ImageNote {
+                text: "Possible duplicate of img7".to_string(),
+                author: None,
+                created_at: "2024-01-01T00:00:00Z".to_string(),
+            }],
+        )];
+
+        // Images with a note
+        let filter_options = FilterOptions {
+            has_note: Some(true),
+            ..Default::default()
+        };
+
+        let result = sort_with_labels(
+            images.clone(),
+            "name".to_string(),
+            "ascending".to_string(),
+            Some(filter_options),
+            ImageLabels::new(Vec::new(), Some(image_notes.clone()), None),
+        )
+        .unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "/test/img1.jpg");
+
+        // Images without a note
+        let filter_options = FilterOptions {
+            has_note: Some(false),
+            ..Default::default()
+        };
+
+        let result = sort_with_labels(
+            images.clone(),
+            "name".to_string(),
+            "ascending".to_string(),
+            Some(filter_options),
+            ImageLabels::new(Vec::new(), Some(image_notes.clone()), None),
+        )
+        .unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "/test/img2.jpg");
+
+        // Note text search is case-insensitive
+        let filter_options = FilterOptions {
+            note_pattern: Some("DUPLICATE".to_string()),
+            ..Default::default()
+        };
+
+        let result = sort_with_labels(
+            images,
+            "name".to_string(),
+            "ascending".to_string(),
+            Some(filter_options),
+            ImageLabels::new(Vec::new(), Some(image_notes), None),
+        )
+        .unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "/test/img1.jpg");
+    }
+
+    fn write_attribute_definitions(directory: &str) {
+        save_attribute_definitions(
+            directory.to_string(),
+            None,
+            vec![
+                AttributeDefinition {
+                    id: "quality".to_string(),
+                    name: "Quality".to_string(),
+                    kind: "rating".to_string(),
+                    min: None,
+                    max: None,
+                    options: None,
+                },
+                AttributeDefinition {
+                    id: "blur".to_string(),
+                    name: "Blur".to_string(),
+                    kind: "numeric".to_string(),
+                    min: Some(0.0),
+                    max: Some(3.0),
+                    options: None,
+                },
+                AttributeDefinition {
+                    id: "lighting".to_string(),
+                    name: "Lighting".to_string(),
+                    kind: "enum".to_string(),
+                    min: None,
+                    max: None,
+                    options: Some(vec!["dark".to_string(), "normal".to_string(), "bright".to_string()]),
+                },
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_set_and_get_image_attributes() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        write_attribute_definitions(&directory);
+
+        set_image_attribute(directory.clone(), None, "/test/image.jpg".to_string(), "quality".to_string(), Some(4.0)).unwrap();
+        set_image_attribute(directory.clone(), None, "/test/image.jpg".to_string(), "blur".to_string(), Some(1.5)).unwrap();
+        let values = set_image_attribute(directory.clone(), None, "/test/image.jpg".to_string(), "quality".to_string(), Some(5.0)).unwrap();
+
+        assert_eq!(values.len(), 2);
+        assert!(values.iter().any(|v| v.attribute_id == "quality" && v.value == 5.0));
+
+        // Clearing a value removes it
+        let values = set_image_attribute(directory.clone(), None, "/test/image.jpg".to_string(), "blur".to_string(), None).unwrap();
+        assert_eq!(values.len(), 1);
+
+        let values = get_image_attributes(directory, None, "/test/image.jpg".to_string()).unwrap();
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].attribute_id, "quality");
+    }
+
+    #[test]
+    fn test_set_image_attribute_validation() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        write_attribute_definitions(&directory);
+
+        let set = |attribute_id: &str, value: f64| {
+            set_image_attribute(directory.clone(), None, "/test/image.jpg".to_string(), attribute_id.to_string(), Some(value))
+        };
+
+        // Ratings are whole numbers between 1 and 5
+        assert!(set("quality", 6.0).err().unwrap().message.contains("out of range"));
+        assert!(set("quality", 2.5).err().unwrap().message.contains("whole number"));
+        // Numeric values respect min/max
+        assert!(set("blur", 3.5).err().unwrap().message.contains("out of range"));
+        assert!(set("blur", 3.0).is_ok());
+        // Enum values are indices into the options
+        assert!(set("lighting", 3.0).err().unwrap().message.contains("Invalid option index"));
+        assert!(set("lighting", 2.0).is_ok());
+        // Unknown attributes are rejected
+        assert!(set("unknown", 1.0).err().unwrap().message.contains("Attribute not found"));
+    }
+
+    #[test]
+    fn test_save_attribute_definitions_drops_orphaned_values() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        write_attribute_definitions(&directory);
+
+        set_image_attribute(directory.clone(), None, "/test/image.jpg".to_string(), "blur".to_string(), Some(2.0)).unwrap();
+        save_attribute_definitions(directory.clone(), None, Vec::new()).unwrap();
+
+        let loaded = load_hito_config(directory, None, None).unwrap();
+        assert!(loaded.attributes.is_none());
+        assert!(loaded.image_attributes.is_none());
+    }
+
+    #[test]
+    fn test_sort_and_filter_by_attribute() {
+        let images = vec![
+            ImagePath {
+                path: "/test/img1.jpg".to_string(),
+                size: Some(1000),
+                created_at: None,
+                ..Default::default()
+            },
+            ImagePath {
+                path: "/test/img2.jpg".to_string(),
+                size: Some(2000),
+                created_at: None,
+                ..Default::default()
+            },
+            ImagePath {
+                path: "/test/img3.jpg".to_string(),
+                size: Some(1500),
+                created_at: None,
+                ..Default::default()
+            },
+        ];
+
+        let rating = |path: &str, value: f64| {
+            (
+                path.to_string(),
+                vec![AttributeValue {
+                    attribute_id: "quality".to_string(),
+                    value,
+                    assigned_at: "2024-01-01T00:00:00Z".to_string(),
+                }],
+            )
+        };
+        // img3 has no rating
+        let image_attributes = vec![rating("/test/img1.jpg", 2.0), rating("/test/img2.jpg", 5.0)];
+
+        let result = sort_with_labels(
+            images.clone(),
+            "attribute:quality".to_string(),
+            "descending".to_string(),
+            None,
+            ImageLabels::new(Vec::new(), None, Some(image_attributes.clone())),
+        )
+        .unwrap();
+
+        assert_eq!(result[0].path, "/test/img2.jpg");
+        assert_eq!(result[1].path, "/test/img1.jpg");
+        assert_eq!(result[2].path, "/test/img3.jpg");
+
+        // Range filter excludes images outside the range and images without a value
+        let filter_options = FilterOptions {
+            attribute_filters: Some(vec![AttributeFilter {
+                attribute_id: "quality".to_string(),
+                min: Some(3.0),
+                max: None,
+            }]),
+            ..Default::default()
+        };
+
+        let result = sort_with_labels(
+            images,
+            "name".to_string(),
+            "ascending".to_string(),
+            Some(filter_options),
+            ImageLabels::new(Vec::new(), None, Some(image_attributes)),
+        )
+        .unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "/test/img2.jpg");
+    }
+
+    #[test]
+    fn test_add_update_remove_region() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+
+        let region = add_region(
+            directory.clone(),
+            None,
+            "/test/image.jpg".to_string(),
+            "defect".to_string(),
+            Geometry::Bbox { x: 0.1, y: 0.2, width: 0.3, height: 0.4 },
+            None,
+        )
+        .unwrap();
+
+        // Adding a region assigns the category
+        let loaded = load_hito_config(directory.clone(), None, None).unwrap();
+        assert_eq!(loaded.image_categories.len(), 1);
+        let assignment = &loaded.image_categories[0].1[0];
+        assert_eq!(assignment.category_id, "defect");
+        assert_eq!(assignment.regions.as_ref().unwrap().len(), 1);
+
+        let updated = update_region(
+            directory.clone(),
+            None,
+            "/test/image.jpg".to_string(),
+            region.id.clone(),
+            Geometry::Point { x: 0.5, y: 0.5 },
+        )
+        .unwrap();
+        assert!(updated.geometry == Geometry::Point { x: 0.5, y: 0.5 });
+
+        remove_region(directory.clone(), None, "/test/image.jpg".to_string(), region.id.clone()).unwrap();
+
+        // The assignment stays as a whole-image label
+        let loaded = load_hito_config(directory.clone(), None, None).unwrap();
+        assert_eq!(loaded.image_categories[0].1.len(), 1);
+        assert!(loaded.image_categories[0].1[0].regions.is_none());
+
+        let result = remove_region(directory.clone(), None, "/test/image.jpg".to_string(), region.id);
+        assert!(result.unwrap_err().message.contains("Region not found"));
+
+        // Every change is in the event log
+        let log_path = events::get_event_log_path(&directory, None).unwrap();
+        let kinds: Vec<String> = events::read_events(&log_path).unwrap().into_iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec!["assign", "region_add", "region_update", "region_remove"]);
+    }
+
+    #[test]
+    fn test_annotator_save_keeps_regions() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        let bbox = Geometry::Bbox { x: 0.1, y: 0.2, width: 0.3, height: 0.4 };
+        add_region(directory.clone(), None, "/test/image.jpg".to_string(), "defect".to_string(), bbox, Some("alice".to_string()))
+            .unwrap();
+
+        // The frontend's copy of the assignment has no regions
+        let assigned_at = load_hito_config(directory.clone(), None, None).unwrap().image_categories[0].1[0].assigned_at.clone();
+        save_hito_config(
+            directory.clone(),
+            vec![
+                ("/test/image.jpg".to_string(), vec![annotated("defect", &assigned_at, None)]),
+                ("/test/other.jpg".to_string(), vec![annotated("defect", "2024-01-01T00:00:00Z", None)]),
+            ],
+            None,
+            None,
+            None,
+            Some("alice".to_string()),
+        )
+        .unwrap();
+
+        let stored = read_hito_file(&get_hito_file_path(&directory, None).unwrap()).unwrap();
+        let regions = |path: &str| {
+            let (_, assignments) = stored.image_categories.iter().find(|(p, _)| p == path).unwrap();
+            assignments[0].regions.as_ref().map(Vec::len)
+        };
+        assert_eq!(regions("/test/image.jpg"), Some(1));
+        assert_eq!(regions("/test/other.jpg"), None);
+    }
+
+    #[test]
+    fn test_label_changes_are_saved_when_the_event_log_fails() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        // A directory in place of the log makes every append fail
+        fs::create_dir(events::get_event_log_path(&directory, None).unwrap()).unwrap();
+
+        save_hito_config(
+            directory.clone(),
+            vec![("/test/image.jpg".to_string(), vec![annotated("defect", "2024-01-01T00:00:00Z", None)])],
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let bbox = Geometry::Bbox { x: 0.1, y: 0.2, width: 0.3, height: 0.4 };
+        let region = add_region(directory.clone(), None, "/test/image.jpg".to_string(), "defect".to_string(), bbox, None).unwrap();
+        update_region(directory.clone(), None, "/test/image.jpg".to_string(), region.id.clone(), Geometry::Point { x: 0.5, y: 0.5 })
+            .unwrap();
+        let stored = read_hito_file(&get_hito_file_path(&directory, None).unwrap()).unwrap();
+        assert!(stored.image_categories[0].1[0].regions.as_ref().unwrap()[0].geometry == Geometry::Point { x: 0.5, y: 0.5 });
+
+        remove_region(directory.clone(), None, "/test/image.jpg".to_string(), region.id).unwrap();
+        let stored = read_hito_file(&get_hito_file_path(&directory, None).unwrap()).unwrap();
+        assert!(stored.image_categories[0].1[0].regions.is_none());
+    }
+
+    #[test]
+    fn test_add_region_goes_on_the_annotators_assignment() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        save_hito_config(
+            directory.clone(),
+            vec![("/test/image.jpg".to_string(), vec![annotated("defect", "2024-01-01T00:00:00Z", None)])],
+            None,
+            None,
+            None,
+            Some("alice".to_string()),
+        )
+        .unwrap();
+
+        let bbox = Geometry::Bbox { x: 0.1, y: 0.2, width: 0.3, height: 0.4 };
+        add_region(directory.clone(), None, "/test/image.jpg".to_string(), "defect".to_string(), bbox.clone(), Some("alice".to_string()))
+            .unwrap();
+        add_region(directory.clone(), None, "/test/image.jpg".to_string(), "defect".to_string(), bbox, Some("bob".to_string()))
+            .unwrap();
+
+        let stored = read_hito_file(&get_hito_file_path(&directory, None).unwrap()).unwrap();
+        let assignments = &stored.image_categories[0].1;
+        assert_eq!(assignments.len(), 2);
+        assert!(assignments.iter().all(|a| a.regions.as_ref().is_some_and(|regions| regions.len() == 1)));
+        let annotators: Vec<Option<&str>> = assignments.iter().map(|a| a.annotator.as_deref()).collect();
+        assert_eq!(annotators, vec![Some("alice"), Some("bob")]);
+
+        // Bob's new assignment is logged under his name
+        let log_path = events::get_event_log_path(&directory, None).unwrap();
+        let assign = events::read_events(&log_path).unwrap().into_iter().rev().find(|e| e.kind == "assign").unwrap();
+        assert_eq!(assign.annotator.as_deref(), Some("bob"));
+    }
+
+    #[test]
+    fn test_region_geometry_validation() {
+        assert!(validate_geometry(&Geometry::Bbox { x: 0.5, y: 0.5, width: 0.6, height: 0.1 }).is_err());
+        assert!(validate_geometry(&Geometry::Bbox { x: 0.0, y: 0.0, width: 0.0, height: 0.5 }).is_err());
+        assert!(validate_geometry(&Geometry::Bbox { x: 0.0, y: 0.0, width: 1.0, height: 1.0 }).is_ok());
+        assert!(validate_geometry(&Geometry::Polygon { points: vec![[0.0, 0.0], [1.0, 0.0]] }).is_err());
+        assert!(validate_geometry(&Geometry::Polygon { points: vec![[0.0, 0.0], [1.0, 0.0], [0.5, 1.2]] }).is_err());
+        assert!(validate_geometry(&Geometry::Polygon { points: vec![[0.0, 0.0], [1.0, 0.0], [0.5, 1.0]] }).is_ok());
+        assert!(validate_geometry(&Geometry::Point { x: -0.1, y: 0.5 }).is_err());
+    }
+
+    #[test]
+    fn test_region_serialization() {
+        let assignment = CategoryAssignment {
+            category_id: "cat1".to_string(),
+            assigned_at: "2024-01-01T00:00:00Z".to_string(),
+            regions: Some(vec![Region {
+                id: "region_1".to_string(),
+                geometry: Geometry::Bbox { x: 0.1, y: 0.1, width: 0.2, height: 0.2 },
+                created_at: "2024-01-01T00:00:00Z".to_string(),
+            }]),
+            annotator: None,
+        };
+
+        let json = serde_json::to_value(&assignment).unwrap();
+        assert_eq!(json["regions"][0]["geometry"]["type"], "bbox");
+
+        // Assignments without regions keep the original format
+        let json = serde_json::to_string(&CategoryAssignment {
+            category_id: "cat1".to_string(),
+            assigned_at: "2024-01-01T00:00:00Z".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!json.contains("regions"));
+    }
+
+    fn annotated(category_id: &str, assigned_at: &str, annotator: Option<&str>) -> CategoryAssignment {
+        CategoryAssignment {
+            category_id: category_id.to_string(),
+            assigned_at: assigned_at.to_string(),
+            annotator: annotator.map(|a| a.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_save_hito_config_per_annotator() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+
+        save_hito_config(
+            directory.clone(),
+            vec![("/test/img1.jpg".to_string(), vec![annotated("cat", "2024-01-01T00:00:00Z", None)])],
+            None,
+            None,
+            None,
+            Some("alice".to_string()),
+        )
+        .unwrap();
+        save_hito_config(
+            directory.clone(),
+            vec![("/test/img1.jpg".to_string(), vec![annotated("dog", "2024-01-02T00:00:00Z", None)])],
+            None,
+            None,
+            None,
+            Some("bob".to_string()),
+        )
+        .unwrap();
+
+        // Both annotators' assignments are stored and stamped
+        let raw = read_hito_file(&temp_dir.path().join(".hito.json")).unwrap();
+        assert_eq!(raw.image_categories[0].1.len(), 2);
+        assert_eq!(list_annotators(directory.clone(), None).unwrap(), vec!["alice", "bob"]);
+
+        // Saving alice's view again replaces only her assignments
+        save_hito_config(
+            directory.clone(),
+            vec![("/test/img1.jpg".to_string(), Vec::new())],
+            None,
+            None,
+            None,
+            Some("alice".to_string()),
+        )
+        .unwrap();
+
+        let alice = load_hito_config(directory.clone(), None, Some("alice".to_string())).unwrap();
+        assert!(alice.image_categories.is_empty());
+        let bob = load_hito_config(directory, None, Some("bob".to_string())).unwrap();
+        assert_eq!(bob.image_categories[0].1.len(), 1);
+        assert_eq!(bob.image_categories[0].1[0].category_id, "dog");
+    }
+
+    #[test]
+    fn test_load_hito_config_merged_view() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+
+        save_hito_config(
+            directory.clone(),
+            vec![(
+                "/test/img1.jpg".to_string(),
+                vec![
+                    annotated("cat", "2024-01-01T00:00:00Z", Some("alice")),
+                    annotated("cat", "2024-01-03T00:00:00Z", Some("bob")),
+                    annotated("dog", "2024-01-02T00:00:00Z", Some("bob")),
+                ],
+            )],
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let merged = load_hito_config(directory.clone(), None, None).unwrap();
+        let assignments = &merged.image_categories[0].1;
+        assert_eq!(assignments.len(), 2);
+        let cat = assignments.iter().find(|a| a.category_id == "cat").unwrap();
+        assert_eq!(cat.annotator.as_deref(), Some("bob"));
+        assert_eq!(cat.assigned_at, "2024-01-03T00:00:00Z");
+    }
+
+    #[test]
+    fn test_save_logs_only_changed_annotators() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        let stored = vec![
+            annotated("cat", "2024-01-01T00:00:00Z", Some("alice")),
+            annotated("dog", "2024-01-02T00:00:00Z", Some("bob")),
+        ];
+        save_hito_config(directory.clone(), vec![("/test/img1.jpg".to_string(), stored)], None, None, None, None).unwrap();
+        let log_path = events::get_event_log_path(&directory, None).unwrap();
+        let logged = || events::read_events(&log_path).unwrap().len();
+        let before = logged();
+
+        // Saving the merged view or alice's view unchanged logs nothing for bob
+        let view = load_hito_config(directory.clone(), None, None).unwrap().image_categories;
+        save_hito_config(directory.clone(), view, None, None, None, None).unwrap();
+        let view = load_hito_config(directory.clone(), None, Some("alice".to_string())).unwrap().image_categories;
+        save_hito_config(directory.clone(), view, None, None, None, Some("alice".to_string())).unwrap();
+        assert_eq!(logged(), before);
+
+        save_hito_config(directory.clone(), Vec::new(), None, None, None, Some("alice".to_string())).unwrap();
+        let events = events::read_events(&log_path).unwrap();
+        assert_eq!(events.len(), before + 1);
+        assert_eq!(events[before].kind, "unassign");
+        assert_eq!(events[before].annotator.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn test_save_merged_view_keeps_other_annotators() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        let stored = vec![
+            annotated("cat", "2024-01-01T00:00:00Z", Some("alice")),
+            annotated("cat", "2024-01-03T00:00:00Z", Some("bob")),
+            annotated("dog", "2024-01-02T00:00:00Z", Some("bob")),
+        ];
+        save_hito_config(directory.clone(), vec![("/test/img1.jpg".to_string(), stored)], None, None, None, None).unwrap();
+
+        // Save the merged view back with "dog" removed and a new unattributed "bird"
+        let mut view = load_hito_config(directory.clone(), None, None).unwrap().image_categories;
+        view[0].1.retain(|a| a.category_id != "dog");
+        view[0].1.push(annotated("bird", "2024-01-04T00:00:00Z", None));
+        view[0].1.push(annotated("cat", "2024-01-04T00:00:00Z", None));
+        save_hito_config(directory.clone(), view, None, None, None, None).unwrap();
+
+        let raw = read_hito_file(&temp_dir.path().join(".hito.json")).unwrap();
+        let mut kept: Vec<(String, Option<String>)> = raw.image_categories[0].1
+            .iter()
+            .map(|a| (a.category_id.clone(), a.annotator.clone()))
+            .collect();
+        kept.sort();
+        assert_eq!(
+            kept,
+            vec![
+                ("bird".to_string(), None),
+                ("cat".to_string(), Some("alice".to_string())),
+                ("cat".to_string(), Some("bob".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_by_annotator() {
+        let images = vec![
+            ImagePath {
+                path: "/test/img1.jpg".to_string(),
+                size: Some(1000),
+                created_at: None,
+                ..Default::default()
+            },
+            ImagePath {
+                path: "/test/img2.jpg".to_string(),
+                size: Some(2000),
+                created_at: None,
+                ..Default::default()
+            },
+        ];
+
+        let image_categories = vec![
+            ("/test/img1.jpg".to_string(), vec![annotated("cat", "2024-01-01T00:00:00Z", Some("alice"))]),
+            ("/test/img2.jpg".to_string(), vec![annotated("cat", "2024-01-01T00:00:00Z", Some("bob"))]),
+        ];
+
+        let filter_options = FilterOptions {
+            annotator: Some("bob".to_string()),
+            ..Default::default()
+        };
+
+        let result = filter_sort(
+            images,
+            "name".to_string(),
+            "ascending".to_string(),
+            image_categories,
+            Some(filter_options),
+        )
+        .unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "/test/img2.jpg");
+    }
+
+    #[test]
+    fn test_filter_by_query() {
+        let images = vec![
+            ImagePath {
+                path: "/test/IMG_1.jpg".to_string(),
+                size: Some(600 * 1024),
+                created_at: None,
+                ..Default::default()
+            },
+            ImagePath {
+                path: "/test/IMG_2.jpg".to_string(),
+                size: Some(600 * 1024),
+                created_at: None,
+                ..Default::default()
+            },
+            ImagePath {
+                path: "/test/other.jpg".to_string(),
+                size: Some(600 * 1024),
+                created_at: None,
+                ..Default::default()
+            },
+        ];
+
+        let image_categories = vec![
+            ("/test/IMG_1.jpg".to_string(), vec![annotated("a", "2024-01-01T00:00:00Z", None)]),
+            ("/test/IMG_2.jpg".to_string(), vec![annotated("c", "2024-01-01T00:00:00Z", None)]),
+            ("/test/other.jpg".to_string(), vec![annotated("b", "2024-01-01T00:00:00Z", None)]),
+        ];
+
+        // The shorthand fields still apply alongside the query
+        let filter_options = FilterOptions {
+            query: Some(r"NOT cat c AND size > 500KB".to_string()),
+            name_pattern: Some("img".to_string()),
+            ..Default::default()
+        };
+
+        let result = filter_sort(
+            images.clone(),
+            "name".to_string(),
+            "ascending".to_string(),
+            image_categories.clone(),
+            Some(filter_options),
+        )
+        .unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "/test/IMG_1.jpg");
+
+        let filter_options = FilterOptions {
+            query: Some("cat a OR".to_string()),
+            ..Default::default()
+        };
+
+        let error = filter_sort(
+            images,
+            "name".to_string(),
+            "ascending".to_string(),
+            image_categories,
+            Some(filter_options),
+        )
+        .err()
+        .unwrap();
+
+        assert_eq!(error.code, ErrorCode::InvalidInput);
+        assert_eq!(error.field.as_deref(), Some("query"));
+        assert_eq!(error.position, Some(8));
+        assert_eq!(error.to_string(), "Invalid query: Unexpected end of query at position 8");
+    }
+
+    fn images_named(paths: &[&str]) -> Vec<ImagePath> {
+        paths
+            .iter()
+            .map(|path| ImagePath {
+                path: path.to_string(),
+                size: Some(1000),
+                created_at: None,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn filtered_paths(
+        images: Vec<ImagePath>,
+        image_categories: Vec<(String, Vec<CategoryAssignment>)>,
+        filter_options: FilterOptions,
+    ) -> Result<Vec<String>, HitoError> {
+        let result = filter_sort(
+            images,
+            "name".to_string(),
+            "ascending".to_string(),
+            image_categories,
+            Some(filter_options),
+        )?;
+        Ok(result.into_iter().map(|img| img.path).collect())
+    }
+
+    #[test]
+    fn test_filter_by_multiple_categories() {
+        let images = images_named(&["/test/a.jpg", "/test/b.jpg", "/test/c.jpg", "/test/d.jpg"]);
+        let image_categories = vec![
+            ("/test/a.jpg".to_string(), vec![annotated("x", "2024-01-01T00:00:00Z", None)]),
+            ("/test/b.jpg".to_string(), vec![annotated("y", "2024-01-01T00:00:00Z", None)]),
+            (
+                "/test/c.jpg".to_string(),
+                vec![annotated("x", "2024-01-01T00:00:00Z", None), annotated("y", "2024-01-01T00:00:00Z", None)],
+            ),
+        ];
+        let filter = |ids: &[&str], mode: Option<&str>| FilterOptions {
+            category_ids: Some(ids.iter().map(|id| id.to_string()).collect()),
+            category_match: mode.map(|m| m.to_string()),
+            ..Default::default()
+        };
+
+        let any = filtered_paths(images.clone(), image_categories.clone(), filter(&["x", "uncategorized"], None)).unwrap();
+        assert_eq!(any, vec!["/test/a.jpg", "/test/c.jpg", "/test/d.jpg"]);
+
+        let all = filtered_paths(images.clone(), image_categories.clone(), filter(&["x", "y"], Some("all"))).unwrap();
+        assert_eq!(all, vec!["/test/c.jpg"]);
+
+        let none = filtered_paths(images.clone(), image_categories.clone(), filter(&["x", "uncategorized"], Some("none"))).unwrap();
+        assert_eq!(none, vec!["/test/b.jpg"]);
+
+        assert!(filtered_paths(images, image_categories, filter(&["x"], Some("most"))).is_err());
+    }
+
+    #[test]
+    fn test_filter_by_label_count() {
+        let images = images_named(&["/test/a.jpg", "/test/b.jpg", "/test/c.jpg"]);
+        let image_categories = vec![
+            ("/test/a.jpg".to_string(), vec![annotated("x", "2024-01-01T00:00:00Z", None)]),
+            (
+                "/test/b.jpg".to_string(),
+                vec![
+                    annotated("x", "2024-01-01T00:00:00Z", Some("alice")),
+                    annotated("x", "2024-01-01T00:00:00Z", Some("bob")),
+                    annotated("y", "2024-01-01T00:00:00Z", Some("bob")),
+                ],
+            ),
+        ];
+
+        let conflicting = filtered_paths(
+            images.clone(),
+            image_categories.clone(),
+            FilterOptions { min_labels: Some(2), ..Default::default() },
+        )
+        .unwrap();
+        assert_eq!(conflicting, vec!["/test/b.jpg"]);
+
+        let at_most_one = filtered_paths(
+            images,
+            image_categories,
+            FilterOptions { max_labels: Some(1), ..Default::default() },
+        )
+        .unwrap();
+        assert_eq!(at_most_one, vec!["/test/a.jpg", "/test/c.jpg"]);
+    }
+
+    #[test]
+    fn test_filter_by_name_regex_and_glob() {
+        let images = images_named(&["/photos/2024/IMG_001.jpg", "/photos/2024/notes.png", "/photos/2023/IMG_002.jpg"]);
+        let filter = |pattern: &str, operator: &str, match_path: bool| FilterOptions {
+            name_pattern: Some(pattern.to_string()),
+            name_operator: Some(operator.to_string()),
+            name_match_path: Some(match_path),
+            name_case_sensitive: Some(true),
+            base_directory: Some("/photos".to_string()),
+            ..Default::default()
+        };
+
+        let result = filtered_paths(images.clone(), Vec::new(), filter(r"^IMG_\d+\.jpg$", "regex", false)).unwrap();
+        assert_eq!(result.len(), 2);
+
+        let result = filtered_paths(images.clone(), Vec::new(), filter("2024/*", "glob", true)).unwrap();
+        assert_eq!(result, vec!["/photos/2024/IMG_001.jpg", "/photos/2024/notes.png"]);
+
+        // Case-sensitive: the lowercase pattern no longer matches
+        let result = filtered_paths(images.clone(), Vec::new(), filter("img", "contains", false)).unwrap();
+        assert!(result.is_empty());
+
+        let error = filtered_paths(images, Vec::new(), filter("IMG_(", "regex", false)).err().unwrap();
+        assert_eq!(error.field.as_deref(), Some("name_pattern"));
+        assert!(error.message.starts_with("Invalid regular expression"));
+    }
+
+    #[test]
+    fn test_filter_by_dates() {
+        let mut images = images_named(&["/test/a.jpg", "/test/b.jpg", "/test/c.jpg"]);
+        images[0].created_at = Some("2024-01-01T10:00:00Z".to_string());
+        images[1].created_at = Some("2024-03-01T10:00:00Z".to_string());
+        images[0].modified_at = Some("2024-06-01T10:00:00Z".to_string());
+        let recent = (chrono::Utc::now() - chrono::Duration::minutes(30)).to_rfc3339();
+        let image_categories = vec![
+            (
+                "/test/a.jpg".to_string(),
+                vec![annotated("x", "2024-01-01T00:00:00Z", None), annotated("y", &recent, None)],
+            ),
+            ("/test/b.jpg".to_string(), vec![annotated("x", "2024-01-01T00:00:00Z", None)]),
+        ];
+        let filter = |field: &str, operator: &str, value: &str, value2: Option<&str>| FilterOptions {
+            date_filters: Some(vec![DateFilter {
+                field: field.to_string(),
+                operator: operator.to_string(),
+                value: value.to_string(),
+                value2: value2.map(|v| v.to_string()),
+            }]),
+            ..Default::default()
+        };
+
+        // Labelled in the last 2 hours (latest assignment counts)
+        let result = filtered_paths(images.clone(), image_categories.clone(), filter("categorized", "within", "2h", None)).unwrap();
+        assert_eq!(result, vec!["/test/a.jpg"]);
+
+        // Images without a creation time never match
+        let result = filtered_paths(images.clone(), image_categories.clone(), filter("created", "before", "2024-02-01", None)).unwrap();
+        assert_eq!(result, vec!["/test/a.jpg"]);
+
+        // Between is inclusive and accepts reversed bounds
+        let result = filtered_paths(
+            images.clone(),
+            image_categories.clone(),
+            filter("created", "between", "2024-03-01T10:00:00Z", Some("2024-01-01T10:00:00Z")),
+        )
+        .unwrap();
+        assert_eq!(result, vec!["/test/a.jpg", "/test/b.jpg"]);
+
+        // A date-only end includes that whole day
+        let result = filtered_paths(
+            images.clone(),
+            image_categories.clone(),
+            filter("created", "between", "2024-02-01", Some("2024-03-01")),
+        )
+        .unwrap();
+        assert_eq!(result, vec!["/test/b.jpg"]);
+        let result = filtered_paths(images.clone(), image_categories.clone(), filter("created", "before", "2024-03-01", None)).unwrap();
+        assert_eq!(result, vec!["/test/a.jpg", "/test/b.jpg"]);
+        let result = filtered_paths(images.clone(), image_categories.clone(), filter("created", "before", "2024-03-01T10:00:00Z", None)).unwrap();
+        assert_eq!(result, vec!["/test/a.jpg"]);
+
+        let result = filtered_paths(images.clone(), image_categories.clone(), filter("modified", "after", "2024-05-01", None)).unwrap();
+        assert_eq!(result, vec!["/test/a.jpg"]);
+
+        let error = filtered_paths(images.clone(), image_categories.clone(), filter("created", "before", "soon", None)).err().unwrap();
+        assert_eq!(error.field.as_deref(), Some("date_filters"));
+        assert!(filtered_paths(images, image_categories, filter("opened", "after", "2024-01-01", None)).is_err());
+    }
+
+    fn sort_key(key: &str, direction: &str) -> SortKey {
+        SortKey { key: key.to_string(), direction: Some(direction.to_string()) }
+    }
+
+    fn sorted_paths(
+        images: Vec<ImagePath>,
+        image_categories: Vec<(String, Vec<CategoryAssignment>)>,
+        sort_keys: Vec<SortKey>,
+        random_seed: Option<u64>,
+    ) -> Result<Vec<String>, HitoError> {
+        let options = SortOptions { sort_keys: Some(sort_keys), random_seed, ..Default::default() };
+        let labels = ImageLabels::new(image_categories, None, None);
+        let result = filter_and_sort_images(&images, &labels, options, &CancelToken::default())?;
+        Ok(result.into_iter().map(|img| img.path).collect())
+    }
+
+    #[test]
+    fn test_sort_by_name_is_natural() {
+        let images = images_named(&["/test/img10.jpg", "/test/img2.jpg", "/test/IMG1.jpg"]);
+
+        let ascending = sorted_paths(images.clone(), Vec::new(), vec![sort_key("name", "ascending")], None).unwrap();
+        assert_eq!(ascending, vec!["/test/IMG1.jpg", "/test/img2.jpg", "/test/img10.jpg"]);
+
+        let descending = sorted_paths(images, Vec::new(), vec![sort_key("name", "descending")], None).unwrap();
+        assert_eq!(descending, vec!["/test/img10.jpg", "/test/img2.jpg", "/test/IMG1.jpg"]);
+    }
+
+    #[test]
+    fn test_sort_by_multiple_keys() {
+        let mut images = images_named(&["/b/2.jpg", "/a/2.jpg", "/a/1.jpg", "/a/3.jpg"]);
+        images[0].created_at = Some("2024-01-02T00:00:00Z".to_string());
+        images[1].created_at = Some("2024-01-01T00:00:00Z".to_string());
+        images[2].created_at = Some("2024-01-01T00:00:00Z".to_string());
+        let image_categories = vec![
+            ("/b/2.jpg".to_string(), vec![annotated("x", "2024-01-01T00:00:00Z", None)]),
+            ("/a/2.jpg".to_string(), vec![annotated("x", "2024-01-01T00:00:00Z", None)]),
+            ("/a/1.jpg".to_string(), vec![annotated("x", "2024-01-01T00:00:00Z", None)]),
+        ];
+
+        let result = sorted_paths(
+            images.clone(),
+            image_categories.clone(),
+            vec![sort_key("category", "ascending"), sort_key("dateCreated", "descending"), sort_key("name", "ascending")],
+            None,
+        )
+        .unwrap();
+        assert_eq!(result, vec!["/b/2.jpg", "/a/1.jpg", "/a/2.jpg", "/a/3.jpg"]);
+
+        // Ties on every key fall back to the path, whatever the input order
+        let mut reversed = images.clone();
+        reversed.reverse();
+        let by_name = sorted_paths(reversed, Vec::new(), vec![sort_key("name", "ascending")], None).unwrap();
+        assert_eq!(by_name, vec!["/a/1.jpg", "/a/2.jpg", "/b/2.jpg", "/a/3.jpg"]);
+
+        let error = sorted_paths(images, image_categories, vec![sort_key("colour", "ascending")], None).err().unwrap();
+        assert_eq!(error.field.as_deref(), Some("sort_keys"));
+    }
+
+    #[test]
+    fn test_sort_random_is_reproducible_per_seed() {
+        let paths: Vec<String> = (0..20).map(|i| format!("/test/{}.jpg", i)).collect();
+        let images = images_named(&paths.iter().map(|p| p.as_str()).collect::<Vec<_>>());
+        let shuffled = |images: Vec<ImagePath>, seed| {
+            sorted_paths(images, Vec::new(), vec![sort_key("random", "ascending")], Some(seed)).unwrap()
+        };
+
+        let first = shuffled(images.clone(), 42);
+        let mut reversed = images.clone();
+        reversed.reverse();
+        assert_eq!(first, shuffled(reversed, 42));
+        assert_ne!(first, shuffled(images.clone(), 43));
+        assert_ne!(first, paths);
+
+        let mut sorted = first;
+        sorted.sort();
+        let mut expected = paths;
+        expected.sort();
+        assert_eq!(sorted, expected);
+
+        // Without a seed every re-sort would reshuffle, so one is required
+        let error = sorted_paths(images, Vec::new(), vec![sort_key("random", "ascending")], None).unwrap_err();
+        assert_eq!(error.field.as_deref(), Some("random_seed"));
+    }
+
+    #[test]
+    fn test_sort_images_request_payload() {
+        let request: SortRequest = serde_json::from_value(serde_json::json!({
+            "images": [{ "path": "/test/b.jpg" }, { "path": "/test/a.jpg" }],
+            "image_categories": [["/test/b.jpg", [{ "category_id": "x", "assigned_at": "2024-01-01T00:00:00Z" }]]],
+            "sort_option": "name",
+            "filter_options": { "category_id": "x" },
+        }))
+        .unwrap();
+
+        let result = block_on(sort_images(request, None)).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "/test/b.jpg");
+    }
+
+    #[test]
+    fn test_sort_images_command_sorts_and_filters_notes() {
+        let request = || -> SortRequest {
+            serde_json::from_value(serde_json::json!({
+                "images": [{ "path": "/test/img1.jpg" }, { "path": "/test/img10.jpg" }, { "path": "/test/img2.jpg" }],
+                "image_notes": [
+                    ["/test/img2.jpg", [{ "text": "Scratch near edge", "created_at": "2024-01-01T00:00:00Z" }]],
+                    ["/test/img10.jpg", [{ "text": "scratched lens", "created_at": "2024-01-01T00:00:00Z" }]],
+                ],
+                "sort_option": "name",
+                "sort_direction": "descending",
+                "filter_options": { "note_pattern": "SCRATCH" },
+            }))
+            .unwrap()
+        };
+
+        let result = block_on(sort_images(request(), None)).unwrap();
+        let paths: Vec<&str> = result.iter().map(|image| image.path.as_str()).collect();
+        assert_eq!(paths, vec!["/test/img10.jpg", "/test/img2.jpg"]);
+
+        // Cancelled before it runs, e.g. when the folder was left right away
+        assert!(!requests::cancel_request("test-cancelled-sort".to_string()));
+        let error = block_on(sort_images(request(), Some("test-cancelled-sort".to_string()))).err().unwrap();
+        assert_eq!(error.code, ErrorCode::Cancelled);
+    }
+
+    #[test]
+    fn test_cancelled_request_stops_scan_and_load() {
+        let temp_dir = granted_temp_dir();
+        let image = temp_dir.path().join("a.png");
+        fs::write(&image, b"png").unwrap();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        let image = image.to_str().unwrap().to_string();
+
+        let request = Request::start(Some("test-cancelled-scan".to_string()));
+        assert!(read_directory(directory.clone(), None, request.token()).is_ok());
+        assert!(requests::cancel_request("test-cancelled-scan".to_string()));
+
+        let error = read_directory(directory.clone(), None, request.token()).err().unwrap();
+        assert_eq!(error.code, ErrorCode::Cancelled);
+        let error = read_image(image.clone(), request.token()).err().unwrap();
+        assert_eq!(error.code, ErrorCode::Cancelled);
+        drop(request);
+
+        // A new request under the same ID starts out uncancelled
+        assert_eq!(block_on(list_directory(directory, None, Some("test-cancelled-scan".to_string()), None)).unwrap().images.len(), 1);
+        assert!(block_on(load_image(image, Some("test-cancelled-scan".to_string()))).is_ok());
+    }
+
+    #[test]
+    fn test_file_commands_stay_in_scope() {
+        let granted = granted_temp_dir();
+        let outside = TempDir::new().unwrap();
+        let image = granted.path().join("a.png");
+        fs::write(&image, b"png").unwrap();
+        fs::write(outside.path().join("b.png"), b"png").unwrap();
+        let directory = granted.path().to_str().unwrap().to_string();
+        let image = image.to_str().unwrap().to_string();
+        let outside_dir = outside.path().to_str().unwrap().to_string();
+        let outside_image = outside.path().join("b.png").to_str().unwrap().to_string();
+
+        let denied = |result: Result<(), HitoError>| assert_eq!(result.unwrap_err().code, ErrorCode::PermissionDenied);
+        denied(block_on(list_directory(outside_dir.clone(), None, None, None)).map(|_| ()));
+        denied(block_on(load_image(outside_image.clone(), None)).map(|_| ()));
+        denied(block_on(copy_image(image.clone(), outside_dir.clone())));
+        denied(block_on(delete_image(outside_image, None, None)));
+        denied(load_hito_config(outside_dir, None, None).map(|_| ()));
+        // The traversal is caught on the directory as well as on the file name
+        denied(load_hito_config(format!("{}/..", directory), None, None).map(|_| ()));
+
+        let error = save_hito_config(directory.clone(), Vec::new(), Some("../escape.json".to_string()), None, None, None)
+            .unwrap_err();
+        assert_eq!(error.field.as_deref(), Some("filename"));
+        assert!(!granted.path().parent().unwrap().join("escape.json").exists());
+        assert!(save_hito_config(directory, Vec::new(), Some("labels.json".to_string()), None, None, None).is_ok());
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,523 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, State};
+
+use crate::error::{ErrorCode, HitoError};
+use crate::requests::{run_blocking, CancelToken, Request};
+use crate::{dates, get_app_data_path, get_hito_file_path, read_directory, read_hito_file, ImagePath};
+
+const INDEX_FILE_NAME: &str = "hito-index.sqlite3";
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS files (
+        path TEXT PRIMARY KEY,
+        directory TEXT NOT NULL,
+        size INTEGER,
+        created_at TEXT,
+        modified_at TEXT,
+        captured_at TEXT,
+        width INTEGER,
+        height INTEGER,
+        sha256 TEXT,
+        indexed_at TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS files_directory ON files (directory);
+    CREATE TABLE IF NOT EXISTS labels (
+        image_path TEXT NOT NULL,
+        directory TEXT NOT NULL,
+        data_file TEXT NOT NULL,
+        category_id TEXT NOT NULL,
+        category_name TEXT,
+        annotator TEXT,
+        assigned_at TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS labels_category ON labels (category_id);
+    CREATE INDEX IF NOT EXISTS labels_category_name ON labels (category_name);
+    CREATE INDEX IF NOT EXISTS labels_data_file ON labels (data_file);
+";
+
+#[derive(Serialize)]
+pub struct IndexedImage {
+    path: String,
+    size: Option<u64>,
+    created_at: Option<String>,
+    modified_at: Option<String>,
+    captured_at: Option<String>, // EXIF capture time
+    width: Option<u32>,
+    height: Option<u32>,
+    sha256: Option<String>, // Hex digest of the file contents
+}
+
+#[derive(Serialize)]
+pub struct IndexedLabel {
+    image_path: String,
+    directory: String,
+    data_file: String,
+    category_id: String,
+    category_name: Option<String>,
+    annotator: Option<String>,
+    assigned_at: String,
+}
+
+#[derive(Serialize)]
+pub struct IndexReport {
+    indexed: usize, // New or changed files whose metadata was (re)computed
+    unchanged: usize,
+    removed: usize,
+    labels: usize,
+}
+
+/// Local SQLite cache of per-file metadata and labels, shared by all directories.
+///
+/// File rows are keyed by path and reused while the file's size and modification time are
+/// unchanged, so hashes, dimensions and EXIF are only computed for new or changed files.
+pub(crate) struct MetadataIndex {
+    connection: Connection,
+}
+
+impl MetadataIndex {
+    pub(crate) fn open(path: &Path) -> Result<Self, HitoError> {
+        let connection = Connection::open(path)
+            .map_err(|e| HitoError::new(ErrorCode::Database, format!("Failed to open metadata index: {}", e)).with_path(path))?;
+        connection
+            .execute_batch(SCHEMA)
+            .map_err(|e| HitoError::new(ErrorCode::Database, format!("Failed to initialize metadata index: {}", e)))?;
+        Ok(MetadataIndex { connection })
+    }
+
+    /// Bring the index up to date with `directory` and the labels of its data file.
+    ///
+    /// Runs in one transaction, so a cancelled or failed run leaves the index as it was.
+    pub(crate) fn index_directory(
+        &mut self,
+        directory: &str,
+        filename: Option<&str>,
+        cancel: &CancelToken,
+    ) -> Result<IndexReport, HitoError> {
+        let data_file = get_hito_file_path(directory, filename)?;
+        let data_file_str = data_file
+            .to_str()
+            .ok_or_else(|| HitoError::invalid_input("Failed to convert path to string").with_path(&data_file))?
+            .to_string();
+        let images = read_directory(directory.to_string(), Some(data_file_str.clone()), cancel)?.images;
+        let data = read_hito_file(&data_file)?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        let db_err = |e: rusqlite::Error| HitoError::new(ErrorCode::Database, format!("Failed to update metadata index: {}", e));
+        let transaction = self.connection.transaction().map_err(db_err)?;
+
+        let mut known: HashMap<String, (Option<u64>, Option<String>)> = HashMap::new();
+        {
+            let mut statement = transaction
+                .prepare("SELECT path, size, modified_at FROM files WHERE directory = ?1")
+                .map_err(db_err)?;
+            let rows = statement
+                .query_map(params![directory], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))
+                .map_err(db_err)?;
+            for row in rows {
+                let (path, key) = row.map_err(db_err)?;
+                known.insert(path, key);
+            }
+        }
+
+        let mut report = IndexReport { indexed: 0, unchanged: 0, removed: 0, labels: 0 };
+        let mut seen = HashSet::new();
+        for image in &images {
+            cancel.check()?;
+            seen.insert(image.path.as_str());
+            if known.get(&image.path) == Some(&(image.size, image.modified_at.clone())) {
+                report.unchanged += 1;
+                continue;
+            }
+
+            let (width, height) = imagesize::size(&image.path)
+                .map(|size| (Some(size.width as u32), Some(size.height as u32)))
+                .unwrap_or_default();
+            transaction
+                .execute(
+                    "INSERT OR REPLACE INTO files
+                         (path, directory, size, created_at, modified_at, captured_at, width, height, sha256, indexed_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    params![
+                        image.path,
+                        directory,
+                        image.size,
+                        image.created_at,
+                        image.modified_at,
+                        dates::capture_time(&image.path),
+                        width,
+                        height,
+                        hash_file(&image.path).ok(),
+                        now,
+                    ],
+                )
+                .map_err(db_err)?;
+            report.indexed += 1;
+        }
+
+        for path in known.keys().filter(|path| !seen.contains(path.as_str())) {
+            transaction
+                .execute("DELETE FROM files WHERE path = ?1", params![path])
+                .map_err(db_err)?;
+            report.removed += 1;
+        }
+
+        let category_names: HashMap<&str, &str> = data
+            .categories
+            .iter()
+            .flatten()
+            .map(|c| (c.id.as_str(), c.name.as_str()))
+            .collect();
+        transaction
+            .execute("DELETE FROM labels WHERE data_file = ?1", params![data_file_str])
+            .map_err(db_err)?;
+        for (image_path, assignments) in &data.image_categories {
+            for assignment in assignments {
+                transaction
+                    .execute(
+                        "INSERT INTO labels
+                             (image_path, directory, data_file, category_id, category_name, annotator, assigned_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                        params![
+                            image_path,
+                            directory,
+                            data_file_str,
+                            assignment.category_id,
+                            category_names.get(assignment.category_id.as_str()),
+                            assignment.annotator,
+                            assignment.assigned_at,
+                        ],
+                    )
+                    .map_err(db_err)?;
+                report.labels += 1;
+            }
+        }
+
+        transaction.commit().map_err(db_err)?;
+        Ok(report)
+    }
+
+    /// Cached metadata of the images of `directory`, without touching the file system.
+    pub(crate) fn images(&self, directory: &str) -> Result<Vec<IndexedImage>, HitoError> {
+        let db_err = |e: rusqlite::Error| HitoError::new(ErrorCode::Database, format!("Failed to read metadata index: {}", e));
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT path, size, created_at, modified_at, captured_at, width, height, sha256
+                 FROM files WHERE directory = ?1 ORDER BY path",
+            )
+            .map_err(db_err)?;
+        let rows = statement
+            .query_map(params![directory], |row| {
+                Ok(IndexedImage {
+                    path: row.get(0)?,
+                    size: row.get(1)?,
+                    created_at: row.get(2)?,
+                    modified_at: row.get(3)?,
+                    captured_at: row.get(4)?,
+                    width: row.get(5)?,
+                    height: row.get(6)?,
+                    sha256: row.get(7)?,
+                })
+            })
+            .map_err(db_err)?;
+        rows.collect::<Result<_, _>>().map_err(db_err)
+    }
+
+    /// Labels in every indexed directory whose category ID or name is `category`.
+    pub(crate) fn labels(&self, category: &str, annotator: Option<&str>) -> Result<Vec<IndexedLabel>, HitoError> {
+        let db_err = |e: rusqlite::Error| HitoError::new(ErrorCode::Database, format!("Failed to read metadata index: {}", e));
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT image_path, directory, data_file, category_id, category_name, annotator, assigned_at
+                 FROM labels
+                 WHERE (category_id = ?1 OR category_name = ?1) AND (?2 IS NULL OR annotator = ?2)
+                 ORDER BY directory, image_path, assigned_at",
+            )
+            .map_err(db_err)?;
+        let rows = statement
+            .query_map(params![category, annotator], |row| {
+                Ok(IndexedLabel {
+                    image_path: row.get(0)?,
+                    directory: row.get(1)?,
+                    data_file: row.get(2)?,
+                    category_id: row.get(3)?,
+                    category_name: row.get(4)?,
+                    annotator: row.get(5)?,
+                    assigned_at: row.get(6)?,
+                })
+            })
+            .map_err(db_err)?;
+        rows.collect::<Result<_, _>>().map_err(db_err)
+    }
+
+}
+
+fn hash_file(path: &str) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// The metadata index in the app data directory, managed as Tauri state.
+///
+/// The connection is opened on first use and kept for the app's lifetime; commands take turns on it.
+#[derive(Clone, Default)]
+pub struct AppIndex(Arc<Mutex<Option<MetadataIndex>>>);
+
+impl AppIndex {
+    /// Run `work` on the worker pool with the index, opening it first if needed.
+    async fn run<T, F>(&self, app: AppHandle, work: F) -> Result<T, HitoError>
+    where
+        F: FnOnce(&mut MetadataIndex) -> Result<T, HitoError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let index = self.0.clone();
+        run_blocking(move || {
+            let mut index = index.lock()?;
+            if index.is_none() {
+                *index = Some(MetadataIndex::open(&get_app_data_path(&app)?.with_file_name(INDEX_FILE_NAME))?);
+            }
+            let index = index.as_mut().ok_or_else(|| HitoError::internal("Metadata index is not open"))?;
+            work(index)
+        })
+        .await
+    }
+
+    /// Images of `directory` as of its last indexing, keyed by path, for `list_images` to reuse
+    /// while they are unchanged.
+    ///
+    /// Empty if the index was never created, is busy indexing, or does not know the directory;
+    /// the listing then reads the metadata from the file system as usual.
+    pub(crate) fn cached_images(&self, app: &AppHandle, directory: &str) -> HashMap<String, ImagePath> {
+        let Ok(mut index) = self.0.try_lock() else {
+            return HashMap::new();
+        };
+        if index.is_none() {
+            let Ok(path) = get_app_data_path(app).map(|path| path.with_file_name(INDEX_FILE_NAME)) else {
+                return HashMap::new();
+            };
+            // Listing alone should not create the index
+            if !path.exists() {
+                return HashMap::new();
+            }
+            match MetadataIndex::open(&path) {
+                Ok(opened) => *index = Some(opened),
+                Err(e) => {
+                    tracing::warn!(error = ?e, "Failed to open metadata index, listing without it");
+                    return HashMap::new();
+                }
+            }
+        }
+        match index.as_ref().map(|index| index.images(directory)) {
+            Some(Ok(images)) => images
+                .into_iter()
+                .map(|image| (image.path.clone(), ImagePath::from(image)))
+                .collect(),
+            Some(Err(e)) => {
+                tracing::warn!(directory = %directory, error = ?e, "Failed to read metadata index, listing without it");
+                HashMap::new()
+            }
+            None => HashMap::new(),
+        }
+    }
+}
+
+impl From<IndexedImage> for ImagePath {
+    fn from(image: IndexedImage) -> Self {
+        ImagePath {
+            path: image.path,
+            size: image.size,
+            created_at: image.created_at,
+            modified_at: image.modified_at,
+            captured_at: image.captured_at,
+        }
+    }
+}
+
+/// Add or refresh `directory` in the metadata index in the app data directory.
+///
+/// Only files that are new or whose size or modification time changed are re-read; files that
+/// disappeared are dropped. The labels of the directory's data file (`filename`, default
+/// `.hito.json`) are replaced with its current contents.
+///
+/// Runs on the worker pool; a `request_id` makes it cancellable, as in `list_images`.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref()), err(Debug))]
+pub async fn index_directory(
+    app: AppHandle,
+    index: State<'_, AppIndex>,
+    directory: String,
+    filename: Option<String>,
+    request_id: Option<String>,
+) -> Result<IndexReport, HitoError> {
+    let cancel = Request::start(request_id);
+    index
+        .run(app, move |index| index.index_directory(&directory, filename.as_deref(), cancel.token()))
+        .await
+}
+
+/// Get the images of `directory` as of its last indexing, without scanning the directory.
+///
+/// Useful to show a large or remote folder immediately while `index_directory` refreshes it.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory), err(Debug))]
+pub async fn get_indexed_images(
+    app: AppHandle,
+    index: State<'_, AppIndex>,
+    directory: String,
+) -> Result<Vec<IndexedImage>, HitoError> {
+    index.run(app, move |index| index.images(&directory)).await
+}
+
+/// Find labels across every indexed directory by category ID or name, optionally for one annotator.
+#[tauri::command]
+#[tracing::instrument(skip_all, err(Debug))]
+pub async fn search_index_labels(
+    app: AppHandle,
+    index: State<'_, AppIndex>,
+    category: String,
+    annotator: Option<String>,
+) -> Result<Vec<IndexedLabel>, HitoError> {
+    index.run(app, move |index| index.labels(&category, annotator.as_deref())).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::requests::cancel_request;
+    use crate::{save_hito_config, CategoryAssignment, CategoryData};
+    use crate::scope::granted_temp_dir;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn index_path(temp_dir: &TempDir) -> std::path::PathBuf {
+        temp_dir.path().join(INDEX_FILE_NAME)
+    }
+
+    #[test]
+    fn test_index_is_incremental() {
+        let temp_dir = granted_temp_dir();
+        let photos = temp_dir.path().join("photos");
+        fs::create_dir(&photos).unwrap();
+        fs::write(photos.join("a.jpg"), b"first").unwrap();
+        fs::write(photos.join("b.jpg"), b"second").unwrap();
+        let directory = photos.to_str().unwrap();
+
+        let mut index = MetadataIndex::open(&index_path(&temp_dir)).unwrap();
+        let report = index.index_directory(directory, None, &CancelToken::default()).unwrap();
+        assert_eq!((report.indexed, report.unchanged, report.removed), (2, 0, 0));
+
+        let report = index.index_directory(directory, None, &CancelToken::default()).unwrap();
+        assert_eq!((report.indexed, report.unchanged, report.removed), (0, 2, 0));
+
+        fs::write(photos.join("b.jpg"), b"changed contents").unwrap();
+        fs::remove_file(photos.join("a.jpg")).unwrap();
+        let report = index.index_directory(directory, None, &CancelToken::default()).unwrap();
+        assert_eq!((report.indexed, report.unchanged, report.removed), (1, 0, 1));
+
+        let images = index.images(directory).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].size, Some(16));
+        assert_eq!(images[0].sha256.as_ref().map(|h| h.len()), Some(64));
+        assert_eq!(images[0].captured_at, None);
+    }
+
+    #[test]
+    fn test_listing_reuses_indexed_metadata() {
+        let temp_dir = granted_temp_dir();
+        let photos = temp_dir.path().join("photos");
+        fs::create_dir(&photos).unwrap();
+        fs::write(photos.join("a.jpg"), b"first").unwrap();
+        let directory = photos.to_str().unwrap();
+
+        let mut index = MetadataIndex::open(&index_path(&temp_dir)).unwrap();
+        index.index_directory(directory, None, &CancelToken::default()).unwrap();
+        let mut indexed: HashMap<String, ImagePath> = index
+            .images(directory)
+            .unwrap()
+            .into_iter()
+            .map(|image| (image.path.clone(), ImagePath::from(image)))
+            .collect();
+
+        let capture_times = |indexed: &HashMap<String, ImagePath>| {
+            let contents = crate::scan_directory(directory.to_string(), None, indexed, &CancelToken::default()).unwrap();
+            contents.images.into_iter().map(|image| (image.size, image.captured_at)).collect::<Vec<_>>()
+        };
+        let a = photos.join("a.jpg").to_str().unwrap().to_string();
+        indexed.get_mut(&a).unwrap().captured_at = Some("2024-01-01T00:00:00+00:00".to_string());
+
+        // Unchanged entries keep the indexed capture time, new files are read from disk
+        fs::write(photos.join("b.jpg"), b"second").unwrap();
+        assert_eq!(
+            capture_times(&indexed),
+            vec![(Some(5), Some("2024-01-01T00:00:00+00:00".to_string())), (Some(6), None)]
+        );
+
+        // A rewritten file is listed as it is now, not as indexed
+        fs::write(photos.join("a.jpg"), b"changed contents").unwrap();
+        assert_eq!(capture_times(&indexed), vec![(Some(16), None), (Some(6), None)]);
+    }
+
+    #[test]
+    fn test_cancelled_indexing_leaves_index_unchanged() {
+        let temp_dir = granted_temp_dir();
+        let photos = temp_dir.path().join("photos");
+        fs::create_dir(&photos).unwrap();
+        fs::write(photos.join("a.jpg"), b"first").unwrap();
+        let directory = photos.to_str().unwrap();
+
+        let mut index = MetadataIndex::open(&index_path(&temp_dir)).unwrap();
+        let request = Request::start(Some("index-test".to_string()));
+        assert!(cancel_request("index-test".to_string()));
+        let error = index.index_directory(directory, None, request.token()).err().unwrap();
+        assert_eq!(error.code, ErrorCode::Cancelled);
+        assert!(index.images(directory).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_labels_across_directories() {
+        let temp_dir = granted_temp_dir();
+        let mut index = MetadataIndex::open(&index_path(&temp_dir)).unwrap();
+
+        for (folder, category_id) in [("one", "c1"), ("two", "c2")] {
+            let dir = temp_dir.path().join(folder);
+            fs::create_dir(&dir).unwrap();
+            fs::write(dir.join("x.jpg"), b"image").unwrap();
+            let directory = dir.to_str().unwrap().to_string();
+            let assignment = CategoryAssignment {
+                category_id: category_id.to_string(),
+                assigned_at: "2024-01-01T00:00:00Z".to_string(),
+                ..Default::default()
+            };
+            let category = CategoryData {
+                id: category_id.to_string(),
+                name: "Cat".to_string(),
+                color: "#000000".to_string(),
+                mutually_exclusive_with: None,
+            };
+            let image = dir.join("x.jpg").to_str().unwrap().to_string();
+            save_hito_config(directory.clone(), vec![(image, vec![assignment])], None, Some(vec![category]), None, None)
+                .unwrap();
+            assert_eq!(index.index_directory(&directory, None, &CancelToken::default()).unwrap().labels, 1);
+        }
+
+        // Category IDs differ per folder, but the name is shared
+        assert_eq!(index.labels("Cat", None).unwrap().len(), 2);
+        assert_eq!(index.labels("c2", None).unwrap().len(), 1);
+        assert!(index.labels("Cat", Some("alice")).unwrap().is_empty());
+
+        // Re-indexing replaces a data file's labels instead of duplicating them
+        let directory = temp_dir.path().join("one");
+        index.index_directory(directory.to_str().unwrap(), None, &CancelToken::default()).unwrap();
+        assert_eq!(index.labels("Cat", None).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,128 @@
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+
+/// Compiled `name_pattern` of `FilterOptions`.
+pub(crate) enum NameMatcher {
+    Contains(String),
+    StartsWith(String),
+    EndsWith(String),
+    Exact(String),
+    Regex(Regex),
+    Glob(GlobMatcher),
+}
+
+/// Text the name filter is applied to.
+pub(crate) struct NameTarget {
+    pub(crate) full_path: bool, // Match the path relative to `base_directory` instead of the file name
+    pub(crate) base_directory: Option<String>,
+    pub(crate) case_sensitive: bool,
+}
+
+impl NameMatcher {
+    /// Compile `pattern` for `operator` ("contains", "startsWith", "endsWith", "exact", "regex" or "glob").
+    ///
+    /// Unknown operators fall back to "contains", as they always have.
+    pub(crate) fn compile(pattern: &str, operator: &str, case_sensitive: bool) -> Result<Self, String> {
+        let text = if case_sensitive { pattern.to_string() } else { pattern.to_lowercase() };
+
+        Ok(match operator {
+            "startsWith" => NameMatcher::StartsWith(text),
+            "endsWith" => NameMatcher::EndsWith(text),
+            "exact" => NameMatcher::Exact(text),
+            "regex" => NameMatcher::Regex(
+                RegexBuilder::new(pattern)
+                    .case_insensitive(!case_sensitive)
+                    .build()
+                    .map_err(|e| format!("Invalid regular expression: {}", e))?,
+            ),
+            "glob" => NameMatcher::Glob(
+                GlobBuilder::new(pattern)
+                    .case_insensitive(!case_sensitive)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| format!("Invalid glob pattern: {}", e))?
+                    .compile_matcher(),
+            ),
+            _ => NameMatcher::Contains(text),
+        })
+    }
+
+    /// Whether the image at `path` matches, looking at the part of the path chosen by `target`.
+    pub(crate) fn is_match(&self, path: &str, target: &NameTarget) -> bool {
+        let subject = target_text(path, target);
+        let folded = || if target.case_sensitive { subject.clone() } else { subject.to_lowercase() };
+
+        match self {
+            NameMatcher::Regex(regex) => regex.is_match(&subject),
+            NameMatcher::Glob(glob) => glob.is_match(&subject),
+            NameMatcher::StartsWith(text) => folded().starts_with(text.as_str()),
+            NameMatcher::EndsWith(text) => folded().ends_with(text.as_str()),
+            NameMatcher::Exact(text) => folded() == *text,
+            NameMatcher::Contains(text) => folded().contains(text.as_str()),
+        }
+    }
+}
+
+/// File name, or the path relative to the base directory with `/` separators.
+fn target_text(path: &str, target: &NameTarget) -> String {
+    if !target.full_path {
+        return Path::new(path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+            .to_string();
+    }
+
+    let relative = target
+        .base_directory
+        .as_deref()
+        .and_then(|base| Path::new(path).strip_prefix(base).ok())
+        .unwrap_or(Path::new(path));
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(full_path: bool, case_sensitive: bool) -> NameTarget {
+        NameTarget {
+            full_path,
+            base_directory: Some("/photos".to_string()),
+            case_sensitive,
+        }
+    }
+
+    #[test]
+    fn test_regex_and_glob_operators() {
+        let regex = NameMatcher::compile(r"^img_\d+\.jpg$", "regex", false).unwrap();
+        assert!(regex.is_match("/photos/IMG_001.jpg", &target(false, false)));
+        assert!(!regex.is_match("/photos/IMG_A.jpg", &target(false, false)));
+
+        let glob = NameMatcher::compile("*.PNG", "glob", false).unwrap();
+        assert!(glob.is_match("/photos/a.png", &target(false, false)));
+        assert!(!glob.is_match("/photos/a.jpg", &target(false, false)));
+    }
+
+    #[test]
+    fn test_full_path_and_case_sensitivity() {
+        let glob = NameMatcher::compile("2024/*.jpg", "glob", true).unwrap();
+        assert!(glob.is_match("/photos/2024/a.jpg", &target(true, true)));
+        assert!(!glob.is_match("/photos/2024/nested/a.jpg", &target(true, true)));
+        assert!(!glob.is_match("/photos/2024/a.jpg", &target(false, true)));
+
+        let contains = NameMatcher::compile("IMG", "contains", true).unwrap();
+        assert!(contains.is_match("/photos/IMG_1.jpg", &target(false, true)));
+        assert!(!contains.is_match("/photos/img_1.jpg", &target(false, true)));
+        assert!(contains.is_match("/photos/IMG_dir/x.jpg", &target(true, true)));
+    }
+
+    #[test]
+    fn test_invalid_patterns_are_errors() {
+        assert!(NameMatcher::compile("(", "regex", false).err().unwrap().starts_with("Invalid regular expression"));
+        assert!(NameMatcher::compile("[a", "glob", false).err().unwrap().starts_with("Invalid glob pattern"));
+        assert!(NameMatcher::compile("(", "contains", false).is_ok());
+    }
+}
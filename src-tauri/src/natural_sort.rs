@@ -0,0 +1,64 @@
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Compare two names the way people read them: runs of digits compare by numeric value, so
+/// `img2` sorts before `img10`. Letters compare case-insensitively.
+///
+/// Names that only differ in case or leading zeros fall back to a plain comparison so the order
+/// stays total.
+pub(crate) fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut left, mut right) = (a.chars().peekable(), b.chars().peekable());
+
+    loop {
+        let ordering = match (left.peek().copied(), right.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                compare_numbers(&take_digits(&mut left), &take_digits(&mut right))
+            }
+            (Some(x), Some(y)) => {
+                left.next();
+                right.next();
+                x.to_lowercase().cmp(y.to_lowercase())
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+fn take_digits(chars: &mut Peekable<Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        digits.push(c);
+    }
+    digits
+}
+
+/// Compare digit runs by value without parsing, so arbitrarily long numbers work.
+fn compare_numbers(a: &str, b: &str) -> Ordering {
+    let (a, b) = (a.trim_start_matches('0'), b.trim_start_matches('0'));
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numbers_compare_by_value() {
+        let mut names = vec!["img10.jpg", "img2.jpg", "IMG1.jpg", "img02.jpg", "img.jpg", "a100"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, vec!["a100", "img.jpg", "IMG1.jpg", "img02.jpg", "img2.jpg", "img10.jpg"]);
+    }
+
+    #[test]
+    fn test_long_numbers_and_ties() {
+        assert_eq!(natural_cmp("x99999999999999999999", "x100000000000000000000"), Ordering::Less);
+        assert_eq!(natural_cmp("a.jpg", "a.jpg"), Ordering::Equal);
+        assert_ne!(natural_cmp("A.jpg", "a.jpg"), Ordering::Equal);
+    }
+}
@@ -0,0 +1,342 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::HitoError;
+use crate::{
+    events, get_data_file_lock, get_hito_file_path, load_app_data, read_hito_file, scope, update_app_data_sync,
+    write_hito_file, AppData, CategoryData, HotkeyData,
+};
+
+/// A named, reusable set of categories and hotkeys (e.g. a team's standard taxonomy).
+///
+/// Exported preset files contain this structure as JSON.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Preset {
+    name: String,
+    categories: Vec<CategoryData>,
+    hotkeys: Vec<HotkeyData>,
+}
+
+impl Preset {
+    fn new(name: &str, categories: Vec<CategoryData>, hotkeys: Vec<HotkeyData>) -> Result<Self, HitoError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(HitoError::invalid_field("name", "Preset name must not be empty"));
+        }
+        Ok(Preset { name: name.to_string(), categories, hotkeys })
+    }
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+/// Add `preset` to app data, replacing a preset with the same name (case-insensitive).
+fn upsert_preset(app_data: &mut AppData, preset: Preset) {
+    let presets = app_data.presets.get_or_insert_with(Vec::new);
+    match presets.iter_mut().find(|p| same_name(&p.name, &preset.name)) {
+        Some(existing) => *existing = preset,
+        None => presets.push(preset),
+    }
+}
+
+fn find_preset(app_data: &AppData, name: &str) -> Result<Preset, HitoError> {
+    app_data
+        .presets
+        .iter()
+        .flatten()
+        .find(|p| same_name(&p.name, name.trim()))
+        .cloned()
+        .ok_or_else(|| HitoError::not_found(format!("Preset does not exist: {}", name)))
+}
+
+/// Merge preset entries into existing ones.
+///
+/// An entry with the same ID is replaced in place, existing entries that `clashes` with a preset
+/// entry are dropped, and the remaining preset entries are appended.
+fn merge_by_id<T: Clone>(
+    existing: Vec<T>,
+    preset: &[T],
+    id: impl Fn(&T) -> &str,
+    clashes: impl Fn(&T, &T) -> bool,
+) -> Vec<T> {
+    let mut merged: Vec<T> = existing
+        .into_iter()
+        .filter_map(|item| match preset.iter().find(|p| id(p) == id(&item)) {
+            Some(replacement) => Some(replacement.clone()),
+            None if preset.iter().any(|p| clashes(p, &item)) => None,
+            None => Some(item),
+        })
+        .collect();
+    for item in preset {
+        if !merged.iter().any(|m| id(m) == id(item)) {
+            merged.push(item.clone());
+        }
+    }
+    merged
+}
+
+/// Whether two hotkeys are bound to the same key combination.
+fn same_binding(a: &HotkeyData, b: &HotkeyData) -> bool {
+    let modifiers = |hotkey: &HotkeyData| {
+        let mut modifiers: Vec<String> = hotkey.modifiers.iter().map(|m| m.to_lowercase()).collect();
+        modifiers.sort();
+        modifiers
+    };
+    a.key.to_lowercase() == b.key.to_lowercase() && modifiers(a) == modifiers(b)
+}
+
+/// Merge `preset` into the categories and hotkeys of a data file, logging created categories.
+///
+/// A data file without categories (or hotkeys) shows the global ones from `app_data`, so the preset
+/// is merged into those, as the frontend would save them.
+fn apply_to_data_file(
+    preset: &Preset,
+    app_data: &AppData,
+    directory: &str,
+    filename: Option<&str>,
+    annotator: Option<&str>,
+) -> Result<(), HitoError> {
+    let hito_path = get_hito_file_path(directory, filename)?;
+    let lock = get_data_file_lock(&hito_path);
+    let _guard = lock.lock()?;
+
+    let mut data = read_hito_file(&hito_path)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let log_path = events::get_event_log_path(directory, filename)?;
+    let snapshot = events::initial_snapshot(&log_path, &data, &now);
+
+    let base_categories = data.categories.take().filter(|c| !c.is_empty()).unwrap_or_else(|| app_data.categories.clone());
+    let base_hotkeys = data.hotkeys.take().filter(|h| !h.is_empty()).unwrap_or_else(|| app_data.hotkeys.clone());
+    // Categories are never dropped, since labels may still refer to them
+    let categories = merge_by_id(base_categories.clone(), &preset.categories, |c| &c.id, |_, _| false);
+    let label_events: Vec<events::LabelEvent> = snapshot
+        .into_iter()
+        .chain(events::diff_categories(Some(&base_categories), Some(&categories), annotator, &now))
+        .collect();
+    data.categories = Some(categories);
+    data.hotkeys = Some(merge_by_id(base_hotkeys, &preset.hotkeys, |h| &h.id, same_binding));
+
+    write_hito_file(&hito_path, &data)?;
+    // The preset is already applied; a missing log entry must not report it as failed
+    if let Err(e) = events::append_events(&log_path, &label_events) {
+        tracing::warn!(error = ?e, "Applied preset but failed to record events");
+    }
+    Ok(())
+}
+
+fn read_preset_file(path: &Path) -> Result<Preset, HitoError> {
+    let content = fs::read_to_string(path).map_err(|e| HitoError::io("Failed to read preset file", path, e))?;
+    let preset: Preset = serde_json::from_str(&content)
+        .map_err(|e| HitoError::invalid_data(format!("Failed to parse preset file: {}", e)).with_path(path))?;
+    Preset::new(&preset.name, preset.categories, preset.hotkeys).map_err(|e| e.with_path(path))
+}
+
+fn write_preset_file(path: &Path, preset: &Preset) -> Result<(), HitoError> {
+    let json_content = serde_json::to_string_pretty(preset)
+        .map_err(|e| HitoError::internal(format!("Failed to serialize preset: {}", e)))?;
+    fs::write(path, json_content).map_err(|e| HitoError::io("Failed to write preset file", path, e))
+}
+
+/// Save categories and hotkeys as a named preset in app data, replacing a preset with the same name.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(name = %name), err(Debug))]
+pub fn save_preset(
+    app: AppHandle,
+    name: String,
+    categories: Vec<CategoryData>,
+    hotkeys: Vec<HotkeyData>,
+) -> Result<(), HitoError> {
+    let preset = Preset::new(&name, categories, hotkeys)?;
+    update_app_data_sync(&app, |mut app_data| {
+        upsert_preset(&mut app_data, preset);
+        Ok(app_data)
+    })
+}
+
+/// List all presets, sorted by name.
+#[tauri::command]
+#[tracing::instrument(skip_all, err(Debug))]
+pub fn list_presets(app: AppHandle) -> Result<Vec<Preset>, HitoError> {
+    let mut presets = load_app_data(app)?.presets.unwrap_or_default();
+    presets.sort_by_key(|p| p.name.to_lowercase());
+    Ok(presets)
+}
+
+/// Apply a preset to the data file in `directory` (`filename`, default `.hito.json`).
+///
+/// Preset categories and hotkeys replace those with the same ID and are added otherwise. Existing
+/// categories are kept, as labels may refer to them; existing hotkeys bound to the same key
+/// combination as a preset hotkey are removed. Labels are not changed. A data file without
+/// categories or hotkeys starts from the global ones in app data.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(name = %name, directory = %directory, filename = filename.as_deref()), err(Debug))]
+pub fn apply_preset(
+    app: AppHandle,
+    name: String,
+    directory: String,
+    filename: Option<String>,
+    annotator: Option<String>,
+) -> Result<(), HitoError> {
+    let app_data = load_app_data(app)?;
+    let preset = find_preset(&app_data, &name)?;
+    apply_to_data_file(&preset, &app_data, &directory, filename.as_deref(), annotator.as_deref())
+}
+
+/// Write a preset to a standalone JSON file, e.g. to share it with the team.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(name = %name, output_path = %output_path), err(Debug))]
+pub fn export_preset(app: AppHandle, name: String, output_path: String) -> Result<(), HitoError> {
+    scope::check(&output_path)?;
+    let preset = find_preset(&load_app_data(app)?, &name)?;
+    write_preset_file(Path::new(&output_path), &preset)
+}
+
+/// Import a preset from a file written by `export_preset`, replacing a preset with the same name.
+///
+/// # Returns
+///
+/// The imported preset.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(path = %path), err(Debug))]
+pub fn import_preset(app: AppHandle, path: String) -> Result<Preset, HitoError> {
+    scope::check(&path)?;
+    let preset = read_preset_file(Path::new(&path))?;
+    let imported = preset.clone();
+    update_app_data_sync(&app, |mut app_data| {
+        upsert_preset(&mut app_data, preset);
+        Ok(app_data)
+    })?;
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::scope::granted_temp_dir;
+    use crate::{save_hito_config, CategoryAssignment};
+
+    fn category(id: &str, name: &str) -> CategoryData {
+        CategoryData {
+            id: id.to_string(),
+            name: name.to_string(),
+            color: "#000000".to_string(),
+            mutually_exclusive_with: None,
+        }
+    }
+
+    fn hotkey(id: &str, key: &str, modifiers: &[&str]) -> HotkeyData {
+        HotkeyData {
+            id: id.to_string(),
+            key: key.to_string(),
+            modifiers: modifiers.iter().map(|m| m.to_string()).collect(),
+            action: format!("toggle_category_{}", id),
+        }
+    }
+
+    fn ids<T>(items: &[T], id: impl Fn(&T) -> &str) -> Vec<&str> {
+        items.iter().map(id).collect()
+    }
+
+    #[test]
+    fn test_save_preset_replaces_same_name() {
+        let mut app_data = AppData::default();
+        upsert_preset(&mut app_data, Preset::new(" Animals ", vec![category("cat", "Cat")], vec![]).unwrap());
+        upsert_preset(&mut app_data, Preset::new("Vehicles", vec![], vec![]).unwrap());
+        upsert_preset(&mut app_data, Preset::new("animals", vec![category("dog", "Dog")], vec![]).unwrap());
+
+        let presets = app_data.presets.as_deref().unwrap();
+        assert_eq!(presets.len(), 2);
+        assert_eq!(presets[0].name, "animals");
+        assert_eq!(presets[0].categories[0].id, "dog");
+        assert_eq!(find_preset(&app_data, "ANIMALS").unwrap().name, "animals");
+        assert_eq!(Preset::new(" ", vec![], vec![]).err().unwrap().field.as_deref(), Some("name"));
+    }
+
+    #[test]
+    fn test_apply_preset_merges_into_data_file() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        let image_path = temp_dir.path().join("a.png").to_str().unwrap().to_string();
+        let assignment = CategoryAssignment {
+            category_id: "old".to_string(),
+            assigned_at: "2024-01-01T00:00:00Z".to_string(),
+            ..Default::default()
+        };
+        save_hito_config(
+            directory.clone(),
+            vec![(image_path, vec![assignment])],
+            None,
+            Some(vec![category("old", "Old"), category("cat", "Kitty")]),
+            Some(vec![hotkey("h1", "1", &[]), hotkey("h2", "K", &["Shift", "Control"])]),
+            None,
+        )
+        .unwrap();
+        let preset = Preset::new(
+            "Animals",
+            vec![category("cat", "Cat"), category("dog", "Dog")],
+            vec![hotkey("h3", "k", &["control", "shift"])],
+        )
+        .unwrap();
+
+        apply_to_data_file(&preset, &AppData::default(), &directory, None, Some("alice")).unwrap();
+
+        let data = read_hito_file(&temp_dir.path().join(".hito.json")).unwrap();
+        let categories = data.categories.unwrap();
+        assert_eq!(ids(&categories, |c| &c.id), vec!["old", "cat", "dog"]);
+        assert_eq!(categories[1].name, "Cat");
+        assert_eq!(ids(&data.hotkeys.unwrap(), |h| &h.id), vec!["h1", "h3"]);
+        assert_eq!(data.image_categories.len(), 1);
+
+        let log = events::read_events(&temp_dir.path().join(".hito.events.jsonl")).unwrap();
+        let created: Vec<_> = log.iter().filter(|e| e.kind == "category_create").filter_map(|e| e.category_id.as_deref()).collect();
+        assert!(created.contains(&"dog"));
+        assert!(!log.iter().any(|e| e.kind == "category_delete"));
+    }
+
+    #[test]
+    fn test_apply_preset_seeds_from_global_categories() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        let app_data = AppData {
+            categories: vec![category("global", "Global"), category("cat", "Kitty")],
+            hotkeys: vec![hotkey("h1", "1", &[])],
+            ..Default::default()
+        };
+        let preset = Preset::new("Animals", vec![category("cat", "Cat")], vec![hotkey("h2", "2", &[])]).unwrap();
+
+        apply_to_data_file(&preset, &app_data, &directory, None, None).unwrap();
+
+        let data = read_hito_file(&temp_dir.path().join(".hito.json")).unwrap();
+        let categories = data.categories.unwrap();
+        assert_eq!(ids(&categories, |c| &c.id), vec!["global", "cat"]);
+        assert_eq!(categories[1].name, "Cat");
+        assert_eq!(ids(&data.hotkeys.unwrap(), |h| &h.id), vec!["h1", "h2"]);
+
+        // The global categories were already in use, so they are not logged as created
+        let log = events::read_events(&temp_dir.path().join(".hito.events.jsonl")).unwrap();
+        assert!(!log.iter().any(|e| e.kind == "category_create"));
+    }
+
+    #[test]
+    fn test_preset_file_round_trip() {
+        let temp_dir = granted_temp_dir();
+        let path = temp_dir.path().join("animals.json");
+        let preset = Preset::new("Animals", vec![category("cat", "Cat")], vec![hotkey("h1", "1", &[])]).unwrap();
+
+        write_preset_file(&path, &preset).unwrap();
+        let imported = read_preset_file(&path).unwrap();
+        assert_eq!(imported.name, "Animals");
+        assert_eq!(imported.categories[0].id, "cat");
+        assert_eq!(imported.hotkeys[0].key, "1");
+
+        fs::write(&path, r#"{"categories": []}"#).unwrap();
+        assert_eq!(read_preset_file(&path).err().unwrap().code, ErrorCode::InvalidData);
+        fs::write(&path, r#"{"name": "", "categories": [], "hotkeys": []}"#).unwrap();
+        assert_eq!(read_preset_file(&path).err().unwrap().field.as_deref(), Some("name"));
+    }
+}
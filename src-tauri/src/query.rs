@@ -0,0 +1,607 @@
+//! Boolean query language for filtering images.
+//!
+//! ```text
+//! query   := or
+//! or      := and ("OR" and)*
+//! and     := not ("AND" not)*
+//! not     := "NOT" not | "(" query ")" | clause
+//! clause  := ("cat" | "category") value          category ID, or "uncategorized"
+//!          | "size" ("<" | "<=" | ">" | ">=" | "=" | "!=") size
+//!          | "name" ("~" | "=" | "^=" | "$=") value
+//! value   := word | "quoted string"
+//! size    := number ["B" | "KB" | "MB" | "GB"]        KB when no unit is given
+//! ```
+//!
+//! Keywords are case-insensitive. `name ~ /regex/` matches the file name against a regular
+//! expression (add `i` after the closing slash to ignore case); `name ~ "text"` is a
+//! case-insensitive substring match, `=`, `^=` and `$=` compare the whole name, its start or its end.
+//! Example: `(cat a OR cat b) AND NOT cat c AND size > 500KB AND name ~ /IMG_\d+/`
+//!
+//! Parentheses and `NOT`s can be nested up to 64 levels deep.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::error::HitoError;
+use crate::{CategoryAssignment, ImagePath};
+
+/// A query parse error with the character offset (0-based) where it was detected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    pub(crate) message: String,
+    pub(crate) position: usize,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    Op(String),
+    Word(String),
+    Quoted(String),
+    Regex { pattern: String, flags: String },
+}
+
+fn is_operator_char(c: char) -> bool {
+    matches!(c, '~' | '=' | '<' | '>' | '!')
+}
+
+/// Split a query into tokens, each with its character offset.
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        match c {
+            '(' => {
+                tokens.push((Token::LParen, start));
+                i += 1;
+            }
+            ')' => {
+                tokens.push((Token::RParen, start));
+                i += 1;
+            }
+            '"' | '\'' => {
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(QueryError {
+                                message: "Unterminated string".to_string(),
+                                position: start,
+                            })
+                        }
+                        Some('\\') if chars.get(i + 1).is_some() => {
+                            value.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(&ch) if ch == c => {
+                            i += 1;
+                            break;
+                        }
+                        Some(&ch) => {
+                            value.push(ch);
+                            i += 1;
+                        }
+                    }
+                }
+                tokens.push((Token::Quoted(value), start));
+            }
+            '/' => {
+                let mut pattern = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(QueryError {
+                                message: "Unterminated regular expression".to_string(),
+                                position: start,
+                            })
+                        }
+                        // Only an escaped slash loses its backslash; other escapes belong to the regex
+                        Some('\\') if chars.get(i + 1) == Some(&'/') => {
+                            pattern.push('/');
+                            i += 2;
+                        }
+                        Some('\\') if chars.get(i + 1).is_some() => {
+                            pattern.push('\\');
+                            pattern.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some('/') => {
+                            i += 1;
+                            break;
+                        }
+                        Some(&ch) => {
+                            pattern.push(ch);
+                            i += 1;
+                        }
+                    }
+                }
+                let mut flags = String::new();
+                while let Some(&ch) = chars.get(i).filter(|ch| ch.is_ascii_alphabetic()) {
+                    flags.push(ch);
+                    i += 1;
+                }
+                tokens.push((Token::Regex { pattern, flags }, start));
+            }
+            '^' | '$' if chars.get(i + 1) == Some(&'=') => {
+                tokens.push((Token::Op(format!("{}=", c)), start));
+                i += 2;
+            }
+            c if is_operator_char(c) => {
+                let mut op = c.to_string();
+                i += 1;
+                if chars.get(i) == Some(&'=') && c != '=' && c != '~' {
+                    op.push('=');
+                    i += 1;
+                }
+                if op == "!" {
+                    return Err(QueryError {
+                        message: "Expected '!='".to_string(),
+                        position: start,
+                    });
+                }
+                tokens.push((Token::Op(op), start));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.get(i) {
+                    if ch.is_whitespace() || ch == '(' || ch == ')' || ch == '"' || ch == '\'' || is_operator_char(ch) {
+                        break;
+                    }
+                    word.push(ch);
+                    i += 1;
+                }
+                tokens.push((Token::Word(word), start));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    fn holds<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum NameMatch {
+    Regex(Regex),
+    Contains(String), // Lowercased
+    Exact(String),
+    StartsWith(String),
+    EndsWith(String),
+}
+
+/// A compiled query.
+#[derive(Debug, Clone)]
+pub(crate) enum Query {
+    And(Vec<Query>), // Flat, so a long chain of terms does not nest
+    Or(Vec<Query>),
+    Not(Box<Query>),
+    Category(String),
+    Uncategorized,
+    Size(Comparison, u64),
+    Name(NameMatch),
+}
+
+/// Deepest nesting of parentheses and `NOT`s accepted, so a query cannot overflow the stack.
+/// `AND`/`OR` chains are flat and add no depth however long they are.
+const MAX_NESTING: usize = 64;
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    end: usize, // Position reported for errors at the end of input
+    depth: usize, // Current nesting of parentheses and NOTs
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(token, _)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.index).map_or(self.end, |(_, position)| *position)
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, QueryError> {
+        Err(QueryError {
+            message: message.into(),
+            position: self.position(),
+        })
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).map(|(token, _)| token.clone());
+        self.index += 1;
+        token
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<Query, QueryError> {
+        let mut terms = vec![self.parse_and()?];
+        while self.at_keyword("or") {
+            self.index += 1;
+            terms.push(self.parse_and()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Query::Or(terms) })
+    }
+
+    fn parse_and(&mut self) -> Result<Query, QueryError> {
+        let mut terms = vec![self.parse_not()?];
+        while self.at_keyword("and") {
+            self.index += 1;
+            terms.push(self.parse_not()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Query::And(terms) })
+    }
+
+    /// Skip the opening `NOT` or `(` and parse the nested part with `parse`, failing at the opening
+    /// token when the query is nested deeper than `MAX_NESTING`.
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Query, QueryError>) -> Result<Query, QueryError> {
+        if self.depth >= MAX_NESTING {
+            return self.error(format!("Query is nested more than {} levels deep", MAX_NESTING));
+        }
+        self.index += 1;
+        self.depth += 1;
+        let query = parse(self);
+        self.depth -= 1;
+        query
+    }
+
+    fn parse_not(&mut self) -> Result<Query, QueryError> {
+        if self.at_keyword("not") {
+            return Ok(Query::Not(Box::new(self.nested(Self::parse_not)?)));
+        }
+
+        match self.peek() {
+            Some(Token::LParen) => {
+                let inner = self.nested(Self::parse_or)?;
+                match self.peek() {
+                    Some(Token::RParen) => {
+                        self.index += 1;
+                        Ok(inner)
+                    }
+                    _ => self.error("Expected ')'"),
+                }
+            }
+            Some(Token::Word(_)) => self.parse_clause(),
+            Some(_) => self.error("Expected a filter such as 'cat', 'size' or 'name'"),
+            None => self.error("Unexpected end of query"),
+        }
+    }
+
+    fn parse_value(&mut self, what: &str) -> Result<String, QueryError> {
+        match self.peek() {
+            Some(Token::Word(word)) if !["and", "or", "not"].iter().any(|k| word.eq_ignore_ascii_case(k)) => {
+                let word = word.clone();
+                self.index += 1;
+                Ok(word)
+            }
+            Some(Token::Quoted(value)) => {
+                let value = value.clone();
+                self.index += 1;
+                Ok(value)
+            }
+            _ => self.error(format!("Expected {}", what)),
+        }
+    }
+
+    fn parse_clause(&mut self) -> Result<Query, QueryError> {
+        let field_position = self.position();
+        let Some(Token::Word(field)) = self.next() else {
+            return self.error("Expected a filter");
+        };
+
+        match field.to_lowercase().as_str() {
+            "cat" | "category" => {
+                let category_id = self.parse_value("a category ID")?;
+                if category_id == "uncategorized" {
+                    Ok(Query::Uncategorized)
+                } else {
+                    Ok(Query::Category(category_id))
+                }
+            }
+            "size" => {
+                let comparison = match self.peek() {
+                    Some(Token::Op(op)) => match op.as_str() {
+                        "<" => Comparison::Less,
+                        "<=" => Comparison::LessOrEqual,
+                        ">" => Comparison::Greater,
+                        ">=" => Comparison::GreaterOrEqual,
+                        "=" => Comparison::Equal,
+                        "!=" => Comparison::NotEqual,
+                        _ => return self.error(format!("Operator '{}' cannot be used with size", op)),
+                    },
+                    _ => return self.error("Expected a comparison operator after 'size'"),
+                };
+                self.index += 1;
+
+                let position = self.position();
+                let value = self.parse_value("a size such as 500KB")?;
+                let bytes = parse_size(&value).ok_or(QueryError {
+                    message: format!("Invalid size '{}'", value),
+                    position,
+                })?;
+                Ok(Query::Size(comparison, bytes))
+            }
+            "name" => {
+                let op_position = self.position();
+                let op = match self.next() {
+                    Some(Token::Op(op)) => op,
+                    _ => {
+                        self.index -= 1;
+                        return self.error("Expected '~', '=', '^=' or '$=' after 'name'");
+                    }
+                };
+
+                let position = self.position();
+                if let Some(Token::Regex { pattern, flags }) = self.peek().cloned() {
+                    if op != "~" {
+                        return self.error("Regular expressions can only be used with '~'");
+                    }
+                    self.index += 1;
+                    let regex = compile_regex(&pattern, &flags).map_err(|message| QueryError { message, position })?;
+                    return Ok(Query::Name(NameMatch::Regex(regex)));
+                }
+
+                let value = self.parse_value("a name pattern")?.to_lowercase();
+                Ok(Query::Name(match op.as_str() {
+                    "~" => NameMatch::Contains(value),
+                    "=" => NameMatch::Exact(value),
+                    "^=" => NameMatch::StartsWith(value),
+                    "$=" => NameMatch::EndsWith(value),
+                    _ => {
+                        return Err(QueryError {
+                            message: format!("Operator '{}' cannot be used with name", op),
+                            position: op_position,
+                        })
+                    }
+                }))
+            }
+            _ => Err(QueryError {
+                message: format!("Unknown filter '{}'", field),
+                position: field_position,
+            }),
+        }
+    }
+}
+
+fn compile_regex(pattern: &str, flags: &str) -> Result<Regex, String> {
+    let mut builder = RegexBuilder::new(pattern);
+    for flag in flags.chars() {
+        match flag {
+            'i' => {
+                builder.case_insensitive(true);
+            }
+            other => return Err(format!("Unknown regular expression flag '{}'", other)),
+        }
+    }
+    builder.build().map_err(|e| format!("Invalid regular expression: {}", e))
+}
+
+/// Parse a size like `500`, `500KB`, `1.5MB` or `20B` into bytes. A bare number is in KB.
+fn parse_size(value: &str) -> Option<u64> {
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier = match unit.to_uppercase().as_str() {
+        "B" => 1.0,
+        "" | "K" | "KB" => 1024.0,
+        "M" | "MB" => 1024.0 * 1024.0,
+        "G" | "GB" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((number * multiplier).round() as u64)
+}
+
+impl Query {
+    /// Compile a query string.
+    pub(crate) fn parse(input: &str) -> Result<Query, QueryError> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            index: 0,
+            end: input.chars().count(),
+            depth: 0,
+        };
+        let query = parser.parse_or()?;
+        if parser.peek().is_some() {
+            return parser.error("Expected 'AND', 'OR' or end of query");
+        }
+        Ok(query)
+    }
+
+    /// Whether an image satisfies the query, given the category assignments of all images.
+    pub(crate) fn matches(&self, image: &ImagePath, category_map: &HashMap<String, Vec<CategoryAssignment>>) -> bool {
+        match self {
+            Query::And(terms) => terms.iter().all(|term| term.matches(image, category_map)),
+            Query::Or(terms) => terms.iter().any(|term| term.matches(image, category_map)),
+            Query::Not(inner) => !inner.matches(image, category_map),
+            Query::Category(category_id) => category_map
+                .get(&image.path)
+                .is_some_and(|assignments| assignments.iter().any(|a| a.category_id == *category_id)),
+            Query::Uncategorized => category_map
+                .get(&image.path)
+                .is_none_or(|assignments| assignments.is_empty()),
+            Query::Size(comparison, bytes) => comparison.holds(image.size.unwrap_or(0), *bytes),
+            Query::Name(name_match) => {
+                let file_name = Path::new(&image.path)
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or_default();
+                match name_match {
+                    NameMatch::Regex(regex) => regex.is_match(file_name),
+                    NameMatch::Contains(text) => file_name.to_lowercase().contains(text),
+                    NameMatch::Exact(text) => file_name.to_lowercase() == *text,
+                    NameMatch::StartsWith(text) => file_name.to_lowercase().starts_with(text),
+                    NameMatch::EndsWith(text) => file_name.to_lowercase().ends_with(text),
+                }
+            }
+        }
+    }
+}
+
+/// Check a query string without running it, so the UI can point at the problem.
+///
+/// # Returns
+///
+/// `Ok(())` when the query is valid, otherwise an `invalid_input` `HitoError` on the "query" field
+/// with the message and the character offset (`position`) of the error.
+#[tauri::command]
+#[tracing::instrument(skip_all, err(Debug))]
+pub fn validate_query(query: String) -> Result<(), HitoError> {
+    Query::parse(&query)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(path: &str, size: u64) -> ImagePath {
+        ImagePath {
+            path: path.to_string(),
+            size: Some(size),
+            created_at: None,
+            ..Default::default()
+        }
+    }
+
+    fn categories(entries: &[(&str, &[&str])]) -> HashMap<String, Vec<CategoryAssignment>> {
+        entries
+            .iter()
+            .map(|(path, ids)| {
+                let assignments = ids
+                    .iter()
+                    .map(|id| CategoryAssignment {
+                        category_id: id.to_string(),
+                        assigned_at: "2024-01-01T00:00:00Z".to_string(),
+                        ..Default::default()
+                    })
+                    .collect();
+                (path.to_string(), assignments)
+            })
+            .collect()
+    }
+
+    fn matching(query: &str, images: &[ImagePath], map: &HashMap<String, Vec<CategoryAssignment>>) -> Vec<String> {
+        let query = Query::parse(query).unwrap();
+        images
+            .iter()
+            .filter(|img| query.matches(img, map))
+            .map(|img| img.path.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_query_combines_clauses() {
+        let images = vec![
+            image("/d/IMG_001.jpg", 600 * 1024),
+            image("/d/IMG_002.jpg", 100 * 1024),
+            image("/d/photo.jpg", 700 * 1024),
+            image("/d/IMG_003.jpg", 800 * 1024),
+        ];
+        let map = categories(&[
+            ("/d/IMG_001.jpg", &["a"]),
+            ("/d/IMG_002.jpg", &["b"]),
+            ("/d/photo.jpg", &["a"]),
+            ("/d/IMG_003.jpg", &["b", "c"]),
+        ]);
+
+        let result = matching(r"(cat a OR cat b) AND NOT cat c AND size > 500KB AND name ~ /IMG_\d+/", &images, &map);
+        assert_eq!(result, vec!["/d/IMG_001.jpg"]);
+
+        assert_eq!(matching("cat uncategorized", &images, &map).len(), 0);
+        assert_eq!(matching("name ^= img AND size <= 0.5mb", &images, &map), vec!["/d/IMG_002.jpg"]);
+        assert_eq!(matching("name = \"photo.jpg\" or name $= 3.JPG", &images, &map).len(), 2);
+        assert_eq!(matching("name ~ /^img/i", &images, &map).len(), 3);
+        assert_eq!(matching("NOT NOT cat c", &images, &map), vec!["/d/IMG_003.jpg"]);
+    }
+
+    #[test]
+    fn test_and_binds_tighter_than_or() {
+        let images = vec![image("/a.jpg", 1), image("/b.jpg", 1)];
+        let map = categories(&[("/a.jpg", &["x"]), ("/b.jpg", &["y", "z"])]);
+
+        assert_eq!(matching("cat x OR cat y AND cat q", &images, &map), vec!["/a.jpg"]);
+        assert_eq!(matching("(cat x OR cat y) AND cat z", &images, &map), vec!["/b.jpg"]);
+    }
+
+    #[test]
+    fn test_parse_errors_report_position() {
+        let error = |query: &str| Query::parse(query).unwrap_err();
+
+        assert_eq!(error("cat a AND").position, 9);
+        assert_eq!(error("(cat a").message, "Expected ')'");
+        assert_eq!(error("(cat a").position, 6);
+        assert_eq!(error("cat a dog").position, 6);
+        assert_eq!(error("colour = red").position, 0);
+        assert_eq!(error("size > huge").position, 7);
+        assert_eq!(error("name < x").position, 5);
+        assert_eq!(error("name ~ /(/").position, 7);
+        assert_eq!(error("name ~ \"open").position, 7);
+        assert_eq!(error("cat a AND cat b)").to_string(), "Expected 'AND', 'OR' or end of query at position 15");
+
+        // Nesting is limited instead of overflowing the stack
+        let deep = format!("{}cat a{}", "(".repeat(MAX_NESTING + 1), ")".repeat(MAX_NESTING + 1));
+        assert_eq!(error(&deep).position, MAX_NESTING);
+        assert_eq!(error(&"NOT ".repeat(100_000)).position, MAX_NESTING * 4);
+        assert!(Query::parse(&format!("{}cat a{}", "(".repeat(MAX_NESTING), ")".repeat(MAX_NESTING))).is_ok());
+
+        // So are long chains, which are evaluated and dropped without deep recursion
+        let images = vec![ImagePath { path: "/a.jpg".to_string(), ..Default::default() }];
+        let map = categories(&[("/a.jpg", &["b"])]);
+        let chain = format!("{} OR cat b", vec!["cat a"; 100_000].join(" OR "));
+        assert_eq!(matching(&chain, &images, &map), vec!["/a.jpg"]);
+        let chain = vec!["NOT cat a"; 100_000].join(" AND ");
+        assert_eq!(matching(&chain, &images, &map), vec!["/a.jpg"]);
+
+        assert!(validate_query("size >= 1GB".to_string()).is_ok());
+        assert!(validate_query("size =>".to_string()).is_err());
+    }
+}
@@ -0,0 +1,71 @@
+/// Small deterministic PRNG (SplitMix64) so seeded shuffles are reproducible across platforms.
+pub(crate) struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..bound` (`bound` must be non-zero).
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as usize
+    }
+
+    /// Fisher-Yates shuffle in place.
+    pub(crate) fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+/// Reproducible pseudo-random rank of `key` for `seed`.
+///
+/// Unlike `shuffle`, the rank depends only on the key, so an item keeps its relative position
+/// no matter how the input was ordered or which other items are present.
+pub(crate) fn rank(seed: u64, key: &str) -> u64 {
+    // FNV-1a, which is stable across platforms and Rust versions (unlike `DefaultHasher`)
+    let hash = key.bytes().fold(0xCBF2_9CE4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01B3)
+    });
+    SplitMix64::new(seed ^ hash).next_u64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shuffle_is_deterministic_per_seed() {
+        let shuffled = |seed| {
+            let mut items: Vec<u32> = (0..20).collect();
+            SplitMix64::new(seed).shuffle(&mut items);
+            items
+        };
+
+        assert_eq!(shuffled(42), shuffled(42));
+        assert_ne!(shuffled(42), shuffled(43));
+
+        let mut sorted = shuffled(42);
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn test_rank_depends_on_seed_and_key_only() {
+        assert_eq!(rank(7, "/a.jpg"), rank(7, "/a.jpg"));
+        assert_ne!(rank(7, "/a.jpg"), rank(8, "/a.jpg"));
+        assert_ne!(rank(7, "/a.jpg"), rank(7, "/b.jpg"));
+    }
+}
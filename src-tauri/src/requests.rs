@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::error::HitoError;
+
+/// Cancellation flag shared between a running command and `cancel_request`.
+#[derive(Clone, Default)]
+pub(crate) struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// `Err(cancelled)` once the request has been cancelled; called between units of work.
+    pub(crate) fn check(&self) -> Result<(), HitoError> {
+        if self.is_cancelled() {
+            Err(HitoError::cancelled())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// How long a cancellation that arrived before its request is remembered.
+const EARLY_CANCEL_TTL: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+struct Registry {
+    in_flight: HashMap<String, (CancelToken, usize)>, // ID -> (shared token, number of commands running under the ID)
+    cancelled_early: HashMap<String, Instant>, // IDs cancelled with nothing in flight -> when
+}
+
+impl Registry {
+    fn prune_early_cancels(&mut self) {
+        self.cancelled_early.retain(|_, at| at.elapsed() < EARLY_CANCEL_TTL);
+    }
+}
+
+static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+
+fn registry() -> MutexGuard<'static, Registry> {
+    // The registry only holds flags, counters and timestamps, so it is still usable after a panic elsewhere
+    REGISTRY
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// A command running under a frontend-chosen request ID.
+///
+/// Several commands may share an ID (e.g. the listing and image loads of one folder view), so
+/// cancelling the ID stops all of them. The ID is unregistered when the last one is dropped.
+pub(crate) struct Request {
+    id: Option<String>,
+    token: CancelToken,
+}
+
+impl Request {
+    /// Register a command under `request_id`; without an ID the command cannot be cancelled.
+    ///
+    /// If the ID was cancelled shortly before (the frontend may cancel a request before its
+    /// command has started), the command starts out cancelled.
+    pub(crate) fn start(request_id: Option<String>) -> Self {
+        let token = match &request_id {
+            Some(id) => {
+                let mut registry = registry();
+                registry.prune_early_cancels();
+                let cancelled_early = registry.cancelled_early.contains_key(id);
+                let entry = registry.in_flight.entry(id.clone()).or_default();
+                entry.1 += 1;
+                if cancelled_early {
+                    entry.0.cancel();
+                }
+                entry.0.clone()
+            }
+            None => CancelToken::default(),
+        };
+        Request { id: request_id, token }
+    }
+
+    pub(crate) fn token(&self) -> &CancelToken {
+        &self.token
+    }
+}
+
+impl Drop for Request {
+    fn drop(&mut self) {
+        let Some(id) = &self.id else {
+            return;
+        };
+        let mut registry = registry();
+        if let Some(entry) = registry.in_flight.get_mut(id) {
+            entry.1 -= 1;
+            if entry.1 == 0 {
+                registry.in_flight.remove(id);
+            }
+        }
+    }
+}
+
+/// Run blocking file system work on the worker pool so it does not hold up other commands.
+pub(crate) async fn run_blocking<T, F>(work: F) -> Result<T, HitoError>
+where
+    F: FnOnce() -> Result<T, HitoError> + Send + 'static,
+    T: Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(work)
+        .await
+        .map_err(|e| HitoError::internal(format!("Worker task failed: {}", e)))?
+}
+
+/// Cancel the commands running under `request_id`.
+///
+/// They stop at their next check and fail with the `cancelled` code. An ID with nothing in flight
+/// is remembered for `EARLY_CANCEL_TTL`, so commands that start under it in that time are
+/// cancelled from the start; afterwards the ID can be used again.
+///
+/// # Returns
+///
+/// `true` if a command was running under the ID.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(request_id = %request_id))]
+pub fn cancel_request(request_id: String) -> bool {
+    let mut registry = registry();
+    registry.prune_early_cancels();
+    match registry.in_flight.get(&request_id) {
+        Some((token, _)) => {
+            token.cancel();
+            true
+        }
+        None => {
+            registry.cancelled_early.insert(request_id, Instant::now());
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn test_cancel_reaches_every_command_under_the_id() {
+        let first = Request::start(Some("test-cancel-shared".to_string()));
+        let second = Request::start(Some("test-cancel-shared".to_string()));
+        let other = Request::start(Some("test-cancel-other".to_string()));
+
+        assert!(cancel_request("test-cancel-shared".to_string()));
+        assert!(first.token().is_cancelled());
+        assert_eq!(second.token().check().unwrap_err().code, ErrorCode::Cancelled);
+        assert!(other.token().check().is_ok());
+
+        drop(first);
+        assert!(registry().in_flight.contains_key("test-cancel-shared"));
+        drop(second);
+        assert!(!registry().in_flight.contains_key("test-cancel-shared"));
+        assert!(!cancel_request("test-cancel-shared".to_string()));
+    }
+
+    #[test]
+    fn test_cancel_before_start_cancels_the_request() {
+        assert!(!cancel_request("test-cancel-early".to_string()));
+        let request = Request::start(Some("test-cancel-early".to_string()));
+        assert_eq!(request.token().check().unwrap_err().code, ErrorCode::Cancelled);
+        // Every command of the cancelled view is stopped, not just the first to start
+        let later = Request::start(Some("test-cancel-early".to_string()));
+        assert!(later.token().is_cancelled());
+        drop((request, later));
+
+        // Once the cancellation has expired the ID is usable again
+        registry().cancelled_early.insert("test-cancel-early".to_string(), Instant::now() - EARLY_CANCEL_TTL);
+        assert!(Request::start(Some("test-cancel-early".to_string())).token().check().is_ok());
+    }
+
+    #[test]
+    fn test_requests_without_id_are_not_registered() {
+        let request = Request::start(None);
+        assert!(request.token().check().is_ok());
+        assert!(!cancel_request(String::new()));
+    }
+}
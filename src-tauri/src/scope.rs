@@ -0,0 +1,364 @@
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use tauri::{AppHandle, Wry};
+use tauri_plugin_dialog::{DialogExt, FileDialogBuilder};
+
+use crate::error::{ErrorCode, HitoError};
+use crate::requests::run_blocking;
+use crate::AppData;
+
+/// Canonical directories the user opened (dialog or drag-drop); file commands stay inside them.
+static GRANTED_ROOTS: OnceLock<Mutex<Vec<PathBuf>>> = OnceLock::new();
+
+/// Canonical paths of single files the user chose in a file dialog, e.g. export targets.
+static GRANTED_FILES: OnceLock<Mutex<Vec<PathBuf>>> = OnceLock::new();
+
+fn lock_paths(paths: &'static OnceLock<Mutex<Vec<PathBuf>>>) -> MutexGuard<'static, Vec<PathBuf>> {
+    // A list of paths cannot be left half-updated, so a poisoned lock is still usable
+    paths
+        .get_or_init(|| Mutex::new(Vec::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+fn granted_roots() -> MutexGuard<'static, Vec<PathBuf>> {
+    lock_paths(&GRANTED_ROOTS)
+}
+
+fn granted_files() -> MutexGuard<'static, Vec<PathBuf>> {
+    lock_paths(&GRANTED_FILES)
+}
+
+/// Canonical form of the absolute `path`.
+///
+/// A path that does not exist yet (e.g. an output file) is resolved through its nearest existing
+/// ancestor; the missing part may not contain `..`.
+fn resolve(path: &Path) -> Result<PathBuf, HitoError> {
+    let invalid = || HitoError::invalid_input(format!("Invalid path: {}", path.display())).with_path(path);
+    if !path.is_absolute() {
+        return Err(invalid());
+    }
+
+    let mut ancestor = path;
+    let mut missing = Vec::new();
+    let canonical = loop {
+        if let Ok(canonical) = ancestor.canonicalize() {
+            break canonical;
+        }
+        match (ancestor.parent(), ancestor.components().next_back()) {
+            (Some(parent), Some(Component::Normal(name))) => {
+                missing.push(name);
+                ancestor = parent;
+            }
+            _ => return Err(invalid()),
+        }
+    };
+    Ok(missing.into_iter().rev().fold(canonical, |path, name| path.join(name)))
+}
+
+/// Add the directory at `path` (or the directory of the file at `path`) to the scope.
+///
+/// # Returns
+///
+/// The canonical directory that was granted.
+pub(crate) fn grant(path: &Path) -> Result<PathBuf, HitoError> {
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| HitoError::io("Failed to open directory", path, e))?;
+    let directory = if canonical.is_dir() {
+        canonical
+    } else {
+        canonical.parent().map(Path::to_path_buf).unwrap_or(canonical)
+    };
+
+    let mut roots = granted_roots();
+    if !roots.iter().any(|root| directory.starts_with(root)) {
+        roots.push(directory.clone());
+    }
+    Ok(directory)
+}
+
+/// Add the single file at `path` to the scope, without the rest of its directory.
+///
+/// The file does not have to exist yet (e.g. the target of a save dialog).
+///
+/// # Returns
+///
+/// The canonical path of the granted file.
+pub(crate) fn grant_file(path: &Path) -> Result<PathBuf, HitoError> {
+    let canonical = resolve(path)?;
+    let mut files = granted_files();
+    if !files.contains(&canonical) {
+        files.push(canonical.clone());
+    }
+    Ok(canonical)
+}
+
+/// Grant the search roots and custom data file locations saved in `app_data` again.
+///
+/// They were checked against the scope when they were saved, but the scope only lasts for one run.
+/// Search roots were opened as directories and are granted as such; data files are granted on
+/// their own, as when they were chosen in a file dialog, so the rest of their directory stays
+/// out of reach. Search roots that no longer exist are skipped.
+pub(crate) fn grant_saved(app_data: &AppData) {
+    for root in app_data.search_roots.iter().flatten() {
+        if let Err(e) = grant(Path::new(root)) {
+            tracing::warn!(error = ?e, "Failed to open a saved search root");
+        }
+    }
+    let data_files = app_data.data_file_paths.iter().flat_map(|paths| paths.values());
+    for data_file in data_files.filter(|path| !path.is_empty()).map(Path::new) {
+        if let Err(e) = grant_file(data_file) {
+            tracing::warn!(error = ?e, "Failed to open a saved data file");
+        }
+    }
+}
+
+/// Check that `path` lies inside an opened directory or is a file chosen in a file dialog.
+///
+/// # Returns
+///
+/// The canonical path, or a `permission_denied` `HitoError` when it is outside the scope.
+pub(crate) fn check(path: impl AsRef<Path>) -> Result<PathBuf, HitoError> {
+    let path = path.as_ref();
+    let canonical = resolve(path)?;
+    if granted_roots().iter().any(|root| canonical.starts_with(root)) || granted_files().contains(&canonical) {
+        Ok(canonical)
+    } else {
+        Err(HitoError::new(
+            ErrorCode::PermissionDenied,
+            format!("Path is outside the opened directories: {}", path.display()),
+        )
+        .with_path(path))
+    }
+}
+
+/// Check that a data file name is a plain file name, so it cannot point outside its directory.
+pub(crate) fn check_file_name(name: &str) -> Result<(), HitoError> {
+    let mut components = Path::new(name).components();
+    let is_plain = matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+        && !name.contains(['/', '\\']);
+    if is_plain {
+        Ok(())
+    } else {
+        Err(HitoError::invalid_field("filename", format!("Data file name must not contain path separators: {}", name)))
+    }
+}
+
+/// Show the folder dialog and grant access to the folder the user picks.
+///
+/// The dialog runs in the backend, so only folders the user actually picked join the scope; a path
+/// sent by the frontend is never granted, `default_path` only sets where the dialog starts (e.g. a
+/// breadcrumb above the opened folders). Dropped files and folders are granted by the window's
+/// drag-drop handler.
+///
+/// # Returns
+///
+/// The canonical path of the granted folder, or `None` when the dialog was cancelled.
+#[tauri::command]
+#[tracing::instrument(skip_all, err(Debug))]
+pub async fn pick_directory(
+    app: AppHandle,
+    title: Option<String>,
+    default_path: Option<String>,
+) -> Result<Option<String>, HitoError> {
+    run_blocking(move || {
+        let mut dialog = app.dialog().file();
+        if let Some(title) = title {
+            dialog = dialog.set_title(title);
+        }
+        if let Some(default_path) = default_path {
+            dialog = dialog.set_directory(default_path);
+        }
+        let Some(picked) = dialog.blocking_pick_folder() else {
+            return Ok(None);
+        };
+        let path = picked
+            .into_path()
+            .map_err(|e| HitoError::invalid_input(format!("Picked folder is not a local path: {}", e)))?;
+        Ok(Some(grant(&path)?.to_string_lossy().into_owned()))
+    })
+    .await
+}
+
+/// File dialog with `title` that only offers files with one of `extensions` (without dots), if given.
+fn file_dialog(app: &AppHandle, title: Option<String>, extensions: Option<Vec<String>>) -> FileDialogBuilder<Wry> {
+    let mut dialog = app.dialog().file();
+    if let Some(title) = title {
+        dialog = dialog.set_title(title);
+    }
+    if let Some(extensions) = extensions.filter(|extensions| !extensions.is_empty()) {
+        let extensions: Vec<&str> = extensions.iter().map(String::as_str).collect();
+        dialog = dialog.add_filter(extensions.join(", "), &extensions);
+    }
+    dialog
+}
+
+/// Show the save dialog and grant access to the file the user chooses, e.g. an export target.
+///
+/// Only the chosen file joins the scope, not its folder, so exports can go anywhere (e.g. the
+/// desktop) without opening that folder. `default_name` is the suggested file name.
+///
+/// # Returns
+///
+/// The canonical path of the granted file, or `None` when the dialog was cancelled.
+#[tauri::command]
+#[tracing::instrument(skip_all, err(Debug))]
+pub async fn pick_save_file(
+    app: AppHandle,
+    title: Option<String>,
+    default_name: Option<String>,
+    extensions: Option<Vec<String>>,
+) -> Result<Option<String>, HitoError> {
+    run_blocking(move || {
+        let mut dialog = file_dialog(&app, title, extensions);
+        if let Some(default_name) = default_name {
+            dialog = dialog.set_file_name(default_name);
+        }
+        let Some(picked) = dialog.blocking_save_file() else {
+            return Ok(None);
+        };
+        let path = picked
+            .into_path()
+            .map_err(|e| HitoError::invalid_input(format!("Chosen file is not a local path: {}", e)))?;
+        Ok(Some(grant_file(&path)?.to_string_lossy().into_owned()))
+    })
+    .await
+}
+
+/// Show the open dialog and grant access to the file the user picks, e.g. a shared preset.
+///
+/// Only the picked file joins the scope, not its folder.
+///
+/// # Returns
+///
+/// The canonical path of the granted file, or `None` when the dialog was cancelled.
+#[tauri::command]
+#[tracing::instrument(skip_all, err(Debug))]
+pub async fn pick_open_file(
+    app: AppHandle,
+    title: Option<String>,
+    extensions: Option<Vec<String>>,
+) -> Result<Option<String>, HitoError> {
+    run_blocking(move || {
+        let Some(picked) = file_dialog(&app, title, extensions).blocking_pick_file() else {
+            return Ok(None);
+        };
+        let path = picked
+            .into_path()
+            .map_err(|e| HitoError::invalid_input(format!("Picked file is not a local path: {}", e)))?;
+        Ok(Some(grant_file(&path)?.to_string_lossy().into_owned()))
+    })
+    .await
+}
+
+/// Get the opened directories, so the frontend can tell which paths it can browse without a dialog.
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn get_opened_directories() -> Vec<String> {
+    granted_roots().iter().map(|root| root.to_string_lossy().into_owned()).collect()
+}
+
+/// Temporary directory inside the scope, for tests of file commands.
+#[cfg(test)]
+pub(crate) fn granted_temp_dir() -> tempfile::TempDir {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    grant(temp_dir.path()).unwrap();
+    temp_dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_check_rejects_paths_outside_granted_roots() {
+        let granted = granted_temp_dir();
+        let outside = TempDir::new().unwrap();
+        fs::create_dir(granted.path().join("sub")).unwrap();
+
+        assert!(check(granted.path().join("sub")).is_ok());
+        // Output files do not exist yet
+        assert!(check(granted.path().join("sub/new.json")).is_ok());
+        assert_eq!(check(outside.path()).unwrap_err().code, ErrorCode::PermissionDenied);
+        assert_eq!(check(granted.path().join("sub/../..")).unwrap_err().code, ErrorCode::PermissionDenied);
+        assert_eq!(check(granted.path().join("missing/../../x")).unwrap_err().code, ErrorCode::InvalidInput);
+        assert_eq!(check("relative/path").unwrap_err().code, ErrorCode::InvalidInput);
+
+        // Granting a file grants its directory
+        let file = outside.path().join("a.png");
+        fs::write(&file, b"png").unwrap();
+        assert_eq!(grant(&file).unwrap(), outside.path().canonicalize().unwrap());
+        assert!(check(outside.path()).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_follows_symlinks() {
+        let granted = granted_temp_dir();
+        let outside = TempDir::new().unwrap();
+        std::os::unix::fs::symlink(outside.path(), granted.path().join("link")).unwrap();
+
+        assert_eq!(check(granted.path().join("link")).unwrap_err().code, ErrorCode::PermissionDenied);
+    }
+
+    #[test]
+    fn test_grant_file_grants_only_that_file() {
+        let outside = TempDir::new().unwrap();
+        let export = outside.path().join("labels.csv");
+
+        assert_eq!(check(&export).unwrap_err().code, ErrorCode::PermissionDenied);
+        // A save target does not exist yet
+        assert_eq!(grant_file(&export).unwrap(), outside.path().canonicalize().unwrap().join("labels.csv"));
+        assert!(check(&export).is_ok());
+        assert_eq!(check(outside.path().join("other.csv")).unwrap_err().code, ErrorCode::PermissionDenied);
+        assert_eq!(check(outside.path()).unwrap_err().code, ErrorCode::PermissionDenied);
+    }
+
+    #[test]
+    fn test_grant_saved_restores_search_roots_and_data_files() {
+        let root = TempDir::new().unwrap();
+        let data_dir = TempDir::new().unwrap();
+        let pending_dir = TempDir::new().unwrap();
+        let image_dir = TempDir::new().unwrap();
+        let data_file = data_dir.path().join("labels.json");
+        fs::write(&data_file, "{}").unwrap();
+        fs::write(data_dir.path().join("other.json"), "{}").unwrap();
+
+        let app_data = AppData {
+            search_roots: Some(vec![root.path().to_str().unwrap().to_string()]),
+            data_file_paths: Some(
+                [
+                    (image_dir.path().to_str().unwrap().to_string(), data_file.to_str().unwrap().to_string()),
+                    ("/elsewhere".to_string(), pending_dir.path().join("new.json").to_str().unwrap().to_string()),
+                    ("/gone".to_string(), "/missing/dir/labels.json".to_string()),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+        grant_saved(&app_data);
+
+        assert!(check(root.path()).is_ok());
+        assert!(check(&data_file).is_ok());
+        assert!(check(pending_dir.path().join("new.json")).is_ok());
+        // Only the data files themselves are granted, not their directories or the image directory
+        assert!(check(data_dir.path().join("other.json")).is_err());
+        assert!(check(pending_dir.path().join("other.json")).is_err());
+        assert!(check(image_dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_check_file_name() {
+        assert!(check_file_name(".hito.json").is_ok());
+        assert!(check_file_name("alice.json").is_ok());
+        for name in ["", ".", "..", "../x.json", "a/b.json", "a\\b.json", "/etc/passwd"] {
+            let error = check_file_name(name).unwrap_err();
+            assert_eq!(error.field.as_deref(), Some("filename"), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::error::HitoError;
+use crate::events::{get_event_log_path, read_events, LabelEvent};
+use crate::scope;
+
+/// Gaps between events longer than this (in seconds) count as idle time and end a session.
+const DEFAULT_IDLE_THRESHOLD_SECS: u64 = 300;
+
+#[derive(Serialize)]
+pub struct SessionSummary {
+    start: String,
+    end: String,
+    active_seconds: f64,
+    event_count: usize,
+    images_labelled: usize,
+    images_per_hour: Option<f64>,
+    labels_per_category: Vec<(String, usize)>, // category ID -> assign events in this session
+}
+
+#[derive(Serialize)]
+pub struct IdlePeriod {
+    start: String,
+    end: String,
+    seconds: f64,
+}
+
+#[derive(Serialize)]
+pub struct ImageTime {
+    image_path: String,
+    seconds: f64,
+}
+
+#[derive(Serialize)]
+pub struct SessionStats {
+    event_count: usize,
+    active_seconds: f64,
+    idle_seconds: f64,
+    images_labelled: usize,
+    images_per_hour: Option<f64>, // Over active time, None when there is none yet
+    sessions: Vec<SessionSummary>,
+    idle_periods: Vec<IdlePeriod>,
+    image_times: Vec<ImageTime>, // Time-on-task per image, sorted by path
+}
+
+/// Session being accumulated while walking the event log.
+struct OpenSession {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    active_seconds: f64,
+    event_count: usize,
+    images: BTreeSet<String>,
+    labels_per_category: BTreeMap<String, usize>,
+}
+
+impl OpenSession {
+    fn new(at: DateTime<Utc>) -> Self {
+        OpenSession {
+            start: at,
+            end: at,
+            active_seconds: 0.0,
+            event_count: 0,
+            images: BTreeSet::new(),
+            labels_per_category: BTreeMap::new(),
+        }
+    }
+
+    fn close(self) -> SessionSummary {
+        SessionSummary {
+            start: self.start.to_rfc3339(),
+            end: self.end.to_rfc3339(),
+            active_seconds: self.active_seconds,
+            event_count: self.event_count,
+            images_labelled: self.images.len(),
+            images_per_hour: per_hour(self.images.len(), self.active_seconds),
+            labels_per_category: self.labels_per_category.into_iter().collect(),
+        }
+    }
+}
+
+fn per_hour(count: usize, seconds: f64) -> Option<f64> {
+    (seconds > 0.0).then(|| count as f64 * 3600.0 / seconds)
+}
+
+/// Derive throughput statistics from label events.
+///
+/// The time between two consecutive events is attributed to the image of the later one (the time
+/// it took to reach that decision). Gaps longer than `idle_threshold_secs` are reported as idle
+/// periods instead and split the log into sessions.
+fn compute_stats(events: &[LabelEvent], idle_threshold_secs: u64) -> SessionStats {
+    let mut timed: Vec<(DateTime<Utc>, &LabelEvent)> = events
+        .iter()
+        .filter(|e| e.kind == "assign" || e.kind == "unassign")
+        .filter_map(|e| {
+            let at = DateTime::parse_from_rfc3339(&e.timestamp).ok()?;
+            Some((at.with_timezone(&Utc), e))
+        })
+        .collect();
+    timed.sort_by_key(|(at, _)| *at);
+
+    let idle_threshold = idle_threshold_secs as f64;
+    let mut sessions = Vec::new();
+    let mut idle_periods = Vec::new();
+    let mut image_times: BTreeMap<String, f64> = BTreeMap::new();
+    let mut all_images = BTreeSet::new();
+    let mut current: Option<OpenSession> = None;
+
+    for (at, event) in &timed {
+        let session = match current.take() {
+            Some(mut session) => {
+                let gap = (*at - session.end).num_milliseconds() as f64 / 1000.0;
+                if gap > idle_threshold {
+                    idle_periods.push(IdlePeriod {
+                        start: session.end.to_rfc3339(),
+                        end: at.to_rfc3339(),
+                        seconds: gap,
+                    });
+                    sessions.push(session.close());
+                    OpenSession::new(*at)
+                } else {
+                    session.active_seconds += gap;
+                    if let Some(path) = &event.image_path {
+                        *image_times.entry(path.clone()).or_default() += gap;
+                    }
+                    session
+                }
+            }
+            None => OpenSession::new(*at),
+        };
+
+        let mut session = session;
+        session.end = *at;
+        session.event_count += 1;
+        if let Some(path) = &event.image_path {
+            image_times.entry(path.clone()).or_default();
+            session.images.insert(path.clone());
+            all_images.insert(path.clone());
+        }
+        if event.kind == "assign" {
+            if let Some(category_id) = &event.category_id {
+                *session.labels_per_category.entry(category_id.clone()).or_default() += 1;
+            }
+        }
+        current = Some(session);
+    }
+    sessions.extend(current.map(OpenSession::close));
+
+    let active_seconds: f64 = sessions.iter().map(|s| s.active_seconds).sum();
+
+    SessionStats {
+        event_count: timed.len(),
+        active_seconds,
+        idle_seconds: idle_periods.iter().map(|p| p.seconds).sum(),
+        images_labelled: all_images.len(),
+        images_per_hour: per_hour(all_images.len(), active_seconds),
+        sessions,
+        idle_periods,
+        image_times: image_times
+            .into_iter()
+            .map(|(image_path, seconds)| ImageTime { image_path, seconds })
+            .collect(),
+    }
+}
+
+/// Load the events of a data file, keeping only those of `annotator` when given.
+fn load_events(
+    directory: &str,
+    filename: Option<&str>,
+    annotator: Option<&str>,
+) -> Result<Vec<LabelEvent>, HitoError> {
+    let events = read_events(&get_event_log_path(directory, filename)?)?;
+    Ok(match annotator {
+        Some(annotator) => events
+            .into_iter()
+            .filter(|e| e.annotator.as_deref() == Some(annotator))
+            .collect(),
+        None => events,
+    })
+}
+
+/// Get labelling throughput for a data file from its event log.
+///
+/// Events are recorded by `save_hito_config`. `idle_threshold_secs` (default 300) is the longest gap
+/// between two events that still counts as working time.
+///
+/// # Returns
+///
+/// A `SessionStats` with time-on-task per image, images per hour, labels per category per session
+/// and the detected idle periods.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref()), err(Debug))]
+pub fn get_session_stats(
+    directory: String,
+    filename: Option<String>,
+    annotator: Option<String>,
+    idle_threshold_secs: Option<u64>,
+) -> Result<SessionStats, HitoError> {
+    let events = load_events(&directory, filename.as_deref(), annotator.as_deref())?;
+    Ok(compute_stats(&events, idle_threshold_secs.unwrap_or(DEFAULT_IDLE_THRESHOLD_SECS)))
+}
+
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn events_to_csv(events: &[LabelEvent]) -> String {
+    let mut csv = String::from("timestamp,kind,image_path,from_path,category_id,annotator\n");
+    for event in events {
+        let fields = [
+            event.timestamp.as_str(),
+            event.kind.as_str(),
+            event.image_path.as_deref().unwrap_or(""),
+            event.from_path.as_deref().unwrap_or(""),
+            event.category_id.as_deref().unwrap_or(""),
+            event.annotator.as_deref().unwrap_or(""),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Export the raw label events of a data file as CSV to `output_path`.
+///
+/// # Returns
+///
+/// The number of exported events.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory, filename = filename.as_deref(), output_path = %output_path), err(Debug))]
+pub fn export_session_events_csv(
+    directory: String,
+    filename: Option<String>,
+    annotator: Option<String>,
+    output_path: String,
+) -> Result<usize, HitoError> {
+    scope::check(&output_path)?;
+    let events = load_events(&directory, filename.as_deref(), annotator.as_deref())?;
+    fs::write(&output_path, events_to_csv(&events))
+        .map_err(|e| HitoError::io("Failed to write CSV file", &output_path, e))?;
+    Ok(events.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{save_hito_config, CategoryAssignment};
+    use crate::scope::granted_temp_dir;
+
+    fn event(timestamp: &str, kind: &str, image_path: &str, category_id: &str) -> LabelEvent {
+        LabelEvent {
+            timestamp: timestamp.to_string(),
+            kind: kind.to_string(),
+            image_path: Some(image_path.to_string()),
+            category_id: Some(category_id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compute_stats_splits_sessions_on_idle() {
+        let events = vec![
+            event("2024-01-01T10:00:00Z", "assign", "/a.jpg", "cat"),
+            event("2024-01-01T10:00:30Z", "assign", "/b.jpg", "dog"),
+            event("2024-01-01T10:01:30Z", "assign", "/c.jpg", "cat"),
+            // 20 minutes away
+            event("2024-01-01T10:21:30Z", "assign", "/d.jpg", "cat"),
+            event("2024-01-01T10:22:00Z", "unassign", "/d.jpg", "cat"),
+        ];
+
+        let stats = compute_stats(&events, 300);
+
+        assert_eq!(stats.event_count, 5);
+        assert_eq!(stats.images_labelled, 4);
+        assert_eq!(stats.sessions.len(), 2);
+        assert_eq!(stats.idle_periods.len(), 1);
+        assert_eq!(stats.idle_periods[0].seconds, 1200.0);
+        assert_eq!(stats.active_seconds, 120.0);
+        assert_eq!(stats.images_per_hour, Some(120.0));
+
+        let first = &stats.sessions[0];
+        assert_eq!(first.event_count, 3);
+        assert_eq!(first.labels_per_category, vec![("cat".to_string(), 2), ("dog".to_string(), 1)]);
+
+        let time = |path: &str| stats.image_times.iter().find(|t| t.image_path == path).unwrap().seconds;
+        assert_eq!(time("/a.jpg"), 0.0);
+        assert_eq!(time("/b.jpg"), 30.0);
+        assert_eq!(time("/c.jpg"), 60.0);
+        assert_eq!(time("/d.jpg"), 30.0);
+    }
+
+    #[test]
+    fn test_csv_escapes_fields() {
+        let csv = events_to_csv(&[event("2024-01-01T10:00:00Z", "assign", "/a, \"b\".jpg", "cat")]);
+        assert_eq!(
+            csv,
+            "timestamp,kind,image_path,from_path,category_id,annotator\n2024-01-01T10:00:00Z,assign,\"/a, \"\"b\"\".jpg\",,cat,\n"
+        );
+    }
+
+    #[test]
+    fn test_save_hito_config_records_events() {
+        let temp_dir = granted_temp_dir();
+        let directory = temp_dir.path().to_str().unwrap().to_string();
+        let assignment = CategoryAssignment {
+            category_id: "cat".to_string(),
+            assigned_at: "2024-01-01T10:00:00Z".to_string(),
+            ..Default::default()
+        };
+
+        save_hito_config(directory.clone(), vec![("/a.jpg".to_string(), vec![assignment])], None, None, None, Some("alice".to_string()))
+            .unwrap();
+        save_hito_config(directory.clone(), Vec::new(), None, None, None, Some("alice".to_string())).unwrap();
+
+        let stats = get_session_stats(directory.clone(), None, Some("alice".to_string()), None).unwrap();
+        assert_eq!(stats.event_count, 2);
+        assert_eq!(get_session_stats(directory.clone(), None, Some("bob".to_string()), None).unwrap().event_count, 0);
+
+        let output = temp_dir.path().join("events.csv");
+        let count = export_session_events_csv(directory, None, None, output.to_str().unwrap().to_string()).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(fs::read_to_string(output).unwrap().lines().count(), 3);
+    }
+}
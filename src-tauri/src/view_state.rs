@@ -0,0 +1,232 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::HitoError;
+use crate::{is_sort_key, load_app_data, scope, update_app_data_sync, AppData, FilterOptions};
+
+const MAX_RECENT_DIRECTORIES: usize = 20;
+
+/// How a directory was last viewed, so it can be reopened as it was left.
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct ViewState {
+    sort_option: Option<String>, // Any `sort_option` of sort_images
+    sort_direction: Option<String>, // "ascending" or "descending"
+    filters: Option<FilterOptions>,
+    last_image: Option<String>, // Image last opened in the modal
+    scroll_position: Option<f64>, // Vertical scroll offset of the image grid, in pixels
+}
+
+impl ViewState {
+    fn validate(&self) -> Result<(), HitoError> {
+        if let Some(sort_option) = self.sort_option.as_deref().filter(|option| !is_sort_key(option)) {
+            return Err(HitoError::invalid_field("sort_option", format!("Unknown sort option: {}", sort_option)));
+        }
+        if let Some(direction) = self.sort_direction.as_deref().filter(|d| !matches!(*d, "ascending" | "descending")) {
+            return Err(HitoError::invalid_field("sort_direction", format!("Unknown sort direction: {}", direction)));
+        }
+        Ok(())
+    }
+}
+
+/// Move `directory` to the front of the recent directories.
+///
+/// Directories beyond `MAX_RECENT_DIRECTORIES` are dropped together with their view state.
+fn touch_recent(app_data: &mut AppData, directory: &str) {
+    let recent = app_data.recent_directories.get_or_insert_with(Vec::new);
+    recent.retain(|d| d != directory);
+    recent.insert(0, directory.to_string());
+    recent.truncate(MAX_RECENT_DIRECTORIES);
+
+    if let Some(view_states) = app_data.view_states.as_mut() {
+        view_states.retain(|d, _| recent.contains(d));
+    }
+}
+
+/// Grant a directory from the recent list again and move it to the front.
+fn reopen(app_data: &mut AppData, directory: &str) -> Result<Option<ViewState>, HitoError> {
+    if !app_data.recent_directories.as_deref().unwrap_or_default().iter().any(|d| d == directory) {
+        return Err(HitoError::not_found(format!("Not a recent directory: {}", directory)).with_path(directory));
+    }
+    scope::grant(Path::new(directory))?;
+    touch_recent(app_data, directory);
+    Ok(app_data.view_states.as_ref().and_then(|view_states| view_states.get(directory)).cloned())
+}
+
+fn remove_recent(app_data: &mut AppData, directory: &str) {
+    if let Some(recent) = app_data.recent_directories.as_mut() {
+        recent.retain(|d| d != directory);
+    }
+    if let Some(view_states) = app_data.view_states.as_mut() {
+        view_states.remove(directory);
+    }
+}
+
+/// Check that `directory` is an opened directory before it is remembered.
+///
+/// Returns its canonical path, which recent directories and view states are keyed by, so the
+/// same folder reached through a symlink or a trailing slash shares one entry.
+fn check_directory(directory: &str) -> Result<String, HitoError> {
+    let canonical = scope::check(directory)?;
+    if !canonical.is_dir() {
+        return Err(HitoError::not_found(format!("Directory does not exist: {}", directory)).with_path(directory));
+    }
+    canonical
+        .to_str()
+        .map(str::to_string)
+        .ok_or_else(|| HitoError::invalid_input("Failed to convert path to string").with_path(directory))
+}
+
+/// Get the recently browsed directories, most recent first.
+#[tauri::command]
+#[tracing::instrument(skip_all, err(Debug))]
+pub fn get_recent_directories(app: AppHandle) -> Result<Vec<String>, HitoError> {
+    Ok(load_app_data(app)?.recent_directories.unwrap_or_default())
+}
+
+/// Record `directory` as the most recently browsed directory.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory), err(Debug))]
+pub fn add_recent_directory(app: AppHandle, directory: String) -> Result<(), HitoError> {
+    let directory = check_directory(&directory)?;
+    update_app_data_sync(&app, |mut app_data| {
+        touch_recent(&mut app_data, &directory);
+        Ok(app_data)
+    })
+}
+
+/// Forget a recent directory and its view state, e.g. after it was deleted.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory), err(Debug))]
+pub fn remove_recent_directory(app: AppHandle, directory: String) -> Result<(), HitoError> {
+    update_app_data_sync(&app, |mut app_data| {
+        remove_recent(&mut app_data, &directory);
+        Ok(app_data)
+    })
+}
+
+/// Reopen a recent directory, e.g. the first one on startup.
+///
+/// Opened directories only last for one run, so the directory is added to them again; only
+/// directories in the recent list can be reopened this way.
+///
+/// # Returns
+///
+/// The directory's saved view state, if any.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory), err(Debug))]
+pub fn open_recent_directory(app: AppHandle, directory: String) -> Result<Option<ViewState>, HitoError> {
+    let mut view_state = None;
+    update_app_data_sync(&app, |mut app_data| {
+        view_state = reopen(&mut app_data, &directory)?;
+        Ok(app_data)
+    })?;
+    Ok(view_state)
+}
+
+/// Save how `directory` is viewed (sort, filters, last image, scroll position).
+///
+/// The directory also becomes the most recent directory; view state is only kept for recent directories.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory), err(Debug))]
+pub fn save_view_state(app: AppHandle, directory: String, view_state: ViewState) -> Result<(), HitoError> {
+    let directory = check_directory(&directory)?;
+    view_state.validate()?;
+    update_app_data_sync(&app, |mut app_data| {
+        touch_recent(&mut app_data, &directory);
+        app_data.view_states.get_or_insert_with(Default::default).insert(directory, view_state);
+        Ok(app_data)
+    })
+}
+
+/// Get the saved view state of `directory`, if any.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(directory = %directory), err(Debug))]
+pub fn get_view_state(app: AppHandle, directory: String) -> Result<Option<ViewState>, HitoError> {
+    let directory = check_directory(&directory)?;
+    Ok(load_app_data(app)?.view_states.and_then(|mut view_states| view_states.remove(&directory)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::scope::granted_temp_dir;
+    use tempfile::TempDir;
+
+    fn recent(app_data: &AppData) -> Vec<&str> {
+        app_data.recent_directories.as_deref().unwrap_or_default().iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn test_recent_directories_are_most_recent_first() {
+        let mut app_data = AppData::default();
+        for directory in ["/a", "/b", "/a", "/c"] {
+            touch_recent(&mut app_data, directory);
+        }
+        assert_eq!(recent(&app_data), vec!["/c", "/a", "/b"]);
+
+        // View state is dropped with the directory
+        app_data.view_states.get_or_insert_with(Default::default).insert("/b".to_string(), ViewState::default());
+        for n in 0..MAX_RECENT_DIRECTORIES {
+            touch_recent(&mut app_data, &format!("/d{}", n));
+        }
+        assert_eq!(recent(&app_data).len(), MAX_RECENT_DIRECTORIES);
+        assert!(!recent(&app_data).contains(&"/b"));
+        assert!(app_data.view_states.as_ref().unwrap().is_empty());
+
+        app_data.view_states.get_or_insert_with(Default::default).insert("/d0".to_string(), ViewState::default());
+        remove_recent(&mut app_data, "/d0");
+        assert!(!recent(&app_data).contains(&"/d0"));
+        assert!(app_data.view_states.as_ref().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reopen_grants_recent_directories_only() {
+        let remembered = TempDir::new().unwrap();
+        let unknown = TempDir::new().unwrap();
+        let directory = remembered.path().to_str().unwrap();
+        let mut app_data = AppData::default();
+        touch_recent(&mut app_data, directory);
+        touch_recent(&mut app_data, "/other");
+        let view_state = ViewState { last_image: Some("a.png".to_string()), ..Default::default() };
+        app_data.view_states.get_or_insert_with(Default::default).insert(directory.to_string(), view_state);
+
+        let error = reopen(&mut app_data, unknown.path().to_str().unwrap()).err().unwrap();
+        assert_eq!(error.code, ErrorCode::NotFound);
+        assert!(scope::check(unknown.path()).is_err());
+
+        let restored = reopen(&mut app_data, directory).unwrap().unwrap();
+        assert_eq!(restored.last_image.as_deref(), Some("a.png"));
+        assert!(scope::check(remembered.path()).is_ok());
+        assert_eq!(recent(&app_data)[0], directory);
+    }
+
+    #[test]
+    fn test_view_state_validation() {
+        let temp_dir = granted_temp_dir();
+        let canonical = temp_dir.path().canonicalize().unwrap().to_str().unwrap().to_string();
+        assert_eq!(check_directory(temp_dir.path().to_str().unwrap()).unwrap(), canonical);
+        let with_slash = format!("{}/", temp_dir.path().to_str().unwrap());
+        assert_eq!(check_directory(&with_slash).unwrap(), canonical);
+        assert_eq!(check_directory(temp_dir.path().join("missing").to_str().unwrap()).unwrap_err().code, ErrorCode::NotFound);
+
+        let valid = ViewState {
+            sort_option: Some("attribute:rating".to_string()),
+            sort_direction: Some("descending".to_string()),
+            ..Default::default()
+        };
+        assert!(valid.validate().is_ok());
+        let bad_option = ViewState { sort_option: Some("colour".to_string()), ..Default::default() };
+        assert_eq!(bad_option.validate().unwrap_err().field.as_deref(), Some("sort_option"));
+        let bad_direction = ViewState { sort_direction: Some("up".to_string()), ..Default::default() };
+        assert_eq!(bad_direction.validate().unwrap_err().field.as_deref(), Some("sort_direction"));
+
+        // Filters round-trip through app-config.json
+        let json = r#"{"sort_option":"name","filters":{"category_id":"cat","name_pattern":"IMG"},"scroll_position":120.5}"#;
+        let view_state: ViewState = serde_json::from_str(json).unwrap();
+        assert_eq!(view_state.filters.as_ref().unwrap().name_pattern.as_deref(), Some("IMG"));
+        assert_eq!(view_state.scroll_position, Some(120.5));
+    }
+}
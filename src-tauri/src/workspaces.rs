@@ -0,0 +1,388 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::{ErrorCode, HitoError};
+use crate::label_search::find_data_files;
+use crate::requests::{run_blocking, CancelToken, Request};
+use crate::{create_hito_file, get_app_data_path, load_app_data, read_hito_file, scope, update_app_data_sync, AppData, HitoFile};
+
+const WORKSPACES_DIR_NAME: &str = "workspaces";
+
+/// A named set of root directories that share one label store.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Workspace {
+    name: String,
+    roots: Vec<String>, // Canonical directories, none inside another
+    pub(crate) data_file_path: String, // Label store with the categories, hotkeys and labels of every root
+    created_at: String, // ISO 8601 datetime string
+}
+
+/// Directory in the app data directory that holds the workspace label stores.
+pub(crate) fn store_directory(app: &AppHandle) -> Result<PathBuf, HitoError> {
+    let store_dir = get_app_data_path(app)?.with_file_name(WORKSPACES_DIR_NAME);
+    fs::create_dir_all(&store_dir)
+        .map_err(|e| HitoError::io("Failed to create workspaces directory", &store_dir, e))?;
+    Ok(store_dir)
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+fn overlaps(a: &Path, b: &Path) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
+
+/// An unused label store file name derived from the workspace name, e.g. `my-project.hito.json`.
+fn store_file_name(store_dir: &Path, name: &str) -> String {
+    let slug = name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug = if slug.is_empty() { "workspace".to_string() } else { slug };
+    (1..)
+        .map(|n| if n == 1 { format!("{}.hito.json", slug) } else { format!("{}-{}.hito.json", slug, n) })
+        .find(|file_name| !store_dir.join(file_name).exists())
+        .expect("unbounded range always yields an unused name")
+}
+
+/// Append the items whose ID is not in `into` yet.
+fn extend_unique<T>(into: &mut Vec<T>, items: Option<Vec<T>>, id: impl Fn(&T) -> &str) {
+    for item in items.unwrap_or_default() {
+        if !into.iter().any(|existing| id(existing) == id(&item)) {
+            into.push(item);
+        }
+    }
+}
+
+/// Merge what the default data files under the roots already hold into one label store.
+///
+/// Every directory under a root switches to the store, so the `.hito.json` files of
+/// subdirectories are merged too, not only those of the roots. Image paths are absolute and the
+/// roots do not overlap, so labels, notes and attribute values are simply concatenated. Category,
+/// hotkey and attribute definitions are merged by ID (the first root wins, and within a root the
+/// first file by path); without any saved categories or hotkeys, the global ones from app data
+/// are used.
+fn consolidate(roots: &[PathBuf], app_data: &AppData, cancel: &CancelToken) -> Result<HitoFile, HitoError> {
+    let mut store = HitoFile::default();
+    let (mut categories, mut hotkeys, mut attributes) = (Vec::new(), Vec::new(), Vec::new());
+    let (mut image_notes, mut image_attributes) = (Vec::new(), Vec::new());
+    let mut data_files = Vec::new();
+    for root in roots {
+        let mut found = Vec::new();
+        find_data_files(root, &mut found, cancel)?;
+        found.sort();
+        data_files.extend(found);
+    }
+    for data_file in &data_files {
+        cancel.check()?;
+        let data = read_hito_file(data_file)?;
+        store.image_categories.extend(data.image_categories);
+        image_notes.extend(data.image_notes.unwrap_or_default());
+        image_attributes.extend(data.image_attributes.unwrap_or_default());
+        extend_unique(&mut categories, data.categories, |c| &c.id);
+        extend_unique(&mut hotkeys, data.hotkeys, |h| &h.id);
+        extend_unique(&mut attributes, data.attributes, |a| &a.id);
+    }
+
+    store.categories = Some(if categories.is_empty() { app_data.categories.clone() } else { categories });
+    store.hotkeys = Some(if hotkeys.is_empty() { app_data.hotkeys.clone() } else { hotkeys });
+    store.attributes = (!attributes.is_empty()).then_some(attributes);
+    store.image_notes = (!image_notes.is_empty()).then_some(image_notes);
+    store.image_attributes = (!image_attributes.is_empty()).then_some(image_attributes);
+    Ok(store)
+}
+
+/// Fail with `conflict` if `name` is taken or a root overlaps the roots of one of `workspaces`.
+fn check_conflicts(workspaces: &[Workspace], name: &str, roots: &[PathBuf]) -> Result<(), HitoError> {
+    if workspaces.iter().any(|w| same_name(&w.name, name)) {
+        return Err(HitoError::new(ErrorCode::Conflict, format!("A workspace named {} already exists", name)));
+    }
+    // A directory can only use one label store
+    for other in workspaces {
+        if let Some(root) = roots.iter().find(|root| other.roots.iter().any(|r| overlaps(Path::new(r), root))) {
+            return Err(HitoError::new(
+                ErrorCode::Conflict,
+                format!("{} overlaps a root of workspace {}", root.display(), other.name),
+            )
+            .with_path(root));
+        }
+    }
+    Ok(())
+}
+
+/// Validate a new workspace against `app_data` and write its label store to `store_dir`.
+///
+/// The workspace is not added to `app_data` yet (see `register_workspace`), so this can run
+/// without holding the app data lock while the roots are walked.
+fn build_workspace(
+    app_data: &AppData,
+    store_dir: &Path,
+    name: &str,
+    roots: &[String],
+    cancel: &CancelToken,
+) -> Result<Workspace, HitoError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(HitoError::invalid_field("name", "Workspace name must not be empty"));
+    }
+
+    let mut canonical_roots: Vec<PathBuf> = Vec::new();
+    for root in roots.iter().map(|root| root.trim()).filter(|root| !root.is_empty()) {
+        let canonical = scope::check(root)?;
+        if !canonical.is_dir() {
+            return Err(HitoError::not_found(format!("Directory does not exist: {}", root)).with_path(root));
+        }
+        if canonical_roots.contains(&canonical) {
+            continue;
+        }
+        if canonical_roots.iter().any(|other| overlaps(other, &canonical)) {
+            return Err(HitoError::invalid_field("roots", format!("Root is inside another root: {}", root)));
+        }
+        canonical_roots.push(canonical);
+    }
+    if canonical_roots.is_empty() {
+        return Err(HitoError::invalid_field("roots", "A workspace needs at least one root directory"));
+    }
+    check_conflicts(app_data.workspaces.as_deref().unwrap_or_default(), name, &canonical_roots)?;
+
+    let store = consolidate(&canonical_roots, app_data, cancel)?;
+    let data_file_path = loop {
+        let path = store_dir.join(store_file_name(store_dir, name));
+        match create_hito_file(&path, &store) {
+            Ok(()) => break path,
+            // Taken by a workspace created at the same time
+            Err(e) if e.code == ErrorCode::Conflict => continue,
+            Err(e) => return Err(e),
+        }
+    };
+
+    Ok(Workspace {
+        name: name.to_string(),
+        roots: canonical_roots.iter().map(|root| root.to_string_lossy().into_owned()).collect(),
+        data_file_path: data_file_path.to_string_lossy().into_owned(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// Add a workspace made by `build_workspace` to `app_data`, checking again for workspaces added
+/// in the meantime.
+fn register_workspace(app_data: &mut AppData, workspace: Workspace) -> Result<(), HitoError> {
+    let roots: Vec<PathBuf> = workspace.roots.iter().map(PathBuf::from).collect();
+    check_conflicts(app_data.workspaces.as_deref().unwrap_or_default(), &workspace.name, &roots)?;
+    app_data.workspaces.get_or_insert_with(Vec::new).push(workspace);
+    Ok(())
+}
+
+/// The workspace with a root containing `directory`, if any.
+pub(crate) fn workspace_for_directory<'a>(app_data: &'a AppData, directory: &str) -> Option<&'a Workspace> {
+    let directory = fs::canonicalize(directory).unwrap_or_else(|_| PathBuf::from(directory));
+    app_data
+        .workspaces
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .find(|workspace| workspace.roots.iter().any(|root| directory.starts_with(root)))
+}
+
+/// Create a workspace over `roots`, which have to be opened directories.
+///
+/// The existing `.hito.json` files under the roots are merged into a new label store in the app
+/// data directory; the files themselves are left as they are. From then on, `get_data_file_path` returns
+/// the store for every directory under the roots that has no data file of its own registered.
+///
+/// Fails with `conflict` when the name is taken or a root overlaps another workspace's roots.
+///
+/// Runs on the worker pool, as it walks the roots; a `request_id` makes it cancellable until the
+/// store is written.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(name = %name, roots = roots.len()), err(Debug))]
+pub async fn create_workspace(
+    app: AppHandle,
+    name: String,
+    roots: Vec<String>,
+    request_id: Option<String>,
+) -> Result<Workspace, HitoError> {
+    let cancel = Request::start(request_id);
+    run_blocking(move || {
+        let store_dir = store_directory(&app)?;
+        // Walking the roots can take a while, so app data is only locked to add the result
+        let workspace = build_workspace(&load_app_data(app.clone())?, &store_dir, &name, &roots, cancel.token())?;
+        let saved = update_app_data_sync(&app, |mut app_data| {
+            register_workspace(&mut app_data, workspace.clone())?;
+            Ok(app_data)
+        });
+        if let Err(error) = saved {
+            // No workspace refers to the store
+            if let Err(e) = fs::remove_file(&workspace.data_file_path) {
+                tracing::warn!(error = ?e, path = %workspace.data_file_path, "Failed to remove unused workspace store");
+            }
+            return Err(error);
+        }
+        Ok(workspace)
+    })
+    .await
+}
+
+/// List all workspaces, in creation order.
+#[tauri::command]
+#[tracing::instrument(skip_all, err(Debug))]
+pub fn list_workspaces(app: AppHandle) -> Result<Vec<Workspace>, HitoError> {
+    Ok(load_app_data(app)?.workspaces.unwrap_or_default())
+}
+
+/// Open the workspace named `name`, adding its roots to the opened directories.
+///
+/// # Returns
+///
+/// The workspace; the frontend browses its roots as usual.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(name = %name), err(Debug))]
+pub fn open_workspace(app: AppHandle, name: String) -> Result<Workspace, HitoError> {
+    let workspace = load_app_data(app)?
+        .workspaces
+        .unwrap_or_default()
+        .into_iter()
+        .find(|workspace| same_name(&workspace.name, name.trim()))
+        .ok_or_else(|| HitoError::not_found(format!("Workspace does not exist: {}", name)))?;
+    for root in &workspace.roots {
+        scope::grant(Path::new(root))?;
+    }
+    Ok(workspace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scope::granted_temp_dir;
+    use crate::{save_hito_config, CategoryAssignment, CategoryData};
+
+    fn category(id: &str) -> CategoryData {
+        CategoryData {
+            id: id.to_string(),
+            name: id.to_uppercase(),
+            color: "#000000".to_string(),
+            mutually_exclusive_with: None,
+        }
+    }
+
+    fn label(directory: &Path, image: &str, category_id: &str) {
+        let assignment = CategoryAssignment {
+            category_id: category_id.to_string(),
+            assigned_at: "2024-01-01T00:00:00Z".to_string(),
+            ..Default::default()
+        };
+        save_hito_config(
+            directory.to_str().unwrap().to_string(),
+            vec![(directory.join(image).to_str().unwrap().to_string(), vec![assignment])],
+            None,
+            Some(vec![category(category_id)]),
+            None,
+            None,
+        )
+        .unwrap();
+    }
+
+    fn root(path: &Path) -> String {
+        path.to_str().unwrap().to_string()
+    }
+
+    fn add_workspace(app_data: &mut AppData, store_dir: &Path, name: &str, roots: &[String]) -> Result<Workspace, HitoError> {
+        let workspace = build_workspace(app_data, store_dir, name, roots, &CancelToken::default())?;
+        register_workspace(app_data, workspace.clone())?;
+        Ok(workspace)
+    }
+
+    #[test]
+    fn test_create_workspace_consolidates_roots() {
+        let temp_dir = granted_temp_dir();
+        let store_dir = temp_dir.path().join("store");
+        let (a, b) = (temp_dir.path().join("a"), temp_dir.path().join("b"));
+        for dir in [&store_dir, &a, &b] {
+            fs::create_dir(dir).unwrap();
+        }
+        fs::create_dir(a.join("sub")).unwrap();
+        label(&a, "1.png", "cat");
+        label(&a.join("sub"), "3.png", "bird");
+        label(&b, "2.png", "dog");
+        let mut app_data = AppData { categories: vec![category("global")], ..Default::default() };
+
+        let workspace = add_workspace(&mut app_data, &store_dir, " My Project ", &[root(&a), root(&b), root(&a)]).unwrap();
+
+        assert_eq!(workspace.name, "My Project");
+        assert_eq!(workspace.roots.len(), 2);
+        assert!(workspace.data_file_path.ends_with("my-project.hito.json"));
+        let store = read_hito_file(Path::new(&workspace.data_file_path)).unwrap();
+        // Subdirectory data files are merged too
+        assert_eq!(store.image_categories.len(), 3);
+        let ids: Vec<&str> = store.categories.as_deref().unwrap().iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["cat", "bird", "dog"]);
+
+        // Directories under a root resolve to the workspace
+        let found = workspace_for_directory(&app_data, a.join("sub").to_str().unwrap()).unwrap();
+        assert_eq!(found.name, "My Project");
+        assert!(workspace_for_directory(&app_data, store_dir.to_str().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_create_workspace_validation() {
+        let temp_dir = granted_temp_dir();
+        let (a, b) = (temp_dir.path().join("a"), temp_dir.path().join("b"));
+        fs::create_dir_all(a.join("nested")).unwrap();
+        fs::create_dir(&b).unwrap();
+        let mut app_data = AppData::default();
+        add_workspace(&mut app_data, temp_dir.path(), "First", &[root(&a)]).unwrap();
+
+        let error = add_workspace(&mut app_data, temp_dir.path(), "first", &[root(&b)]).err().unwrap();
+        assert_eq!(error.code, ErrorCode::Conflict);
+        let error = add_workspace(&mut app_data, temp_dir.path(), "Second", &[root(&a.join("nested"))]).err().unwrap();
+        assert_eq!(error.code, ErrorCode::Conflict);
+        let error = add_workspace(&mut app_data, temp_dir.path(), "Second", &[root(&b), root(&b.join(".."))]).err().unwrap();
+        assert_eq!(error.field.as_deref(), Some("roots"));
+        let error = add_workspace(&mut app_data, temp_dir.path(), "Second", &[]).err().unwrap();
+        assert_eq!(error.field.as_deref(), Some("roots"));
+        let error = add_workspace(&mut app_data, temp_dir.path(), "  ", &[root(&b)]).err().unwrap();
+        assert_eq!(error.field.as_deref(), Some("name"));
+
+        // Global categories seed a workspace without saved labels
+        app_data.categories = vec![category("global")];
+        let workspace = add_workspace(&mut app_data, temp_dir.path(), "Second", &[root(&b)]).unwrap();
+        let store = read_hito_file(Path::new(&workspace.data_file_path)).unwrap();
+        assert_eq!(store.categories.unwrap()[0].id, "global");
+        assert_eq!(app_data.workspaces.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_workspaces_built_at_the_same_time_cannot_overlap() {
+        let temp_dir = granted_temp_dir();
+        let (a, b) = (temp_dir.path().join("a"), temp_dir.path().join("b"));
+        fs::create_dir_all(a.join("nested")).unwrap();
+        fs::create_dir(&b).unwrap();
+        let mut app_data = AppData::default();
+
+        // All are built against the same app data, as two create_workspace calls would be
+        let first = build_workspace(&app_data, temp_dir.path(), "Same", &[root(&a)], &CancelToken::default()).unwrap();
+        let second = build_workspace(&app_data, temp_dir.path(), "same", &[root(&b)], &CancelToken::default()).unwrap();
+        let third = build_workspace(&app_data, temp_dir.path(), "Third", &[root(&a.join("nested"))], &CancelToken::default()).unwrap();
+        assert_ne!(first.data_file_path, second.data_file_path);
+
+        register_workspace(&mut app_data, first).unwrap();
+        assert_eq!(register_workspace(&mut app_data, second).err().unwrap().code, ErrorCode::Conflict);
+        assert_eq!(register_workspace(&mut app_data, third).err().unwrap().code, ErrorCode::Conflict);
+        assert_eq!(app_data.workspaces.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_store_file_name_is_unique() {
+        let temp_dir = granted_temp_dir();
+        assert_eq!(store_file_name(temp_dir.path(), "Cats & Dogs"), "cats-dogs.hito.json");
+        fs::write(temp_dir.path().join("cats-dogs.hito.json"), "{}").unwrap();
+        assert_eq!(store_file_name(temp_dir.path(), "cats dogs"), "cats-dogs-2.hito.json");
+        assert_eq!(store_file_name(temp_dir.path(), "!!"), "workspace.hito.json");
+    }
+}
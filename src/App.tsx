@@ -2,6 +2,7 @@ import React, { useEffect, useMemo } from "react";
 import { useAtomValue } from "jotai";
 import { setupDocumentDragHandlers, setupTauriDragEvents } from "./handlers/dragDrop";
 import { setupKeyboardHandlers } from "./handlers/keyboard";
+import { restoreLastDirectory, setupViewStatePersistence } from "./utils/viewState";
 import { allImagePathsAtom, allDirectoryPathsAtom } from "./state";
 import { loadAppData } from "./ui/categories";
 import { DropZone } from "./components/DropZone";
@@ -38,6 +39,10 @@ function App() {
     setupDocumentDragHandlers();
     setupKeyboardHandlers();
     
+    // Remember how each folder is viewed and reopen the last one where it was left
+    const cleanupViewState = setupViewStatePersistence();
+    void restoreLastDirectory();
+    
     // Listen to Tauri drag events to uncollapse path-input-container when dragging
     const handleTauriDragEnter = () => setIsDragOver(true);
     const handleTauriDragLeave = () => setIsDragOver(false);
@@ -63,6 +68,7 @@ function App() {
       window.removeEventListener(CUSTOM_DRAG_EVENTS.OVER, handleTauriDragEnter);
       window.removeEventListener(CUSTOM_DRAG_EVENTS.LEAVE, handleTauriDragLeave);
       window.removeEventListener(CUSTOM_DRAG_EVENTS.DROP, handleTauriDragLeave);
+      cleanupViewState();
       if (cleanupTauriDragEvents) {
         cleanupTauriDragEvents();
       }
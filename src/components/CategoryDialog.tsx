@@ -14,6 +14,7 @@ import {
 } from "../ui/categories";
 import { autoAssignHotkeyToCategory } from "../ui/hotkeys";
 import { showError as showErrorNotification } from "../ui/notification";
+import { getErrorMessage } from "../utils/errors";
 
 /**
  * Generates a UUID v4 string.
@@ -199,8 +200,7 @@ export function CategoryDialog() {
           setCategories(originalCategories);
 
           // Show error to user and keep dialog open so they can retry or cancel
-          const saveErrorMessage =
-            error instanceof Error ? error.message : String(error);
+          const saveErrorMessage = getErrorMessage(error);
           setErrorMessage(`Failed to save category: ${saveErrorMessage}`);
           setShowError(true);
         }
@@ -265,8 +265,7 @@ export function CategoryDialog() {
         setCategories(originalCategories);
 
         // Show error to user and keep dialog open so they can retry or cancel
-        const saveErrorMessage =
-          error instanceof Error ? error.message : String(error);
+        const saveErrorMessage = getErrorMessage(error);
         setErrorMessage(`Failed to save category: ${saveErrorMessage}`);
         setShowError(true);
       }
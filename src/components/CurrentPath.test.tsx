@@ -0,0 +1,56 @@
+import { describe, it, expect, beforeEach, afterEach, vi } from "vitest";
+import { render, cleanup, screen, fireEvent, waitFor } from "@testing-library/react";
+import { CurrentPath } from "./CurrentPath";
+import { store } from "../utils/jotaiStore";
+import { currentDirectoryAtom, resetStateAtom } from "../state";
+
+vi.mock("../handlers/dragDrop", () => ({
+  handleFolder: vi.fn(),
+  selectFolder: vi.fn(),
+}));
+
+vi.mock("../utils/tauri", () => ({
+  invokeTauri: vi.fn(),
+  isTauriInvokeAvailable: vi.fn().mockReturnValue(true),
+}));
+
+describe("CurrentPath", () => {
+  beforeEach(async () => {
+    vi.clearAllMocks();
+    store.set(resetStateAtom);
+    const { invokeTauri } = await import("../utils/tauri");
+    vi.mocked(invokeTauri).mockResolvedValue(["/photos/trip"]);
+  });
+
+  afterEach(() => {
+    cleanup();
+  });
+
+  it("should browse crumbs inside the opened directories directly", async () => {
+    const { handleFolder, selectFolder } = await import("../handlers/dragDrop");
+    store.set(currentDirectoryAtom, "/photos/trip/day1");
+    render(<CurrentPath />);
+
+    const trip = screen.getByText("trip");
+    await waitFor(() => expect(trip.classList.contains("breadcrumb-link-unopened")).toBe(false));
+    fireEvent.click(trip);
+
+    expect(handleFolder).toHaveBeenCalledWith("/photos/trip");
+    expect(selectFolder).not.toHaveBeenCalled();
+  });
+
+  it("should pick crumbs above the opened directories through the folder dialog", async () => {
+    const { handleFolder, selectFolder } = await import("../handlers/dragDrop");
+    const { invokeTauri } = await import("../utils/tauri");
+    store.set(currentDirectoryAtom, "/photos/trip/day1");
+    render(<CurrentPath />);
+
+    await waitFor(() => expect(invokeTauri).toHaveBeenCalledWith("get_opened_directories"));
+    const photos = screen.getByText("photos");
+    expect(photos.classList.contains("breadcrumb-link-unopened")).toBe(true);
+    fireEvent.click(photos);
+
+    expect(selectFolder).toHaveBeenCalledWith("/photos");
+    expect(handleFolder).not.toHaveBeenCalled();
+  });
+});
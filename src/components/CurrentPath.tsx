@@ -1,8 +1,9 @@
-import React, { useMemo } from "react";
+import React, { useEffect, useMemo, useState } from "react";
 import { useAtomValue } from "jotai";
 import { currentDirectoryAtom } from "../state";
-import { normalizePath } from "../utils/state";
-import { handleFolder } from "../handlers/dragDrop";
+import { isWithinDirectories, normalizePath } from "../utils/state";
+import { handleFolder, selectFolder } from "../handlers/dragDrop";
+import { invokeTauri, isTauriInvokeAvailable } from "../utils/tauri";
 
 interface CurrentPathProps {
   id?: string;
@@ -11,6 +12,27 @@ interface CurrentPathProps {
 export function CurrentPath({ id = "current-path" }: CurrentPathProps = {}) {
   const currentDirectory = useAtomValue(currentDirectoryAtom);
   const isVisible = useMemo(() => currentDirectory.length > 0, [currentDirectory]);
+  // Folders the file commands may read; crumbs above them go through the folder dialog
+  const [openedDirectories, setOpenedDirectories] = useState<string[]>([]);
+
+  useEffect(() => {
+    if (!currentDirectory || !isTauriInvokeAvailable()) {
+      return;
+    }
+    let cancelled = false;
+    invokeTauri<string[]>("get_opened_directories")
+      .then((directories) => {
+        if (!cancelled) {
+          setOpenedDirectories(directories);
+        }
+      })
+      .catch((error) => {
+        console.error("[CurrentPath] Failed to get opened directories:", error);
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [currentDirectory]);
 
   if (!isVisible || !currentDirectory) {
     return null;
@@ -22,9 +44,14 @@ export function CurrentPath({ id = "current-path" }: CurrentPathProps = {}) {
   const isWindowsDrive = /^[A-Za-z]:\//.test(normalized);
   const startIndex = isWindowsDrive ? 1 : 0;
 
-  const handleBreadcrumbClick = (e: React.MouseEvent, path: string) => {
+  const handleBreadcrumbClick = (e: React.MouseEvent, path: string, isOpened: boolean) => {
     e.preventDefault();
-    handleFolder(path);
+    if (isOpened) {
+      handleFolder(path);
+    } else {
+      // Folders above the opened ones have to be picked before they can be listed
+      selectFolder(path);
+    }
   };
 
   // Build breadcrumb items with paths
@@ -44,6 +71,7 @@ export function CurrentPath({ id = "current-path" }: CurrentPathProps = {}) {
       segment,
       path: pathUpToSegment,
       isLast,
+      isOpened: isWithinDirectories(pathUpToSegment, openedDirectories),
     };
   });
 
@@ -59,8 +87,9 @@ export function CurrentPath({ id = "current-path" }: CurrentPathProps = {}) {
               ) : (
                 <a
                   href="#"
-                  className="breadcrumb-link"
-                  onClick={(e) => handleBreadcrumbClick(e, item.path)}
+                  className={`breadcrumb-link ${item.isOpened ? "" : "breadcrumb-link-unopened"}`}
+                  title={item.isOpened ? undefined : "Choose this folder to open it"}
+                  onClick={(e) => handleBreadcrumbClick(e, item.path, item.isOpened)}
                 >
                   {item.segment}
                 </a>
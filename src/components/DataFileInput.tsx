@@ -2,8 +2,10 @@ import React, { useState } from "react";
 import { useAtomValue, useSetAtom } from "jotai";
 import { dataFilePathAtom, currentDirectoryAtom } from "../state";
 import { invokeTauri, isTauriInvokeAvailable } from "../utils/tauri";
+import { getErrorMessage } from "../utils/errors";
 import { showNotification, showError } from "../ui/notification";
 import { loadHitoConfig } from "../ui/categories";
+import { openDirectorySession } from "../utils/directorySession";
 
 export function DataFileInput() {
   const value = useAtomValue(dataFilePathAtom);
@@ -46,7 +48,7 @@ export function DataFileInput() {
       showNotification("Data file path saved successfully.");
     } catch (error) {
       console.error("Failed to save data file path:", error);
-      showError(`Failed to save data file path: ${error}`);
+      showError(`Failed to save data file path: ${getErrorMessage(error)}`);
       setIsSaving(false);
       return;
     }
@@ -55,9 +57,10 @@ export function DataFileInput() {
     // loadHitoConfig will clear assignments if the file doesn't exist
     try {
       await loadHitoConfig();
+      await openDirectorySession(currentDirectory, trimmedValue);
     } catch (error) {
       console.error("Failed to reload configuration:", error);
-      showError(`Failed to reload configuration: ${error}`);
+      showError(`Failed to reload configuration: ${getErrorMessage(error)}`);
     } finally {
       setIsSaving(false);
     }
@@ -4,6 +4,7 @@ import { currentDirectoryAtom, isLoadingAtom } from "../state";
 import { handleFileDrop, selectFolder } from "../handlers/dragDrop";
 import { showNotification } from "../ui/notification";
 import { CUSTOM_DRAG_EVENTS } from "../constants";
+import { getErrorMessage } from "../utils/errors";
 
 export function DropZone() {
   const currentDirectory = useAtomValue(currentDirectoryAtom);
@@ -109,7 +110,7 @@ export function DropZone() {
         await selectFolder();
       } catch (error) {
         console.error('Error opening file picker:', error);
-        showNotification(`Failed to open file picker: ${error}`);
+        showNotification(`Failed to open file picker: ${getErrorMessage(error)}`);
       }
     }
   };
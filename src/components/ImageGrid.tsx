@@ -13,13 +13,15 @@ import {
   cachedImageCategoriesForRefilterAtom,
   isLoadingAtom,
   sortedImagesAtom,
+  directorySessionAtom,
+  labelsSavedCountAtom,
 } from "../state";
 import { store } from "../utils/jotaiStore";
 import { BATCH_SIZE } from "../constants";
 import { loadImageBatch } from "../core/browse";
 import { ImageGridItem } from "./ImageGridItem";
 import { DirectoryItem } from "./DirectoryItem";
-import { invokeTauri, isTauriInvokeAvailable } from "../utils/tauri";
+import { isTauriInvokeAvailable } from "../utils/tauri";
 import { getFilteredAndSortedImages, getSortedDirectoriesAndImages } from "../utils/filteredImages";
 import type { ImagePath, DirectoryPath } from "../types";
 
@@ -41,6 +43,8 @@ export function ImageGrid() {
   const imageCategories = useAtomValue(imageCategoriesAtom);
   const suppressCategoryRefilter = useAtomValue(suppressCategoryRefilterAtom);
   const cachedImageCategoriesForRefilter = useAtomValue(cachedImageCategoriesForRefilterAtom);
+  const directorySession = useAtomValue(directorySessionAtom);
+  const labelsSavedCount = useAtomValue(labelsSavedCountAtom);
   const isLoading = useAtomValue(isLoadingAtom);
   const isLoadingBatch = useAtomValue(isLoadingBatchAtom);
   const setCurrentIndex = useSetAtom(currentIndexAtom);
@@ -176,44 +180,8 @@ export function ImageGrid() {
       }
 
       try {
-        // Use cached snapshot if suppressCategoryRefilter is active (defer refiltering)
-        const imageCategoriesForSorting = suppressCategoryRefilter && cachedImageCategoriesForRefilter
-          ? cachedImageCategoriesForRefilter
-          : imageCategories;
-        
-        // Convert imageCategories Map to array format for Rust
-        const imageCategoriesArray = Array.from(imageCategoriesForSorting.entries());
-
-        // Convert filterOptions to Rust format (camelCase to snake_case)
-        const filters = filterOptions;
-        const hasCategoryFilter = filters.categoryId && filters.categoryId !== "";
-        const hasNameFilter = filters.namePattern && filters.namePattern !== "";
-        const hasSizeFilter = filters.sizeValue && filters.sizeValue !== "";
-        const rustFilterOptions = (hasCategoryFilter || hasNameFilter || hasSizeFilter) ? {
-          category_id: hasCategoryFilter ? filters.categoryId : null,
-          name_pattern: hasNameFilter ? filters.namePattern : null,
-          name_operator: hasNameFilter ? filters.nameOperator : null,
-          size_operator: hasSizeFilter ? filters.sizeOperator : null,
-          size_value: hasSizeFilter ? filters.sizeValue : null,
-          size_value2: hasSizeFilter && filters.sizeOperator === "between" ? filters.sizeValue2 : null,
-        } : null;
-
-        // Call Rust sorting and filtering
-        const sorted = await invokeTauri<ImagePath[]>("sort_images", {
-          images,
-          sortOption: sortOption,
-          sortDirection: sortDirection,
-          imageCategories: imageCategoriesArray,
-          filterOptions: rustFilterOptions,
-        });
-
-        if (!cancelled) {
-          setSortedImages(sorted);
-          setSortedImagesAtom(sorted);
-        }
-      } catch (error) {
-        console.error("Failed to sort images in Rust:", error);
-        // Fallback to JavaScript utility on error
+        // Queries the folder's Rust session, or sends the images to sort_images (with the cached
+        // labels while refiltering is suppressed), falling back to JavaScript on error
         const sorted = await getFilteredAndSortedImages();
         if (!cancelled) {
           setSortedImages(sorted);
@@ -237,7 +205,7 @@ export function ImageGrid() {
         setIsLoading(false);
       }
     };
-  }, [allImagePaths, sortOption, sortDirection, sortFilterKey, imageCategories, suppressCategoryRefilter, cachedImageCategoriesForRefilter, setIsLoading, setSortedImagesAtom]);
+  }, [allImagePaths, sortOption, sortDirection, sortFilterKey, imageCategories, suppressCategoryRefilter, cachedImageCategoriesForRefilter, directorySession, labelsSavedCount, setIsLoading, setSortedImagesAtom]);
 
   // Filtering is now done in Rust, so sortedImages are already filtered
   const processedImages = React.useMemo(() => {
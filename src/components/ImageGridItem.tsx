@@ -4,6 +4,7 @@ import { loadImageData } from "../utils/images";
 import { openModal } from "../ui/modal";
 import { selectionModeAtom, selectedImagesAtom, toggleImageSelectionAtom, categoriesAtom, imageCategoriesAtom } from "../state";
 import { getContrastColor } from "../utils/colors";
+import { isCancelledError } from "../utils/errors";
 import type { Category } from "../types";
 
 interface ImageGridItemProps {
@@ -48,7 +49,8 @@ export function ImageGridItem({ imagePath }: ImageGridItemProps) {
           setIsLoading(false);
         }
       } catch (error) {
-        if (!cancelled) {
+        // A cancelled load belongs to a folder that is no longer shown
+        if (!cancelled && !isCancelledError(error)) {
           console.error(`Failed to load image: ${imagePath}`, error);
           setHasError(true);
           setIsLoading(false);
@@ -2,6 +2,7 @@ import React, { useEffect, useRef, useMemo } from "react";
 import { useAtomValue, useSetAtom } from "jotai";
 import { selectionModeAtom, selectedImagesAtom, toggleImageSelectionAtom, allImagePathsAtom, loadedImagesAtom, categoriesAtom, imageCategoriesAtom } from "../state";
 import { invokeTauri } from "../utils/tauri";
+import { getErrorMessage } from "../utils/errors";
 import { store, deleteFromAtomMap } from "../utils/jotaiStore";
 import { showNotification, showError } from "../ui/notification";
 import { open } from "../utils/dialog";
@@ -137,7 +138,7 @@ export function ImageGridSelection() {
         successCount++;
       } catch (error) {
         errorCount++;
-        const errorMessage = error instanceof Error ? error.message : String(error);
+        const errorMessage = getErrorMessage(error);
         errors.push(`${imagePath}: ${errorMessage}`);
       }
     }
@@ -184,7 +185,7 @@ export function ImageGridSelection() {
         return;
       }
     } catch (error) {
-      const errorMessage = error instanceof Error ? error.message : String(error);
+      const errorMessage = getErrorMessage(error);
       showError(`Failed to open folder picker: ${errorMessage}`);
       return;
     }
@@ -212,7 +213,7 @@ export function ImageGridSelection() {
         successCount++;
       } catch (error) {
         errorCount++;
-        const errorMessage = error instanceof Error ? error.message : String(error);
+        const errorMessage = getErrorMessage(error);
         errors.push(`${imagePath}: ${errorMessage}`);
       }
     }
@@ -256,7 +257,7 @@ export function ImageGridSelection() {
         return;
       }
     } catch (error) {
-      const errorMessage = error instanceof Error ? error.message : String(error);
+      const errorMessage = getErrorMessage(error);
       showError(`Failed to open folder picker: ${errorMessage}`);
       return;
     }
@@ -293,7 +294,7 @@ export function ImageGridSelection() {
         successCount++;
       } catch (error) {
         errorCount++;
-        const errorMessage = error instanceof Error ? error.message : String(error);
+        const errorMessage = getErrorMessage(error);
         errors.push(`${imagePath}: ${errorMessage}`);
       }
     }
@@ -361,7 +362,7 @@ export function ImageGridSelection() {
         successCount++;
       } catch (error) {
         errorCount++;
-        const errorMessage = error instanceof Error ? error.message : String(error);
+        const errorMessage = getErrorMessage(error);
         errors.push(`${imagePath}: ${errorMessage}`);
       }
     }
@@ -7,6 +7,7 @@ import { ensureImagePathsArray, getFilename } from "../utils/state";
 import { closeModal, showPreviousImage, showNextImage } from "../ui/modal";
 import { ModalCategories } from "./ModalCategories";
 import { ShortcutsOverlay } from "./ShortcutsOverlay";
+import { getErrorMessage, isCancelledError } from "../utils/errors";
 
 export function ImageModal() {
   const [isOpen, setIsOpen] = useState(false);
@@ -83,12 +84,12 @@ export function ImageModal() {
             try {
               dataUrl = await loadImageData(imagePath);
             } catch (error) {
-              // Only show error if this is still the latest request
-              if (currentLoadRequestId === loadRequestIdRef.current) {
+              // Only show error if this is still the latest request and was not cancelled
+              if (currentLoadRequestId === loadRequestIdRef.current && !isCancelledError(error)) {
                 // Clear any stale image state before showing error
                 setImageSrc("");
                 setCaption("");
-                showError(`Error loading image: ${error}`);
+                showError(`Error loading image: ${getErrorMessage(error)}`);
               }
               return;
             }
@@ -14,6 +14,8 @@ import { invokeTauri, isTauriInvokeAvailable } from "../utils/tauri";
 import { showError } from "../ui/error";
 import { showNotification } from "../ui/notification";
 import { loadAppData, loadHitoConfig } from "../ui/categories";
+import { openDirectorySession } from "../utils/directorySession";
+import { getFolderRequestId } from "../utils/requests";
 
 // Mock dependencies
 vi.mock("../utils/images", () => ({
@@ -47,6 +49,15 @@ vi.mock("../utils/tauri", () => ({
   isTauriInvokeAvailable: vi.fn().mockReturnValue(true),
 }));
 
+vi.mock("../utils/requests", () => ({
+  startFolderRequest: vi.fn().mockReturnValue("folder-1"),
+  getFolderRequestId: vi.fn().mockReturnValue("folder-1"),
+}));
+
+vi.mock("../utils/directorySession", () => ({
+  openDirectorySession: vi.fn().mockResolvedValue(undefined),
+}));
+
 describe("browse", () => {
   beforeEach(() => {
     // Reset state
@@ -241,9 +252,10 @@ describe("browse", () => {
       await browseImages("/test/path");
 
       expect(invokeTauri).toHaveBeenCalledWith("get_data_file_path", { directory: "/test/path" });
-      expect(invokeTauri).toHaveBeenCalledWith("list_images", { path: "/test/path" });
+      expect(invokeTauri).toHaveBeenCalledWith("list_images", { path: "/test/path", requestId: "folder-1" });
       // loadAppData is no longer called - categories/hotkeys are loaded via loadHitoConfig
       expect(loadHitoConfig).toHaveBeenCalled();
+      expect(openDirectorySession).toHaveBeenCalledWith("/test/path", "");
       expect(store.get(allDirectoryPathsAtom)).toEqual(contents.directories);
       expect(store.get(allImagePathsAtom)).toEqual(contents.images);
     });
@@ -292,6 +304,30 @@ describe("browse", () => {
       expect(store.get(isLoadingAtom)).toBe(false);
     });
 
+    it("should ignore a cancelled listing", async () => {
+      vi.mocked(invokeTauri).mockResolvedValueOnce(null); // get_data_file_path
+      vi.mocked(invokeTauri).mockRejectedValueOnce({ code: "cancelled", message: "Request was cancelled" }); // list_images
+
+      await browseImages("/test/path");
+
+      expect(showError).not.toHaveBeenCalled();
+    });
+
+    it("should not update state once another folder was opened", async () => {
+      setupBrowseMocks({
+        directories: [],
+        images: [{ path: "/test/image1.png" }],
+      });
+      // The listing returns after the user opened another folder
+      vi.mocked(getFolderRequestId).mockReturnValueOnce("folder-1").mockReturnValue("folder-2");
+
+      await browseImages("/test/path");
+      vi.mocked(getFolderRequestId).mockReturnValue("folder-1");
+
+      expect(loadHitoConfig).not.toHaveBeenCalled();
+      expect(store.get(allImagePathsAtom)).toEqual([]);
+    });
+
     it("should reset state before browsing", async () => {
       store.set(currentIndexAtom, 100);
       store.set(isLoadingBatchAtom, true);
@@ -20,6 +20,9 @@ import { showError, clearError } from "../ui/error";
 import { loadHitoConfig } from "../ui/categories";
 import { ensureImagePathsArray } from "../utils/state";
 import { invokeTauri, isTauriInvokeAvailable } from "../utils/tauri";
+import { getErrorMessage, isCancelledError } from "../utils/errors";
+import { getFolderRequestId, startFolderRequest } from "../utils/requests";
+import { openDirectorySession } from "../utils/directorySession";
 
 /**
  * Manages the batch loading state flag to prevent concurrent batch loading operations.
@@ -60,6 +63,10 @@ export async function loadImageBatch(startIndex: number): Promise<void> {
 export async function browseImages(path: string): Promise<void> {
   clearError();
   store.set(isLoadingAtom, true);
+  // Stop the listing and image loads of the folder we are leaving
+  const requestId = startFolderRequest();
+  // Another folder opened while this one was loading owns the state from then on
+  const isStale = () => getFolderRequestId() !== requestId;
   
   // Reset state
   store.set(currentIndexAtom, 0);
@@ -84,13 +91,23 @@ export async function browseImages(path: string): Promise<void> {
       console.warn("[browseImages] Failed to load saved data file path:", error);
     }
   }
+  if (isStale()) {
+    return;
+  }
   store.set(dataFilePathAtom, savedDataFilePath);
   
   try {
     if (!isTauriInvokeAvailable()) {
       throw new Error("Tauri invoke API not available");
     }
-    const contents = await invokeTauri<DirectoryContents>("list_images", { path });
+    // A task batch data file restricts the listing to its images
+    const contents = await invokeTauri<DirectoryContents>(
+      "list_images",
+      savedDataFilePath ? { path, dataFilePath: savedDataFilePath, requestId } : { path, requestId },
+    );
+    if (isStale()) {
+      return;
+    }
     
     // Store directories and images - ensure they are arrays
     const directories = Array.isArray(contents.directories) ? contents.directories : [];
@@ -100,6 +117,11 @@ export async function browseImages(path: string): Promise<void> {
     // even if there are no images yet. This ensures per-directory configuration
     // is loaded and prevents config from previous directory from leaking.
     await loadHitoConfig();
+    // Filtering and sorting query the folder's session from now on
+    await openDirectorySession(path, savedDataFilePath);
+    if (isStale()) {
+      return;
+    }
     
     if (images.length === 0 && directories.length === 0) {
       showNotification("No images or directories found in this directory.");
@@ -125,9 +147,12 @@ export async function browseImages(path: string): Promise<void> {
     // Hide spinner after everything is loaded
     store.set(isLoadingAtom, false);
   } catch (error) {
+    if (isCancelledError(error) || isStale()) {
+      return;
+    }
     console.error('[browseImages] ERROR:', error);
     store.set(isLoadingAtom, false);
-    showError(`Error: ${error}`);
+    showError(`Error: ${getErrorMessage(error)}`);
   }
 }
 
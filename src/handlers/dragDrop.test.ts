@@ -235,7 +235,7 @@ describe('handleFileDrop', () => {
     await handleFileDrop(['/test/file.png']);
 
       expect(store.get(isLoadingAtom)).toBe(false);
-    expect(showError).toHaveBeenCalledWith('Error: Error: Parent not found. Please drop a folder or use the file picker.');
+    expect(showError).toHaveBeenCalledWith('Error: Parent not found. Please drop a folder or use the file picker.');
   });
 
   it('should handle Event<DragDropEvent> format', async () => {
@@ -1134,7 +1134,7 @@ describe('selectFolder', () => {
     await selectFolder();
 
       expect(store.get(isLoadingAtom)).toBe(false);
-    expect(showError).toHaveBeenCalledWith('Error selecting folder: Error: Dialog error');
+    expect(showError).toHaveBeenCalledWith('Error selecting folder: Dialog error');
   });
 
   it('should handle array with multiple folders (uses first)', async () => {
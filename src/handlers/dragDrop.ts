@@ -7,6 +7,7 @@ import { showError, clearError } from "../ui/error";
 import { browseImages } from "../core/browse";
 import { open } from "../utils/dialog";
 import { invokeTauri, isTauriInvokeAvailable } from "../utils/tauri";
+import { getErrorMessage } from "../utils/errors";
 
 /**
  * Normalize various drag-and-drop event shapes into a list of file paths.
@@ -81,7 +82,7 @@ export async function handleFileDrop(event: Event<DragDropEvent> | DragDropEvent
       handleFolder(parentPath);
     } catch (err) {
       store.set(isLoadingAtom, false);
-      showError(`Error: ${err}. Please drop a folder or use the file picker.`);
+      showError(`Error: ${getErrorMessage(err)}. Please drop a folder or use the file picker.`);
     }
   }
 }
@@ -156,7 +157,7 @@ export async function setupTauriDragEvents(): Promise<(() => void) | undefined>
                 .catch((err) => {
                   console.error('[TauriDragEvent] handleFileDrop error:', err);
                   store.set(isLoadingAtom, false);
-                  showError(`Error: ${err}`);
+                  showError(`Error: ${getErrorMessage(err)}`);
                 });
             } else if (eventName === DRAG_EVENTS.ENTER) {
               // Notify React component via custom event
@@ -214,13 +215,16 @@ export async function setupTauriDragEvents(): Promise<(() => void) | undefined>
  * If the user selects a folder, calls `handleFolder` with the chosen path.
  * If the selection is cancelled or no valid path is returned, restores the drop
  * zone UI. On error, displays an error message and restores the drop zone UI.
+ *
+ * @param defaultPath - Optional folder the picker starts in, e.g. a folder that is not opened yet
  */
-export async function selectFolder(): Promise<void> {
+export async function selectFolder(defaultPath?: string): Promise<void> {
   try {
     const selected = await open({
       directory: true,
       multiple: false,
-      title: "Select a folder to browse images"
+      title: "Select a folder to browse images",
+      ...(defaultPath ? { defaultPath } : {}),
     });
     
     if (selected && typeof selected === 'string') {
@@ -232,7 +236,7 @@ export async function selectFolder(): Promise<void> {
     }
   } catch (error) {
     store.set(isLoadingAtom, false);
-    showError(`Error selecting folder: ${error}`);
+    showError(`Error selecting folder: ${getErrorMessage(error)}`);
   }
 }
 
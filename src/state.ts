@@ -15,6 +15,7 @@ export const categoriesAtom = atom<Category[]>([]);
 export const imageCategoriesAtom = atom<Map<string, CategoryAssignment[]>>(new Map<string, CategoryAssignment[]>()); // image path -> category assignments with datetime
 export const currentDirectoryAtom = atom<string>(""); // Current directory being viewed
 export const dataFilePathAtom = atom<string>(""); // Custom data file path (empty = default to currentDirectory/.hito.json)
+export const annotatorAtom = atom<string>(""); // Annotator name from app data (empty = single-annotator mode)
 export const resetCounterAtom = atom<number>(0); // Incremented on reset to force ImageGrid remount
 export const shortcutsOverlayVisibleAtom = atom<boolean>(false); // Whether the keyboard shortcuts overlay is visible
 export const categoryDialogVisibleAtom = atom<boolean>(false); // Whether the category dialog is visible
@@ -58,6 +59,8 @@ export const toggleImageSelectionAtom = atom<((path: string) => void) | undefine
 export const suppressCategoryRefilterAtom = atom<boolean>(false); // When true, don't trigger re-filtering on category changes (used during modal assignment)
 export const cachedImageCategoriesForRefilterAtom = atom<Map<string, CategoryAssignment[]> | null>(null); // Cached snapshot of imageCategories when suppressCategoryRefilter is set
 export const sortedImagesAtom = atom<ImagePath[]>([]); // Sorted and filtered images (updated by ImageGrid, used by ImageModal for consistent indexing)
+export const directorySessionAtom = atom<string>(""); // Directory with an open Rust session, queried instead of sending the image list (empty = none)
+export const labelsSavedCountAtom = atom<number>(0); // Incremented after each data file save, so session queries rerun on the saved labels
 
 // Create a write-only atom that resets all state to initial values
 export const resetStateAtom = atom(null, (get, set) => {
@@ -74,6 +77,7 @@ export const resetStateAtom = atom(null, (get, set) => {
   set(imageCategoriesAtom, new Map<string, CategoryAssignment[]>());
   set(currentDirectoryAtom, "");
   set(dataFilePathAtom, "");
+  set(annotatorAtom, "");
   set(resetCounterAtom, (prev) => prev + 1); // Increment to force remounts
   set(shortcutsOverlayVisibleAtom, false);
   set(categoryDialogVisibleAtom, false);
@@ -92,6 +96,8 @@ export const resetStateAtom = atom(null, (get, set) => {
   set(suppressCategoryRefilterAtom, false);
   set(cachedImageCategoriesForRefilterAtom, null);
   set(sortedImagesAtom, []);
+  set(directorySessionAtom, "");
+  set(labelsSavedCountAtom, 0);
 });
 
 // DOM Elements
@@ -6,6 +6,18 @@ export interface ImagePath {
   path: string;
   size?: number; // File size in bytes
   created_at?: string; // ISO 8601 datetime string
+  modified_at?: string; // ISO 8601 datetime string
+  captured_at?: string; // EXIF capture time, only set when filtered on
+}
+
+// Error returned by Tauri commands
+export interface HitoError {
+  code: "not_found" | "permission_denied" | "conflict" | "invalid_input" | "invalid_data" | "io" | "database" | "cancelled" | "internal";
+  message: string;
+  path?: string | null; // File or directory the error is about
+  io_kind?: string | null; // std::io::ErrorKind of the underlying error
+  field?: string | null; // Argument or filter field an invalid_input error is about
+  position?: number | null; // Character offset in the field's value
 }
 
 export interface DirectoryPath {
@@ -44,6 +56,7 @@ export interface Category {
 export interface CategoryAssignment {
   category_id: string;
   assigned_at: string; // ISO 8601 datetime string
+  annotator?: string | null; // Who made the assignment (stamped by the backend)
 }
 
 // Type augmentation for window.__TAURI__
@@ -3,6 +3,7 @@ import { store } from "../utils/jotaiStore";
 import {
   currentDirectoryAtom,
   dataFilePathAtom,
+  annotatorAtom,
   categoriesAtom,
   imageCategoriesAtom,
   hotkeysAtom,
@@ -374,6 +375,28 @@ describe("categories config file location", () => {
       });
     });
 
+    it("should load and save as the annotator from app data", async () => {
+      store.set(currentDirectoryAtom, "/test/dir");
+      store.set(annotatorAtom, "alice");
+      mockInvoke.mockResolvedValue({ image_categories: [] });
+
+      const { loadHitoConfig, saveHitoConfig } = await import("./categories");
+      await loadHitoConfig();
+      await saveHitoConfig();
+
+      expect(mockInvoke).toHaveBeenCalledWith("load_hito_config", {
+        directory: "/test/dir",
+        filename: undefined,
+        annotator: "alice",
+      });
+      expect(mockInvoke).toHaveBeenCalledWith("save_hito_config", {
+        directory: "/test/dir",
+        imageCategories: [],
+        filename: undefined,
+        annotator: "alice",
+      });
+    });
+
     it("should handle errors gracefully", async () => {
       store.set(currentDirectoryAtom, "/test/dir");
       const consoleSpy = vi.spyOn(console, "error").mockImplementation(() => {});
@@ -2961,6 +2984,19 @@ describe("categories UI and management", () => {
       expect(mockInvoke).toHaveBeenCalledWith("save_hito_config", expect.any(Object));
     });
 
+    it("should load the annotator from app data", async () => {
+      mockInvoke.mockResolvedValue({
+        categories: [],
+        hotkeys: [{ id: "h1", key: "J", modifiers: [], action: "next_image" }],
+        annotator: "alice",
+      });
+
+      const { loadAppData } = await import("./categories");
+      await loadAppData();
+
+      expect(store.get(annotatorAtom)).toBe("alice");
+    });
+
     it("should handle error when saving default hotkeys fails", async () => {
       const consoleErrorSpy = vi.spyOn(console, "error").mockImplementation(() => {});
       const consoleLogSpy = vi.spyOn(console, "log").mockImplementation(() => {});
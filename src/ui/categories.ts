@@ -4,6 +4,8 @@ import {
   imageCategoriesAtom,
   hotkeysAtom,
   dataFilePathAtom,
+  annotatorAtom,
+  labelsSavedCountAtom,
   currentDirectoryAtom,
   allImagePathsAtom,
   filterOptionsAtom,
@@ -16,12 +18,14 @@ import {
 import type { Category, HotkeyConfig, CategoryAssignment } from "../types";
 import { confirm } from "../utils/dialog";
 import { invokeTauri, isTauriInvokeAvailable } from "../utils/tauri";
+import { getErrorMessage } from "../utils/errors";
 import { normalizePath } from "../utils/state";
 
 interface HitoFile {
   image_categories?: Array<[string, CategoryAssignment[]]>;
   categories?: Category[];
   hotkeys?: HotkeyConfig[];
+  annotator?: string | null;
 }
 
 interface AppData {
@@ -127,6 +131,8 @@ export async function loadAppData(abortSignal?: AbortSignal): Promise<void> {
       store.set(categoriesAtom, data.categories);
     }
 
+    store.set(annotatorAtom, data.annotator || "");
+
     if (data.hotkeys && data.hotkeys.length > 0) {
       // Ensure hotkeys have all required fields
       store.set(hotkeysAtom, data.hotkeys.map((h) => ({
@@ -212,12 +218,19 @@ export async function loadHitoConfig(): Promise<void> {
     }
 
     const dataFileName = getDataFileName();
-    console.log("[loadHitoConfig] Loading data file:", { directory: dataDir, filename: dataFileName });
+    const annotator = store.get(annotatorAtom);
+    console.log("[loadHitoConfig] Loading data file:", { directory: dataDir, filename: dataFileName, annotator });
     
-    const data = await invokeTauri<HitoFile>("load_hito_config", {
+    // With an annotator set, only their own assignments are loaded, so saving them back
+    // leaves the other annotators' assignments untouched
+    const args: { directory: string; filename?: string; annotator?: string } = {
       directory: dataDir,
       filename: dataFileName,
-    });
+    };
+    if (annotator) {
+      args.annotator = annotator;
+    }
+    const data = await invokeTauri<HitoFile>("load_hito_config", args);
     
     console.log("[loadHitoConfig] Loaded data file:", {
       imageCategoriesCount: data.image_categories?.length || 0,
@@ -260,9 +273,10 @@ export async function loadHitoConfig(): Promise<void> {
     }
   } catch (error) {
     // Check if this is a file-not-found error
-    const errorMessage = error instanceof Error ? error.message : String(error);
+    const errorMessage = getErrorMessage(error);
     const errorCode = (error as { code?: string })?.code;
     const isFileNotFound = 
+      errorCode === 'not_found' ||
       errorCode === 'ENOENT' ||
       errorMessage.toLowerCase().includes('no such file') ||
       errorMessage.toLowerCase().includes('not found');
@@ -318,6 +332,7 @@ export async function saveHitoConfig(): Promise<void> {
       filename?: string;
       categories?: Category[];
       hotkeys?: HotkeyConfig[];
+      annotator?: string;
     } = {
       directory: dataDir,
       imageCategories: imageCategoriesArray,
@@ -335,7 +350,13 @@ export async function saveHitoConfig(): Promise<void> {
       payload.hotkeys = hotkeys;
     }
 
+    const annotator = store.get(annotatorAtom);
+    if (annotator) {
+      payload.annotator = annotator;
+    }
+
     await invokeTauri("save_hito_config", payload);
+    store.set(labelsSavedCountAtom, (count) => count + 1);
 
     console.log("[saveHitoConfig] Data file saved successfully");
   } catch (error) {
@@ -16,6 +16,8 @@ import {
   suppressCategoryRefilterAtom,
   cachedImageCategoriesForRefilterAtom,
   sortedImagesAtom,
+  dataFilePathAtom,
+  annotatorAtom,
 } from "../state";
 import {
   openModal,
@@ -335,6 +337,23 @@ describe("modal", () => {
       expect(showNotification).toHaveBeenCalled();
     });
 
+    it("should pass the data file and annotator for the event log", async () => {
+      const tauri = await import("../utils/tauri");
+      store.set(sortedImagesAtom, [{ path: "/test/image1.png" }, { path: "/test/image2.png" }]);
+      store.set(currentModalImagePathAtom, "/test/image2.png");
+      store.set(dataFilePathAtom, "/labels/batch.json");
+      store.set(annotatorAtom, "alice");
+      vi.spyOn(tauri, "invokeTauri").mockResolvedValueOnce(undefined);
+
+      await deleteCurrentImage();
+
+      expect(tauri.invokeTauri).toHaveBeenCalledWith("delete_image", {
+        imagePath: "/test/image2.png",
+        dataFilePath: "/labels/batch.json",
+        annotator: "alice",
+      });
+    });
+
     it("should close modal if only image", async () => {
       const tauri = await import("../utils/tauri");
       const { showNotification } = await import("./notification");
@@ -380,7 +399,7 @@ describe("modal", () => {
 
       await deleteCurrentImage();
 
-      expect(showError).toHaveBeenCalledWith("Failed to delete image: Error: Delete failed");
+      expect(showError).toHaveBeenCalledWith("Failed to delete image: Delete failed");
       expect(store.get(isDeletingImageAtom)).toBe(false);
     });
 
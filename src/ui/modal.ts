@@ -9,12 +9,15 @@ import {
   allImagePathsAtom,
   currentIndexAtom,
   sortedImagesAtom,
+  dataFilePathAtom,
+  annotatorAtom,
 } from "../state";
 import { loadImageData } from "../utils/images";
 import { showError } from "./error";
 import { showNotification } from "./notification";
 import { ensureImagePathsArray } from "../utils/state";
 import { invokeTauri, isTauriInvokeAvailable } from "../utils/tauri";
+import { getErrorMessage, isCancelledError } from "../utils/errors";
 
 /**
  * Opens the image viewer modal for the image at the given path, ensuring the image data is available and updating modal UI.
@@ -48,7 +51,11 @@ export async function openModal(imagePath: string): Promise<void> {
       // loadImageData will update the cache atomically, so we don't need to update it here
       dataUrl = await loadImageData(imagePath);
     } catch (error) {
-      showError(`Error loading image: ${error}`);
+      // A cancelled load means another folder was opened, which already reset the modal
+      if (isCancelledError(error)) {
+        return;
+      }
+      showError(`Error loading image: ${getErrorMessage(error)}`);
       // Reset modal on error
       store.set(currentModalImagePathAtom, "");
       return;
@@ -264,7 +271,17 @@ export async function deleteCurrentImage(): Promise<void> {
   store.set(isDeletingImageAtom, true);
   
   try {
-    await invokeTauri("delete_image", { imagePath });
+    // The delete is recorded in the label event log of the data file in use
+    const args: { imagePath: string; dataFilePath?: string; annotator?: string } = { imagePath };
+    const dataFilePath = store.get(dataFilePathAtom);
+    if (dataFilePath) {
+      args.dataFilePath = dataFilePath;
+    }
+    const annotator = store.get(annotatorAtom);
+    if (annotator) {
+      args.annotator = annotator;
+    }
+    await invokeTauri("delete_image", args);
     
     // Remove from loaded images cache
     deleteFromAtomMap(loadedImagesAtom, imagePath);
@@ -318,7 +335,7 @@ export async function deleteCurrentImage(): Promise<void> {
     
     showNotification("Image deleted");
   } catch (error) {
-    showError(`Failed to delete image: ${error}`);
+    showError(`Failed to delete image: ${getErrorMessage(error)}`);
   } finally {
     // Always reset the deletion flag
     store.set(isDeletingImageAtom, false);
@@ -54,20 +54,51 @@ describe("dialog utilities", () => {
       expect(result).toBe("/path/to/file");
     });
 
-    it("should call Tauri dialog.open with directory option", async () => {
-      const mockOpen = vi.fn().mockResolvedValue("/path/to/directory");
+    it("should pick directories in the backend", async () => {
+      const mockOpen = vi.fn();
+      const mockInvoke = vi.fn().mockResolvedValue("/granted/directory");
       (globalThis as any).window = {
         __TAURI__: {
+          core: { invoke: mockInvoke },
           dialog: {
             open: mockOpen,
           },
         },
       };
 
+      const result = await open({ directory: true, multiple: false, title: "Select a folder" });
+
+      expect(mockInvoke).toHaveBeenCalledWith("pick_directory", { title: "Select a folder" });
+      expect(mockOpen).not.toHaveBeenCalled();
+      expect(result).toBe("/granted/directory");
+    });
+
+    it("should start the folder dialog at the default path", async () => {
+      const mockInvoke = vi.fn().mockResolvedValue("/photos");
+      (globalThis as any).window = {
+        __TAURI__: {
+          core: { invoke: mockInvoke },
+        },
+      };
+
+      const result = await open({ directory: true, defaultPath: "/photos" });
+
+      expect(mockInvoke).toHaveBeenCalledWith("pick_directory", { defaultPath: "/photos" });
+      expect(result).toBe("/photos");
+    });
+
+    it("should return null when the folder dialog is cancelled", async () => {
+      const mockInvoke = vi.fn().mockResolvedValue(null);
+      (globalThis as any).window = {
+        __TAURI__: {
+          core: { invoke: mockInvoke },
+        },
+      };
+
       const result = await open({ directory: true });
 
-      expect(mockOpen).toHaveBeenCalledWith({ directory: true });
-      expect(result).toBe("/path/to/directory");
+      expect(mockInvoke).toHaveBeenCalledWith("pick_directory", {});
+      expect(result).toBeNull();
     });
 
     it("should call Tauri dialog.open with multiple files option", async () => {
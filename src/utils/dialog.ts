@@ -1,5 +1,24 @@
-// Dialog is provided via plugin, accessed through window.__TAURI__
-export function open(options?: { directory?: boolean; multiple?: boolean; title?: string }): Promise<string | string[] | null> {
+import { invokeTauri } from "./tauri";
+
+/**
+ * Show the open dialog.
+ *
+ * Folders are picked through the backend's `pick_directory`, which grants the picked folder to the
+ * file commands and returns its canonical path; only one folder can be picked at a time.
+ * `defaultPath` only sets the folder the dialog starts in.
+ */
+export async function open(options?: { directory?: boolean; multiple?: boolean; title?: string; defaultPath?: string }): Promise<string | string[] | null> {
+  if (options?.directory) {
+    const args: { title?: string; defaultPath?: string } = {};
+    if (options.title) {
+      args.title = options.title;
+    }
+    if (options.defaultPath) {
+      args.defaultPath = options.defaultPath;
+    }
+    return invokeTauri<string | null>("pick_directory", args);
+  }
+  // Dialog is provided via plugin, accessed through window.__TAURI__
   if (!window.__TAURI__?.dialog?.open) {
     throw new Error("Dialog API not available");
   }
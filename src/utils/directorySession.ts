@@ -0,0 +1,54 @@
+import { store } from "./jotaiStore";
+import { annotatorAtom, directorySessionAtom } from "../state";
+import { invokeTauri, isTauriInvokeAvailable } from "./tauri";
+import { normalizePath } from "./state";
+import { getFolderRequestId } from "./requests";
+
+/**
+ * Opens a Rust directory session for the folder being browsed, so filtering and sorting send only
+ * the options instead of the full image and label lists.
+ *
+ * The session of the previous folder is closed. No session is opened when the data file is in
+ * another directory; filtering then goes through sort_images as before.
+ *
+ * @param directory - The folder being browsed
+ * @param dataFilePath - The folder's data file path, or "" for the default `.hito.json`
+ */
+export async function openDirectorySession(directory: string, dataFilePath: string): Promise<void> {
+  const previous = store.get(directorySessionAtom);
+  store.set(directorySessionAtom, "");
+  if (!isTauriInvokeAvailable()) {
+    return;
+  }
+  if (previous && previous !== directory) {
+    invokeTauri<boolean>("close_directory_session", { directory: previous }).catch((error) => {
+      console.warn("[openDirectorySession] Failed to close previous session:", error);
+    });
+  }
+
+  const args: { directory: string; filename?: string; annotator?: string; requestId?: string } = { directory };
+  if (dataFilePath) {
+    const path = normalizePath(dataFilePath);
+    const lastSlash = path.lastIndexOf("/");
+    if (lastSlash >= 0 && path.substring(0, lastSlash) !== normalizePath(directory)) {
+      return;
+    }
+    args.filename = path.substring(lastSlash + 1) || undefined;
+  }
+  const annotator = store.get(annotatorAtom);
+  if (annotator) {
+    args.annotator = annotator;
+  }
+  // Listing the folder again runs under its request, so opening another folder cancels it
+  const requestId = getFolderRequestId();
+  if (requestId) {
+    args.requestId = requestId;
+  }
+
+  try {
+    await invokeTauri("open_directory_session", args);
+    store.set(directorySessionAtom, directory);
+  } catch (error) {
+    console.warn("[openDirectorySession] Failed to open session, filtering without one:", error);
+  }
+}
@@ -0,0 +1,28 @@
+import { describe, it, expect } from "vitest";
+import { getErrorMessage, isCancelledError, isHitoError } from "./errors";
+
+describe("getErrorMessage", () => {
+  it("should use the message of a structured command error", () => {
+    const error = { code: "not_found", message: "Image does not exist: /a.jpg", path: "/a.jpg" };
+    expect(isHitoError(error)).toBe(true);
+    expect(getErrorMessage(error)).toBe("Image does not exist: /a.jpg");
+  });
+
+  it("should use the message of an Error", () => {
+    expect(getErrorMessage(new Error("Dialog error"))).toBe("Dialog error");
+  });
+
+  it("should stringify anything else", () => {
+    expect(isHitoError("plain")).toBe(false);
+    expect(getErrorMessage("plain")).toBe("plain");
+    expect(getErrorMessage(null)).toBe("null");
+  });
+});
+
+describe("isCancelledError", () => {
+  it("should only match structured errors with the cancelled code", () => {
+    expect(isCancelledError({ code: "cancelled", message: "Request was cancelled" })).toBe(true);
+    expect(isCancelledError({ code: "io", message: "Disk error" })).toBe(false);
+    expect(isCancelledError(new Error("cancelled"))).toBe(false);
+  });
+});
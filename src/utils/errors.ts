@@ -0,0 +1,26 @@
+import type { HitoError } from "../types";
+
+/**
+ * Checks if a rejected command value is a structured error from the backend.
+ */
+export function isHitoError(error: unknown): error is HitoError {
+  return typeof error === "object" && error !== null && "code" in error && "message" in error;
+}
+
+/**
+ * Checks if a command failed because its request was cancelled (e.g. the user opened another
+ * folder), which callers ignore instead of reporting.
+ */
+export function isCancelledError(error: unknown): boolean {
+  return isHitoError(error) && error.code === "cancelled";
+}
+
+/**
+ * Gets a displayable message from a thrown value or a rejected command.
+ */
+export function getErrorMessage(error: unknown): string {
+  if (error instanceof Error || isHitoError(error)) {
+    return error.message;
+  }
+  return String(error);
+}
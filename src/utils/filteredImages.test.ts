@@ -9,6 +9,7 @@ import {
   filterOptionsAtom,
   suppressCategoryRefilterAtom,
   cachedImageCategoriesForRefilterAtom,
+  directorySessionAtom,
   resetStateAtom,
 } from "../state";
 import { getFilteredAndSortedImages, getFilteredAndSortedImagesSync, getSortedDirectoriesAndImages } from "./filteredImages";
@@ -489,11 +490,13 @@ describe("filteredImages", () => {
 
         const result = await getFilteredAndSortedImages();
 
-        expect(invokeTauri).toHaveBeenCalledWith("sort_images", expect.objectContaining({
-          images: store.get(allImagePathsAtom),
-          sortOption: store.get(sortOptionAtom),
-          sortDirection: store.get(sortDirectionAtom),
-        }));
+        expect(invokeTauri).toHaveBeenCalledWith("sort_images", {
+          request: expect.objectContaining({
+            images: store.get(allImagePathsAtom),
+            sort_option: store.get(sortOptionAtom),
+            sort_direction: store.get(sortDirectionAtom),
+          }),
+        });
         expect(result).toEqual([
           { path: "/test/sorted1.jpg" },
           { path: "/test/sorted2.jpg" },
@@ -512,16 +515,18 @@ describe("filteredImages", () => {
 
         await getFilteredAndSortedImages();
 
-        expect(invokeTauri).toHaveBeenCalledWith("sort_images", expect.objectContaining({
-          filterOptions: {
-            category_id: "cat1",
-            name_pattern: "test",
-            name_operator: "contains",
-            size_operator: "largerThan",
-            size_value: "100",
-            size_value2: null,
-          },
-        }));
+        expect(invokeTauri).toHaveBeenCalledWith("sort_images", {
+          request: expect.objectContaining({
+            filter_options: {
+              category_id: "cat1",
+              name_pattern: "test",
+              name_operator: "contains",
+              size_operator: "largerThan",
+              size_value: "100",
+              size_value2: null,
+            },
+          }),
+        });
       });
 
       it("should pass sizeValue2 when operator is between", async () => {
@@ -540,11 +545,54 @@ describe("filteredImages", () => {
 
         await getFilteredAndSortedImages();
 
-        expect(invokeTauri).toHaveBeenCalledWith("sort_images", expect.objectContaining({
-          filterOptions: expect.objectContaining({
-            size_value2: "200",
+        expect(invokeTauri).toHaveBeenCalledWith("sort_images", {
+          request: expect.objectContaining({
+            filter_options: expect.objectContaining({
+              size_value2: "200",
+            }),
+          }),
+        });
+      });
+
+      it("should query the directory session instead of sending the images", async () => {
+        const { invokeTauri, isTauriInvokeAvailable } = await import("./tauri");
+        vi.mocked(isTauriInvokeAvailable).mockReturnValueOnce(true);
+        vi.mocked(invokeTauri).mockResolvedValueOnce({ total: 1, offset: 0, images: [{ path: "/test/b.jpg" }] });
+
+        store.set(directorySessionAtom, "/test");
+        store.set(allImagePathsAtom, [{ path: "/test/a.jpg" }, { path: "/test/b.jpg" }]);
+        store.set(sortDirectionAtom, "descending");
+
+        const result = await getFilteredAndSortedImages();
+
+        expect(invokeTauri).toHaveBeenCalledWith("query_directory_session", {
+          directory: "/test",
+          query: { sort_option: "name", sort_direction: "descending", filter_options: null },
+        });
+        expect(invokeTauri).not.toHaveBeenCalledWith("sort_images", expect.anything());
+        expect(result).toEqual([{ path: "/test/b.jpg" }]);
+      });
+
+      it("should send the cached labels to sort_images while refiltering is suppressed", async () => {
+        const { invokeTauri, isTauriInvokeAvailable } = await import("./tauri");
+        vi.mocked(isTauriInvokeAvailable).mockReturnValueOnce(true);
+        vi.mocked(invokeTauri).mockResolvedValueOnce([]);
+
+        const cached = new Map<string, CategoryAssignment[]>([
+          ["/test/a.jpg", [{ category_id: "cat1", assigned_at: "2024-01-01T00:00:00Z" }]],
+        ]);
+        store.set(directorySessionAtom, "/test");
+        store.set(allImagePathsAtom, [{ path: "/test/a.jpg" }]);
+        store.set(suppressCategoryRefilterAtom, true);
+        store.set(cachedImageCategoriesForRefilterAtom, cached);
+
+        await getFilteredAndSortedImages();
+
+        expect(invokeTauri).toHaveBeenCalledWith("sort_images", {
+          request: expect.objectContaining({
+            image_categories: Array.from(cached.entries()),
           }),
-        }));
+        });
       });
 
       it("should fallback to JS when Rust fails", async () => {
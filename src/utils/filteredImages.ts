@@ -7,8 +7,10 @@ import {
   imageCategoriesAtom,
   suppressCategoryRefilterAtom,
   cachedImageCategoriesForRefilterAtom,
+  directorySessionAtom,
 } from "../state";
 import { invokeTauri, isTauriInvokeAvailable } from "./tauri";
+import { getFolderRequestId } from "./requests";
 import type { ImagePath, DirectoryPath } from "../types";
 
 /**
@@ -206,8 +208,11 @@ export async function getFilteredAndSortedImages(): Promise<ImagePath[]> {
   let rustSucceeded = false;
   if (isTauriInvokeAvailable()) {
     try {
-      const imageCategories = store.get(imageCategoriesAtom);
-      const imageCategoriesArray = Array.from(imageCategories.entries());
+      // Use cached snapshot if suppressCategoryRefilter is active (defer refiltering)
+      const suppressCategoryRefilter = store.get(suppressCategoryRefilterAtom);
+      const cachedImageCategoriesForRefilter = store.get(cachedImageCategoriesForRefilterAtom);
+      const deferRefilter = suppressCategoryRefilter && cachedImageCategoriesForRefilter !== null;
+      const imageCategories = deferRefilter ? cachedImageCategoriesForRefilter : store.get(imageCategoriesAtom);
       
       // Convert filterOptions to Rust format (camelCase to snake_case)
       const filters = store.get(filterOptionsAtom);
@@ -225,13 +230,33 @@ export async function getFilteredAndSortedImages(): Promise<ImagePath[]> {
         size_value2: hasSizeFilter && filters.sizeOperator === "between" ? filters.sizeValue2 : null,
       } : null;
       
-      images = await invokeTauri<ImagePath[]>("sort_images", {
-        images,
-        sortOption: sortOption,
-        sortDirection: sortDirection,
-        imageCategories: imageCategoriesArray,
-        filterOptions: rustFilterOptions,
-      });
+      // The session holds the folder's images and saved labels, so only the options are sent.
+      // A deferred refilter needs the cached labels, which only sort_images can take.
+      const session = store.get(directorySessionAtom);
+      if (session && !deferRefilter) {
+        // Runs under the folder's request, so opening another folder cancels it
+        const requestId = getFolderRequestId();
+        const page = await invokeTauri<{ total: number; offset: number; images: ImagePath[] }>("query_directory_session", {
+          directory: session,
+          query: {
+            sort_option: sortOption,
+            sort_direction: sortDirection,
+            filter_options: rustFilterOptions,
+          },
+          ...(requestId ? { requestId } : {}),
+        });
+        images = page.images;
+      } else {
+        images = await invokeTauri<ImagePath[]>("sort_images", {
+          request: {
+            images,
+            sort_option: sortOption,
+            sort_direction: sortDirection,
+            image_categories: Array.from(imageCategories.entries()),
+            filter_options: rustFilterOptions,
+          },
+        });
+      }
       rustSucceeded = true;
     } catch (error) {
       console.error("Failed to sort and filter images in Rust:", error);
@@ -188,6 +188,14 @@ describe('image utilities', () => {
       await expect(loadImageData('/test/image.png')).rejects.toThrow('Failed to load image');
     });
 
+    it('should pass cancelled errors through unwrapped', async () => {
+      const { invoke } = window.__TAURI__!.core;
+      const cancelled = { code: 'cancelled', message: 'Request was cancelled' };
+      vi.mocked(invoke).mockRejectedValueOnce(cancelled);
+
+      await expect(loadImageData('/test/image.png')).rejects.toBe(cancelled);
+    });
+
     it('should cache loaded images', async () => {
       const { invoke } = window.__TAURI__!.core;
       const dataUrl = 'data:image/png;base64,testdata';
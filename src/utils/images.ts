@@ -4,24 +4,37 @@ import { createElement } from "./dom";
 import { openModal } from "../ui/modal";
 import { ensureImagePathsArray, getFilename } from "./state";
 import { invokeTauri } from "./tauri";
+import { getErrorMessage, isCancelledError } from "./errors";
+import { getFolderRequestId } from "./requests";
 
 /**
  * Load an image from disk, return its data URL, and cache it in the module's image cache.
  *
  * @param imagePath - The filesystem path of the image to load
  * @returns The image encoded as a data URL string
- * @throws If the Tauri invoke API is unavailable or the image cannot be loaded or decoded
+ * @throws If the Tauri invoke API is unavailable or the image cannot be loaded or decoded; a load
+ * cancelled because another folder was opened rejects with the backend's `cancelled` error as is
  */
 export async function loadImageData(imagePath: string): Promise<string> {
+  const requestId = getFolderRequestId();
   try {
-    const dataUrl = await invokeTauri<string>("load_image", { imagePath });
+    const dataUrl = await invokeTauri<string>("load_image", {
+      imagePath,
+      requestId: requestId ?? undefined,
+    });
     if (!dataUrl || typeof dataUrl !== 'string') {
       throw new Error(`Invalid data URL returned for ${imagePath}`);
     }
-    updateAtomMap(loadedImagesAtom, imagePath, dataUrl);
+    // An image of the previous folder is not cached for the new one
+    if (getFolderRequestId() === requestId) {
+      updateAtomMap(loadedImagesAtom, imagePath, dataUrl);
+    }
     return dataUrl;
   } catch (error) {
-    throw new Error(`Failed to load image: ${error}`);
+    if (isCancelledError(error)) {
+      throw error;
+    }
+    throw new Error(`Failed to load image: ${getErrorMessage(error)}`);
   }
 }
 
@@ -0,0 +1,31 @@
+import { invokeTauri, isTauriInvokeAvailable } from "./tauri";
+
+let folderRequestCount = 0;
+let folderRequestId: string | null = null;
+
+/**
+ * Gets the request ID shared by the scans and image loads of the current folder view.
+ *
+ * @returns The request ID, or null before the first folder is opened
+ */
+export function getFolderRequestId(): string | null {
+  return folderRequestId;
+}
+
+/**
+ * Starts a new folder view and cancels the scans and image loads still running for the previous one.
+ *
+ * @returns The request ID to pass to the commands of the new view
+ */
+export function startFolderRequest(): string {
+  const previous = folderRequestId;
+  folderRequestCount += 1;
+  folderRequestId = `folder-${folderRequestCount}`;
+
+  if (previous && isTauriInvokeAvailable()) {
+    invokeTauri<boolean>("cancel_request", { requestId: previous }).catch((error) => {
+      console.warn("[startFolderRequest] Failed to cancel previous folder requests:", error);
+    });
+  }
+  return folderRequestId;
+}
@@ -1,7 +1,7 @@
 import { describe, it, expect, beforeEach, vi } from "vitest";
 import { store } from "./jotaiStore";
 import { allImagePathsAtom, resetStateAtom } from "../state";
-import { ensureImagePathsArray, normalizePath, getFilename } from "./state";
+import { ensureImagePathsArray, normalizePath, getFilename, isWithinDirectories } from "./state";
 
 describe("state utilities", () => {
   beforeEach(() => {
@@ -95,6 +95,25 @@ describe("state utilities", () => {
     });
   });
 
+  describe("isWithinDirectories", () => {
+    it("should accept the directories themselves and paths inside them", () => {
+      expect(isWithinDirectories("/photos/trip", ["/photos/trip"])).toBe(true);
+      expect(isWithinDirectories("/photos/trip/day1", ["/other", "/photos/trip/"])).toBe(true);
+      expect(isWithinDirectories("/anything", ["/"])).toBe(true);
+    });
+
+    it("should reject parents and siblings with a common prefix", () => {
+      expect(isWithinDirectories("/photos", ["/photos/trip"])).toBe(false);
+      expect(isWithinDirectories("/photos/trip2", ["/photos/trip"])).toBe(false);
+      expect(isWithinDirectories("/photos", [])).toBe(false);
+    });
+
+    it("should ignore Windows verbatim prefixes and separators", () => {
+      expect(isWithinDirectories("C:/Users/test/photos", ["\\\\?\\C:\\Users\\test"])).toBe(true);
+      expect(isWithinDirectories("C:/Users", ["\\\\?\\C:\\Users\\test"])).toBe(false);
+    });
+  });
+
   describe("getFilename", () => {
     it("should extract filename from Unix-style path", () => {
       expect(getFilename("/path/to/image.jpg")).toBe("image.jpg");
@@ -27,6 +27,24 @@ export function normalizePath(path: string): string {
   return path.replace(/\\/g, "/");
 }
 
+/**
+ * Checks whether a path is one of the given directories or lies inside one of them.
+ *
+ * Windows verbatim prefixes (`\\?\`) of canonical paths are ignored.
+ *
+ * @param path - The path to check
+ * @param directories - Directories to check against, e.g. the opened directories
+ * @returns true if the path is inside any of the directories
+ */
+export function isWithinDirectories(path: string, directories: string[]): boolean {
+  const clean = (p: string) => normalizePath(p).replace(/^\/\/\?\//, "").replace(/\/+$/, "");
+  const target = clean(path);
+  return directories.some(directory => {
+    const root = clean(directory);
+    return target === root || target.startsWith(root + "/") || (root === "" && target.startsWith("/"));
+  });
+}
+
 /**
  * Extracts the filename from a path.
  * 
@@ -0,0 +1,119 @@
+import { describe, it, expect, beforeEach, afterEach, vi } from "vitest";
+import { store } from "./jotaiStore";
+import {
+  currentDirectoryAtom,
+  currentModalImagePathAtom,
+  filterOptionsAtom,
+  sortDirectionAtom,
+  sortedImagesAtom,
+  sortOptionAtom,
+  resetStateAtom,
+} from "../state";
+import { restoreLastDirectory, setupViewStatePersistence } from "./viewState";
+
+vi.mock("./tauri", () => ({
+  invokeTauri: vi.fn(),
+  isTauriInvokeAvailable: vi.fn().mockReturnValue(true),
+}));
+
+vi.mock("../core/browse", () => ({
+  browseImages: vi.fn(),
+}));
+
+vi.mock("../ui/modal", () => ({
+  openModal: vi.fn(),
+}));
+
+describe("viewState", () => {
+  beforeEach(() => {
+    vi.clearAllMocks();
+    store.set(resetStateAtom);
+  });
+
+  afterEach(() => {
+    vi.useRealTimers();
+  });
+
+  it("should save sort, filters and the modal image of the current directory once changes settle", async () => {
+    vi.useFakeTimers();
+    const { invokeTauri } = await import("./tauri");
+    vi.mocked(invokeTauri).mockResolvedValue(undefined);
+    const cleanup = setupViewStatePersistence();
+
+    store.set(currentDirectoryAtom, "/photos");
+    store.set(sortOptionAtom, "size");
+    store.set(sortDirectionAtom, "descending");
+    store.set(filterOptionsAtom, { ...store.get(filterOptionsAtom), namePattern: "IMG", nameOperator: "startsWith" });
+    store.set(currentModalImagePathAtom, "/photos/a.jpg");
+    expect(invokeTauri).not.toHaveBeenCalled();
+
+    await vi.runAllTimersAsync();
+    cleanup();
+
+    expect(invokeTauri).toHaveBeenCalledTimes(1);
+    expect(invokeTauri).toHaveBeenCalledWith("save_view_state", {
+      directory: "/photos",
+      viewState: expect.objectContaining({
+        sort_option: "size",
+        sort_direction: "descending",
+        filters: expect.objectContaining({ name_pattern: "IMG", name_operator: "startsWith", category_id: null }),
+        last_image: "/photos/a.jpg",
+      }),
+    });
+  });
+
+  it("should not save before a directory is opened", async () => {
+    vi.useFakeTimers();
+    const { invokeTauri } = await import("./tauri");
+    const cleanup = setupViewStatePersistence();
+
+    store.set(sortOptionAtom, "size");
+    await vi.runAllTimersAsync();
+    cleanup();
+
+    expect(invokeTauri).not.toHaveBeenCalled();
+  });
+
+  it("should reopen the most recent directory as it was left", async () => {
+    const { invokeTauri } = await import("./tauri");
+    const { browseImages } = await import("../core/browse");
+    const { openModal } = await import("../ui/modal");
+    vi.mocked(invokeTauri)
+      .mockResolvedValueOnce(["/photos", "/older"])
+      .mockResolvedValueOnce({
+        sort_option: "random", // Not offered by the grid, so the default is kept
+        sort_direction: "descending",
+        filters: { name_pattern: "IMG", name_operator: "endsWith" },
+        last_image: "/photos/b.jpg",
+      });
+    vi.mocked(browseImages).mockImplementation(async (path: string) => {
+      store.set(currentDirectoryAtom, path);
+      store.set(sortedImagesAtom, [{ path: "/photos/a.jpg" }, { path: "/photos/b.jpg" }]);
+    });
+
+    await restoreLastDirectory();
+
+    expect(invokeTauri).toHaveBeenCalledWith("open_recent_directory", { directory: "/photos" });
+    expect(browseImages).toHaveBeenCalledWith("/photos");
+    expect(store.get(sortOptionAtom)).toBe("name");
+    expect(store.get(sortDirectionAtom)).toBe("descending");
+    expect(store.get(filterOptionsAtom)).toMatchObject({ namePattern: "IMG", nameOperator: "endsWith", categoryId: "" });
+    expect(openModal).toHaveBeenCalledWith("/photos/b.jpg");
+  });
+
+  it("should not replace a folder opened while the last one was being looked up", async () => {
+    const { invokeTauri } = await import("./tauri");
+    const { browseImages } = await import("../core/browse");
+    vi.mocked(invokeTauri)
+      .mockImplementationOnce(async () => {
+        store.set(currentDirectoryAtom, "/dropped");
+        return ["/photos"] as never;
+      })
+      .mockResolvedValueOnce(null);
+
+    await restoreLastDirectory();
+
+    expect(browseImages).not.toHaveBeenCalled();
+    expect(store.get(currentDirectoryAtom)).toBe("/dropped");
+  });
+});
@@ -0,0 +1,231 @@
+import { store } from "./jotaiStore";
+import {
+  allImagePathsAtom,
+  currentDirectoryAtom,
+  currentModalImagePathAtom,
+  filterOptionsAtom,
+  sortDirectionAtom,
+  sortedImagesAtom,
+  sortExplicitlySetAtom,
+  sortOptionAtom,
+} from "../state";
+import { invokeTauri, isTauriInvokeAvailable } from "./tauri";
+import { browseImages } from "../core/browse";
+import { openModal } from "../ui/modal";
+
+const SAVE_DELAY_MS = 500;
+const RESTORE_TIMEOUT_MS = 10000;
+const SORT_OPTIONS = ["name", "dateCreated", "lastCategorized", "size"] as const;
+const NAME_OPERATORS = ["contains", "startsWith", "endsWith", "exact"] as const;
+const SIZE_OPERATORS = ["largerThan", "lessThan", "between"] as const;
+
+/**
+ * How a directory was last viewed, as saved by the `save_view_state` command (snake_case, like
+ * the filter options of sort_images).
+ */
+export interface ViewState {
+  sort_option?: string | null;
+  sort_direction?: string | null;
+  filters?: {
+    category_id?: string | null;
+    name_pattern?: string | null;
+    name_operator?: string | null;
+    size_operator?: string | null;
+    size_value?: string | null;
+    size_value2?: string | null;
+  } | null;
+  last_image?: string | null;
+  scroll_position?: number | null;
+}
+
+let saveTimer: ReturnType<typeof setTimeout> | null = null;
+let restoring = false;
+
+function oneOf<T extends string>(values: readonly T[], value: string | null | undefined, fallback: T): T {
+  return values.find((v) => v === value) ?? fallback;
+}
+
+/**
+ * Gets the view state of the current directory from the sort, filter and modal atoms and the
+ * grid's scroll position.
+ */
+export function currentViewState(): ViewState {
+  const filters = store.get(filterOptionsAtom);
+  return {
+    sort_option: store.get(sortOptionAtom),
+    sort_direction: store.get(sortDirectionAtom),
+    filters: {
+      category_id: filters.categoryId || null,
+      name_pattern: filters.namePattern || null,
+      name_operator: filters.nameOperator,
+      size_operator: filters.sizeOperator,
+      size_value: filters.sizeValue || null,
+      size_value2: filters.sizeValue2 || null,
+    },
+    last_image: store.get(currentModalImagePathAtom) || null,
+    scroll_position: window.scrollY || document.documentElement.scrollTop || 0,
+  };
+}
+
+/**
+ * Saves the view state of the current directory, which also makes it the most recent directory.
+ *
+ * Nothing is saved before a directory is opened or while the last one is being restored.
+ */
+export async function saveViewState(): Promise<void> {
+  const directory = store.get(currentDirectoryAtom);
+  if (!directory || restoring || !isTauriInvokeAvailable()) {
+    return;
+  }
+  try {
+    await invokeTauri("save_view_state", { directory, viewState: currentViewState() });
+  } catch (error) {
+    console.warn("[saveViewState] Failed to save view state:", error);
+  }
+}
+
+/**
+ * Saves the view state once changes have settled, so scrolling or flipping through images does
+ * not write the app config on every step.
+ */
+export function scheduleViewStateSave(): void {
+  if (saveTimer) {
+    clearTimeout(saveTimer);
+  }
+  saveTimer = setTimeout(() => {
+    saveTimer = null;
+    void saveViewState();
+  }, SAVE_DELAY_MS);
+}
+
+/**
+ * Saves the view state whenever the sort, filters, modal image, listing or scroll position change.
+ *
+ * @returns A cleanup function that stops saving
+ */
+export function setupViewStatePersistence(): () => void {
+  const unsubscribes = [
+    store.sub(sortOptionAtom, scheduleViewStateSave),
+    store.sub(sortDirectionAtom, scheduleViewStateSave),
+    store.sub(filterOptionsAtom, scheduleViewStateSave),
+    store.sub(currentModalImagePathAtom, scheduleViewStateSave),
+    // Set once a folder is listed, which adds it to the recent directories
+    store.sub(allImagePathsAtom, scheduleViewStateSave),
+  ];
+  window.addEventListener("scroll", scheduleViewStateSave, { passive: true });
+
+  return () => {
+    unsubscribes.forEach((unsubscribe) => unsubscribe());
+    window.removeEventListener("scroll", scheduleViewStateSave);
+    if (saveTimer) {
+      clearTimeout(saveTimer);
+      saveTimer = null;
+    }
+  };
+}
+
+/**
+ * Applies the sort and filters of a saved view state; unknown values fall back to the defaults.
+ */
+export function applySortAndFilters(viewState: ViewState): void {
+  if (viewState.sort_option) {
+    store.set(sortOptionAtom, oneOf(SORT_OPTIONS, viewState.sort_option, "name"));
+    store.set(sortExplicitlySetAtom, true);
+  }
+  if (viewState.sort_direction) {
+    store.set(sortDirectionAtom, viewState.sort_direction === "descending" ? "descending" : "ascending");
+  }
+  const filters = viewState.filters;
+  if (filters) {
+    store.set(filterOptionsAtom, {
+      categoryId: filters.category_id ?? "",
+      namePattern: filters.name_pattern ?? "",
+      nameOperator: oneOf(NAME_OPERATORS, filters.name_operator, "contains"),
+      sizeOperator: oneOf(SIZE_OPERATORS, filters.size_operator, "largerThan"),
+      sizeValue: filters.size_value ?? "",
+      sizeValue2: filters.size_value2 ?? "",
+    });
+  }
+}
+
+/**
+ * Waits until the grid has sorted the images of `directory`.
+ *
+ * @returns False if another directory was opened or the grid stayed empty
+ */
+function waitForSortedImages(directory: string): Promise<boolean> {
+  return new Promise((resolve) => {
+    const isReady = () => store.get(sortedImagesAtom).length > 0;
+    if (isReady()) {
+      resolve(true);
+      return;
+    }
+    const finish = (ready: boolean) => {
+      unsubscribeSorted();
+      unsubscribeDirectory();
+      clearTimeout(timer);
+      resolve(ready);
+    };
+    const unsubscribeSorted = store.sub(sortedImagesAtom, () => {
+      if (isReady()) {
+        finish(true);
+      }
+    });
+    const unsubscribeDirectory = store.sub(currentDirectoryAtom, () => {
+      if (store.get(currentDirectoryAtom) !== directory) {
+        finish(false);
+      }
+    });
+    const timer = setTimeout(() => finish(false), RESTORE_TIMEOUT_MS);
+  });
+}
+
+/**
+ * Reopens the most recent directory on startup as it was left: sort, filters, scroll position
+ * and the image open in the modal.
+ *
+ * Does nothing if there is no recent directory, it can no longer be opened, or another folder
+ * was opened in the meantime.
+ */
+export async function restoreLastDirectory(): Promise<void> {
+  if (!isTauriInvokeAvailable()) {
+    return;
+  }
+
+  let directory: string | undefined;
+  let viewState: ViewState | null = null;
+  try {
+    const recent = await invokeTauri<string[]>("get_recent_directories");
+    directory = recent[0];
+    if (!directory) {
+      return;
+    }
+    // Opened directories only last for one run, so the recent one is opened again first
+    viewState = await invokeTauri<ViewState | null>("open_recent_directory", { directory });
+  } catch (error) {
+    console.warn("[restoreLastDirectory] Failed to reopen the last directory:", error);
+    return;
+  }
+  if (store.get(currentDirectoryAtom)) {
+    return;
+  }
+
+  restoring = true;
+  try {
+    if (viewState) {
+      applySortAndFilters(viewState);
+    }
+    await browseImages(directory);
+    if (!viewState || store.get(currentDirectoryAtom) !== directory || !(await waitForSortedImages(directory))) {
+      return;
+    }
+    if (viewState.scroll_position) {
+      window.scrollTo(0, viewState.scroll_position);
+    }
+    if (viewState.last_image) {
+      await openModal(viewState.last_image);
+    }
+  } finally {
+    restoring = false;
+  }
+}